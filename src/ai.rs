@@ -0,0 +1,498 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::VecDeque, fmt::Display, time::Duration};
+
+use crate::{
+    PlayerInput,
+    engine::point::{Point, Vector},
+    entities::{snake::Snake, world::World},
+};
+
+/// The moves an AI can make, as tile offsets.
+const MOVES: [(PlayerInput, isize, isize); 4] = [
+    (PlayerInput::Up, 0, -1),
+    (PlayerInput::Down, 0, 1),
+    (PlayerInput::Left, -1, 0),
+    (PlayerInput::Right, 1, 0),
+];
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Difficulty {
+    /// Only sees food that's close by, walks into dead ends and reacts late
+    Easy,
+    /// Checks there's room to move before heading for food
+    #[default]
+    Normal,
+    /// Sees the whole level, follows its tail when food isn't safe to reach
+    /// and reacts instantly
+    Hard,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Easy => write!(f, "easy"),
+            Self::Normal => write!(f, "normal"),
+            Self::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum TrapAvoidance {
+    /// Any move that doesn't crash straight away is fine
+    None,
+    /// Only moves into an area with room for the whole snake
+    CountSpace,
+    /// Counts space, and chases its own tail when the food isn't safe
+    FollowTail,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Skill {
+    /// How many tiles away food can be found by pathfinding.
+    look_ahead: usize,
+    trap_avoidance: TrapAvoidance,
+    /// How long after reaching a tile a decision is made. Slow enough
+    /// reactions turn a tile or more late.
+    reaction_delay: Duration,
+}
+
+impl Difficulty {
+    fn skill(&self) -> Skill {
+        match self {
+            Self::Easy => Skill {
+                look_ahead: 8,
+                trap_avoidance: TrapAvoidance::None,
+                reaction_delay: Duration::from_millis(100),
+            },
+            Self::Normal => Skill {
+                look_ahead: 24,
+                trap_avoidance: TrapAvoidance::CountSpace,
+                reaction_delay: Duration::from_millis(40),
+            },
+            Self::Hard => Skill {
+                look_ahead: usize::MAX,
+                trap_avoidance: TrapAvoidance::FollowTail,
+                reaction_delay: Duration::ZERO,
+            },
+        }
+    }
+}
+
+/// Steers a snake by producing the same `PlayerInput`s a player would, so a
+/// computer controlled snake follows the same movement rules.
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    skill: Skill,
+    last_head: Option<Point>,
+    pending: Option<(PlayerInput, Duration)>,
+}
+
+impl Autopilot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            skill: difficulty.skill(),
+            last_head: None,
+            pending: None,
+        }
+    }
+
+    /// Doesn't look for a path at all, only taking whichever move gets
+    /// closest to the food without crashing on the next tile.
+    pub fn greedy() -> Self {
+        Self {
+            skill: Skill {
+                look_ahead: 0,
+                trap_avoidance: TrapAvoidance::None,
+                reaction_delay: Duration::ZERO,
+            },
+            last_head: None,
+            pending: None,
+        }
+    }
+
+    /// Plans a move each time the snake reaches a new tile, returning it once
+    /// the reaction delay has passed. `player` is the index of the snake being
+    /// steered in `snakes`, and `food` is the food's screen position.
+    pub fn update(
+        &mut self,
+        elapsed: &Duration,
+        world: &World,
+        player: usize,
+        snakes: &[&Snake],
+        food: Point,
+    ) -> Option<PlayerInput> {
+        let head = snakes[player].head();
+        if self.pending.is_none() && self.last_head != Some(head) {
+            self.last_head = Some(head);
+            let input = self.plan(world, player, snakes, food)?;
+            self.pending = Some((input, self.skill.reaction_delay));
+            return self.take_ready(&Duration::ZERO);
+        }
+
+        self.take_ready(elapsed)
+    }
+
+    fn take_ready(&mut self, elapsed: &Duration) -> Option<PlayerInput> {
+        let (input, remaining) = self.pending.as_mut()?;
+        *remaining = remaining.saturating_sub(*elapsed);
+        if !remaining.is_zero() {
+            return None;
+        }
+
+        let input = *input;
+        self.pending = None;
+        Some(input)
+    }
+
+    /// Picks the turn to make from the snake's current tile, or `None` to
+    /// keep going straight.
+    fn plan(
+        &self,
+        world: &World,
+        player: usize,
+        snakes: &[&Snake],
+        food: Point,
+    ) -> Option<PlayerInput> {
+        let grid = Grid::new(world, snakes);
+        let snake = snakes[player];
+        let head = world.to_tile(snake.head())?;
+        let heading = heading_move(snake.heading());
+        let length = snake.size();
+
+        let moves: Vec<_> = MOVES
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &(_, x, y))| Some((index, grid.neighbor(head, x, y)?)))
+            .filter(|&(_, tile)| grid.is_free(tile))
+            .collect();
+
+        let is_safe = |tile: Point| {
+            self.skill.trap_avoidance == TrapAvoidance::None || grid.space(tile, length) >= length
+        };
+
+        let food = world.to_tile(food);
+        let towards_food = food
+            .and_then(|food| grid.first_move(head, food, self.skill.look_ahead))
+            .filter(|&index| moves.iter().any(|&(i, tile)| i == index && is_safe(tile)));
+
+        let towards_tail = match self.skill.trap_avoidance {
+            TrapAvoidance::FollowTail => snake
+                .body()
+                .last()
+                .and_then(|&tail| world.to_tile(tail))
+                .and_then(|tail| grid.first_move(head, tail, usize::MAX))
+                .filter(|&index| moves.iter().any(|&(i, _)| i == index)),
+            _ => None,
+        };
+
+        // Without a good path, head for the most room, then towards the food,
+        // then straight on
+        let fallback = || {
+            moves
+                .iter()
+                .max_by_key(|&&(index, tile)| {
+                    let space = match self.skill.trap_avoidance {
+                        TrapAvoidance::None => 0,
+                        _ => grid.space(tile, grid.size()),
+                    };
+                    let distance = food.map(|food| manhattan(tile, food)).unwrap_or_default();
+                    (space, Reverse(distance), Some(index) == heading)
+                })
+                .map(|&(index, _)| index)
+        };
+
+        let index = towards_food.or(towards_tail).or_else(fallback)?;
+        match Some(index) == heading {
+            true => None,
+            false => Some(MOVES[index].0),
+        }
+    }
+}
+
+/// The index in `MOVES` of the direction a velocity is heading.
+fn heading_move(velocity: Vector) -> Option<usize> {
+    MOVES
+        .iter()
+        .position(|&(_, x, y)| x.signum() == velocity.x.signum() && y == velocity.y)
+}
+
+fn manhattan(a: Point, b: Point) -> usize {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
+
+/// The level's tiles as the AI sees them, with every wall and snake blocking
+/// the way. Tails that will move out of the way on the next tile are free.
+struct Grid {
+    width: usize,
+    height: usize,
+    wrap: bool,
+    blocked: Vec<bool>,
+}
+
+impl Grid {
+    fn new(world: &World, snakes: &[&Snake]) -> Self {
+        let level = world.level();
+        let (width, height) = (level.width(), level.height());
+        let mut blocked: Vec<_> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point::new(x, y)))
+            .map(|tile| level.is_wall(tile))
+            .collect();
+
+        for snake in snakes {
+            let moving_tail = usize::from(snake.size() == snake.length());
+            let parts = snake.body().len() - moving_tail;
+            for tile in snake.body()[..parts]
+                .iter()
+                .filter_map(|&p| world.to_tile(p))
+            {
+                blocked[tile.y * width + tile.x] = true;
+            }
+        }
+
+        Self {
+            width,
+            height,
+            wrap: world.wraps(),
+            blocked,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn is_free(&self, tile: Point) -> bool {
+        !self.blocked[tile.y * self.width + tile.x]
+    }
+
+    fn neighbor(&self, tile: Point, x: isize, y: isize) -> Option<Point> {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let (x, y) = (tile.x as isize + x, tile.y as isize + y);
+        if self.wrap {
+            return Some(Point::new(
+                x.rem_euclid(width) as usize,
+                y.rem_euclid(height) as usize,
+            ));
+        }
+
+        match (0..width).contains(&x) && (0..height).contains(&y) {
+            true => Some(Point::new(x as usize, y as usize)),
+            false => None,
+        }
+    }
+
+    /// The first move of the shortest path from `from` to `goal` that's at
+    /// most `max_depth` tiles long. The goal doesn't need to be free.
+    fn first_move(&self, from: Point, goal: Point, max_depth: usize) -> Option<usize> {
+        let mut first_moves = vec![None; self.size()];
+        let mut queue = VecDeque::from([(from, None, 0)]);
+
+        while let Some((tile, first, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+
+            for (index, &(_, x, y)) in MOVES.iter().enumerate() {
+                let Some(next) = self.neighbor(tile, x, y) else {
+                    continue;
+                };
+
+                let first = first.or(Some(index));
+                if next == goal {
+                    return first;
+                }
+
+                let visited = &mut first_moves[next.y * self.width + next.x];
+                if next == from || visited.is_some() || !self.is_free(next) {
+                    continue;
+                }
+
+                *visited = first;
+                queue.push_back((next, first, depth + 1));
+            }
+        }
+
+        None
+    }
+
+    /// Counts the free tiles reachable from `from`, stopping at `limit`.
+    fn space(&self, from: Point, limit: usize) -> usize {
+        let mut seen = vec![false; self.size()];
+        let mut queue = VecDeque::from([from]);
+        let mut count = 0;
+        seen[from.y * self.width + from.x] = true;
+
+        while let Some(tile) = queue.pop_front() {
+            count += 1;
+            if count >= limit {
+                break;
+            }
+
+            for &(_, x, y) in &MOVES {
+                let Some(next) = self.neighbor(tile, x, y) else {
+                    continue;
+                };
+
+                let seen = &mut seen[next.y * self.width + next.x];
+                if !*seen && self.is_free(next) {
+                    *seen = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GameConfig, config::SnakeConfig, engine::traits::Entity, test_support::test_config,
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                speed: 10.0,
+                ..test_config().snake
+            },
+            show_border: false,
+            ..test_config()
+        }
+    }
+
+    fn create_world(level: &str) -> World {
+        let config = GameConfig {
+            level: Some(level.parse().unwrap()),
+            ..config()
+        };
+
+        World::new(&config, Point::new(0, 0))
+    }
+
+    fn plan(
+        difficulty: Difficulty,
+        world: &World,
+        snake: &Snake,
+        food: Point,
+    ) -> Option<PlayerInput> {
+        Autopilot::new(difficulty).plan(world, 0, &[snake], world.to_position(food))
+    }
+
+    #[test]
+    fn it_turns_towards_food() {
+        let world = create_world("---\n......\n..S...\n......\n......");
+        let snake = world.create_snake();
+
+        assert_eq!(
+            plan(Difficulty::Hard, &world, &snake, Point::new(2, 3)),
+            Some(PlayerInput::Down)
+        );
+        assert_eq!(
+            plan(Difficulty::Hard, &world, &snake, Point::new(5, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn it_only_sees_food_within_its_look_ahead() {
+        let world = create_world(&format!("---\n..S{}", ".".repeat(30)));
+        let snake = world.create_snake();
+        let grid = Grid::new(&world, &[&snake]);
+        let head = world.to_tile(snake.head()).unwrap();
+        let food = Point::new(32, 0);
+
+        let easy = Difficulty::Easy.skill().look_ahead;
+        let hard = Difficulty::Hard.skill().look_ahead;
+        assert_eq!(grid.first_move(head, food, easy), None);
+        assert_eq!(grid.first_move(head, food, hard), Some(3));
+    }
+
+    #[test]
+    fn it_avoids_dead_ends_unless_easy() {
+        // The food sits in a pocket too small for the snake to turn around in
+        let world = create_world(
+            "---\n\
+             ..........\n\
+             ..S.......\n\
+             .#.#......\n\
+             .#.#......\n\
+             .###......",
+        );
+        let snake = world.create_snake();
+        let food = Point::new(2, 3);
+
+        assert_eq!(
+            plan(Difficulty::Easy, &world, &snake, food),
+            Some(PlayerInput::Down)
+        );
+        assert_ne!(
+            plan(Difficulty::Normal, &world, &snake, food),
+            Some(PlayerInput::Down)
+        );
+        assert_ne!(
+            plan(Difficulty::Hard, &world, &snake, food),
+            Some(PlayerInput::Down)
+        );
+    }
+
+    #[test]
+    fn it_waits_for_its_reaction_delay() {
+        let world = create_world("---\n......\n..S...\n......\n......");
+        let snake = world.create_snake();
+        let food = world.to_position(Point::new(2, 3));
+        let mut autopilot = Autopilot::new(Difficulty::Easy);
+
+        let step = Difficulty::Easy.skill().reaction_delay;
+        assert_eq!(autopilot.update(&step, &world, 0, &[&snake], food), None);
+        assert_eq!(
+            autopilot.update(&step, &world, 0, &[&snake], food),
+            Some(PlayerInput::Down)
+        );
+    }
+
+    #[test]
+    fn it_survives_in_a_level_on_hard() {
+        let config = GameConfig {
+            level: Some(crate::level::Level::builtin("pillars").unwrap()),
+            ..config()
+        };
+
+        let world = World::new(&config, Point::new(0, 0));
+        let mut snake = world.create_snake();
+        let mut autopilot = Autopilot::new(Difficulty::Hard);
+        let mut food = world.get_random_position() * Point::new(2, 1);
+        let step = Duration::from_millis(101);
+        let mut eaten = 0;
+
+        for _ in 0..2000 {
+            if eaten == 30 {
+                break;
+            }
+
+            if let Some(input) = autopilot.update(&step, &world, 0, &[&snake], food) {
+                snake.process_input(&input);
+            }
+
+            snake.update(&step);
+            assert!(!world.detect_collision(snake.head()));
+            assert!(!snake.detect_self_collision());
+
+            if snake.detect_head_collision(food) {
+                snake.grow(1);
+                eaten += 1;
+                food = world.get_random_position() * Point::new(2, 1);
+                while snake.detect_collision(food) {
+                    food = world.get_random_position() * Point::new(2, 1);
+                }
+            }
+        }
+
+        assert_eq!(eaten, 30);
+    }
+}
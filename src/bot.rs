@@ -0,0 +1,358 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    PlayerInput,
+    engine::point::Point,
+    entities::{snake::Snake, world::World},
+    level::Direction,
+};
+
+/// Sent with every state so bots can check they understand it.
+pub const BOT_PROTOCOL_VERSION: u32 = 1;
+
+/// How long a bot has to reply to each state unless `--bot-timeout` is given.
+pub const DEFAULT_BOT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A tile as `[x, y]`, counting from the top left of the level.
+pub type Tile = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotSnake {
+    /// Every tile of the snake, starting with its head.
+    pub body: Vec<Tile>,
+    pub direction: Direction,
+}
+
+impl BotSnake {
+    /// Describes a snake in the world's tiles.
+    pub fn new(world: &World, snake: &Snake) -> Self {
+        Self {
+            body: snake
+                .body()
+                .iter()
+                .filter_map(|&part| world.to_tile(part))
+                .map(|tile| (tile.x, tile.y))
+                .collect(),
+            direction: Direction::from_velocity(snake.heading()),
+        }
+    }
+}
+
+/// What a bot is sent each time its snake reaches a new tile, as one line of
+/// JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotState {
+    pub version: u32,
+    pub tick: u64,
+    pub width: usize,
+    pub height: usize,
+    /// True if leaving one edge comes back on the opposite edge.
+    pub wrap: bool,
+    pub walls: Vec<Tile>,
+    pub snakes: Vec<BotSnake>,
+    /// The index in `snakes` of the bot's own snake.
+    pub you: usize,
+    pub food: Option<Tile>,
+    pub score: u32,
+}
+
+impl BotState {
+    /// Describes the world in tiles. `food` is the food's screen position.
+    pub fn new(
+        world: &World,
+        snakes: &[&Snake],
+        you: usize,
+        food: Point,
+        score: u32,
+        tick: u64,
+    ) -> Self {
+        let level = world.level();
+        let tile = |position: Point| world.to_tile(position).map(|tile| (tile.x, tile.y));
+
+        Self {
+            version: BOT_PROTOCOL_VERSION,
+            tick,
+            width: level.width(),
+            height: level.height(),
+            wrap: world.wraps(),
+            walls: level.walls().map(|wall| (wall.x, wall.y)).collect(),
+            snakes: snakes
+                .iter()
+                .map(|snake| BotSnake::new(world, snake))
+                .collect(),
+            you,
+            food: tile(food),
+            score,
+        }
+    }
+}
+
+/// A bot's answer to a state. A missing or `null` move keeps going straight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotReply {
+    #[serde(rename = "move")]
+    pub direction: Option<Direction>,
+}
+
+/// An external program that steers a snake, started with the system shell.
+/// It reads one `BotState` per line on stdin and answers each with one
+/// `BotReply` line on stdout, such as `{"move": "up"}`. Anything it writes to
+/// stderr is discarded.
+#[derive(Debug)]
+pub struct Bot {
+    command: String,
+    timeout: Duration,
+    process: Option<BotProcess>,
+    last_head: Option<Point>,
+}
+
+#[derive(Debug)]
+struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<String>,
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        // The bot may already have exited, in which case there's nothing to
+        // clean up
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Bot {
+    /// The bot isn't started until it's first asked for a move.
+    pub fn new(command: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            command: command.into(),
+            timeout,
+            process: None,
+            last_head: None,
+        }
+    }
+
+    /// Asks the bot for a move each time the snake at `you` reaches a new
+    /// tile, returning the turn to make, if any.
+    pub fn update(
+        &mut self,
+        world: &World,
+        snakes: &[&Snake],
+        you: usize,
+        food: Point,
+        score: u32,
+        tick: u64,
+    ) -> Result<Option<PlayerInput>> {
+        let head = snakes[you].head();
+        if self.last_head == Some(head) {
+            return Ok(None);
+        }
+
+        self.last_head = Some(head);
+        let state = BotState::new(world, snakes, you, food, score, tick);
+        let heading = state.snakes[you].direction;
+
+        Ok(match self.next_move(&state)? {
+            Some(direction) if direction != heading => Some(input_for(direction)),
+            _ => None,
+        })
+    }
+
+    /// Sends `state` to the bot and waits for its reply. Replies that can't
+    /// be parsed, take longer than the timeout or turn the snake back on
+    /// itself are errors.
+    pub fn next_move(&mut self, state: &BotState) -> Result<Option<Direction>> {
+        let timeout = self.timeout;
+        let process = self.process()?;
+
+        let line = serde_json::to_string(state)?;
+        writeln!(process.stdin, "{line}")
+            .and_then(|_| process.stdin.flush())
+            .map_err(|_| process.exit_error())?;
+
+        let reply = match process.replies.recv_timeout(timeout) {
+            Ok(reply) => reply,
+            Err(RecvTimeoutError::Timeout) => {
+                bail!("Bot didn't reply within {}ms", timeout.as_millis())
+            }
+            Err(RecvTimeoutError::Disconnected) => return Err(process.exit_error()),
+        };
+
+        let reply: BotReply = serde_json::from_str(&reply)
+            .with_context(|| format!("Bot sent a reply that isn't a move: {reply}"))?;
+
+        let heading = state.snakes.get(state.you).map(|snake| snake.direction);
+        match reply.direction {
+            Some(direction) if Some(direction.opposite()) == heading => {
+                bail!(
+                    "Bot tried to turn {direction} while heading {}",
+                    direction.opposite()
+                )
+            }
+            direction => Ok(direction),
+        }
+    }
+
+    fn process(&mut self) -> Result<&mut BotProcess> {
+        if self.process.is_none() {
+            self.process = Some(self.spawn()?);
+        }
+
+        self.process
+            .as_mut()
+            .ok_or_else(|| anyhow!("Bot isn't running"))
+    }
+
+    fn spawn(&self) -> Result<BotProcess> {
+        let (shell, flag) = match cfg!(windows) {
+            true => ("cmd", "/C"),
+            false => ("sh", "-c"),
+        };
+
+        let mut child = Command::new(shell)
+            .args([flag, &self.command])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start bot {}", self.command))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open the bot's input"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Failed to open the bot's output"))?;
+
+        // Lines are read on their own thread so waiting for a reply can time
+        // out. The thread ends when the bot closes its output.
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(BotProcess {
+            child,
+            stdin,
+            replies,
+        })
+    }
+}
+
+impl BotProcess {
+    fn exit_error(&mut self) -> anyhow::Error {
+        match self.child.try_wait() {
+            Ok(Some(status)) => anyhow!("Bot exited with {status}"),
+            _ => anyhow!("Bot stopped reading moves"),
+        }
+    }
+}
+
+fn input_for(direction: Direction) -> PlayerInput {
+    match direction {
+        Direction::Up => PlayerInput::Up,
+        Direction::Down => PlayerInput::Down,
+        Direction::Left => PlayerInput::Left,
+        Direction::Right => PlayerInput::Right,
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    fn state() -> BotState {
+        BotState {
+            version: BOT_PROTOCOL_VERSION,
+            tick: 0,
+            width: 10,
+            height: 5,
+            wrap: false,
+            walls: vec![(0, 0)],
+            snakes: vec![BotSnake {
+                body: vec![(3, 1), (2, 1), (1, 1)],
+                direction: Direction::Right,
+            }],
+            you: 0,
+            food: Some((5, 3)),
+            score: 0,
+        }
+    }
+
+    /// A bot that answers every line with `reply`.
+    fn replying(reply: &str) -> Bot {
+        Bot::new(format!("while read line; do echo '{reply}'; done"), TIMEOUT)
+    }
+
+    #[test]
+    fn it_reads_moves_from_the_bot() {
+        let mut bot = replying(r#"{"move": "down"}"#);
+        assert_eq!(bot.next_move(&state()).unwrap(), Some(Direction::Down));
+        assert_eq!(bot.next_move(&state()).unwrap(), Some(Direction::Down));
+
+        let mut bot = replying(r#"{"move": null}"#);
+        assert_eq!(bot.next_move(&state()).unwrap(), None);
+    }
+
+    #[test]
+    fn it_sends_the_state_as_a_json_line() {
+        // Echoing the state back isn't a move, so it ends up in the error
+        let mut bot = Bot::new("head -n 1", TIMEOUT);
+        let error = bot.next_move(&state()).unwrap_err().to_string();
+
+        assert!(error.contains(r#""width":10,"height":5"#), "{error}");
+        assert!(error.contains(r#""body":[[3,1],[2,1],[1,1]],"direction":"right""#));
+        assert!(error.contains(r#""food":[5,3]"#));
+    }
+
+    #[test]
+    fn it_rejects_malformed_replies() {
+        let mut bot = replying("go up");
+        let error = bot.next_move(&state()).unwrap_err();
+        assert!(error.to_string().contains("isn't a move"));
+
+        let mut bot = replying(r#"{"move": "sideways"}"#);
+        assert!(bot.next_move(&state()).is_err());
+    }
+
+    #[test]
+    fn it_rejects_turning_back_on_itself() {
+        let mut bot = replying(r#"{"move": "left"}"#);
+        let error = bot.next_move(&state()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Bot tried to turn left while heading right"
+        );
+    }
+
+    #[test]
+    fn it_times_out_slow_bots() {
+        let mut bot = Bot::new("sleep 5", Duration::from_millis(50));
+        let error = bot.next_move(&state()).unwrap_err();
+        assert_eq!(error.to_string(), "Bot didn't reply within 50ms");
+    }
+
+    #[test]
+    fn it_fails_when_the_bot_exits() {
+        let mut bot = Bot::new("exit 3", TIMEOUT);
+        assert!(bot.next_move(&state()).is_err());
+    }
+}
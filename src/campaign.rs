@@ -0,0 +1,92 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::files;
+
+const PROGRESS_FILE_NAME: &str = "campaign.json";
+const PROGRESS_DESCRIPTION: &str = "campaign progress";
+
+/// How far through the campaign the player has made it. The level after the
+/// last one cleared is unlocked, along with every level before it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    cleared: usize,
+}
+
+impl CampaignProgress {
+    /// The progress file in the user's data directory, e.g.
+    /// `$XDG_DATA_HOME/snake/campaign.json` on Linux.
+    pub fn default_path() -> Result<PathBuf> {
+        files::data_file(PROGRESS_FILE_NAME)
+    }
+
+    /// Reads the progress, treating a missing file as a new campaign.
+    pub fn load(path: &Path) -> Result<Self> {
+        files::load_json(path, PROGRESS_DESCRIPTION)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        files::save_json(path, PROGRESS_DESCRIPTION, self)
+    }
+
+    /// The zero based index of the furthest level that can be played.
+    pub fn highest_unlocked(&self) -> usize {
+        self.cleared
+    }
+
+    pub fn is_unlocked(&self, level_index: usize) -> bool {
+        level_index <= self.cleared
+    }
+
+    /// Marks the level as cleared, unlocking the one after it. Returns true
+    /// if that's a level that wasn't unlocked before.
+    pub fn clear(&mut self, level_index: usize) -> bool {
+        let unlocked = level_index + 1 > self.cleared;
+        self.cleared = self.cleared.max(level_index + 1);
+        unlocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn it_unlocks_the_level_after_the_one_cleared() {
+        let mut progress = CampaignProgress::default();
+        assert!(progress.is_unlocked(0));
+        assert!(!progress.is_unlocked(1));
+
+        assert!(progress.clear(0));
+        assert!(progress.is_unlocked(1));
+        assert!(!progress.is_unlocked(2));
+
+        assert!(!progress.clear(0));
+        assert_eq!(progress.highest_unlocked(), 1);
+    }
+
+    #[test]
+    fn it_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!(
+            "snake-{}-campaign/{PROGRESS_FILE_NAME}",
+            std::process::id()
+        ));
+
+        assert_eq!(
+            CampaignProgress::load(&path).unwrap(),
+            CampaignProgress::default()
+        );
+
+        let mut progress = CampaignProgress::default();
+        progress.clear(2);
+        progress.save(&path).unwrap();
+
+        let loaded = CampaignProgress::load(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, progress);
+    }
+}
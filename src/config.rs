@@ -1,37 +1,289 @@
-use crate::{CommandOptions, SnakeStyle};
-
-#[derive(Debug, Clone)]
-pub struct GameConfig {
-    pub snake: SnakeConfig,
-    pub rows: usize,
-    pub columns: usize,
-    pub show_frame_rate: bool,
-    pub show_border: bool,
-    pub frame_rate: u8,
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub use snake::game::{GameConfig, SnakeConfig};
+use snake::game::{MIN_COLUMNS, MIN_ROWS};
+
+use crate::{
+    CommandOptions, GameMode, SnakeStyle,
+    curve::{CustomCurves, SpeedCurve},
+    entities::food::SpawnTable,
+    files,
+    level::Level,
+};
+
+/// A `GameConfig` that can be changed while the game is running, such as from
+/// the settings scene, and read when new scenes are created.
+pub type SharedConfig = Rc<RefCell<GameConfig>>;
+
+const DEFAULT_SPEED: f32 = 15.0;
+const DEFAULT_GROW_RATE: usize = 2;
+const DEFAULT_FRAME_RATE: u8 = 15;
+const DEFAULT_TURN_QUEUE_DEPTH: usize = 3;
+const CONFIG_FILE_NAME: &str = "config.toml";
+const CONFIG_FILE_DESCRIPTION: &str = "config file";
+
+/// Builds the game's config from the command line options, falling back to
+/// the values in the config file and then the defaults. A level's speed is
+/// used over the config file's, but not over `--speed`. Fails if the speed
+/// curve asked for doesn't exist.
+pub fn game_config(
+    command_options: CommandOptions,
+    config_file: ConfigFile,
+    level: Option<Level>,
+    (columns, rows): (u16, u16),
+) -> Result<GameConfig> {
+    let curve = command_options
+        .curve
+        .as_deref()
+        .or(config_file.curve.as_deref())
+        .map(|name| SpeedCurve::find(name, config_file.curves.as_ref()))
+        .transpose()?
+        .flatten();
+
+    Ok(GameConfig {
+        snake: SnakeConfig {
+            grow_rate: command_options
+                .grow_rate
+                .or(config_file.grow_rate)
+                .unwrap_or(DEFAULT_GROW_RATE),
+            speed: command_options
+                .speed
+                .or(level.as_ref().and_then(|level| level.speed))
+                .or(config_file.speed)
+                .unwrap_or(DEFAULT_SPEED),
+            size: 6,
+            turn_queue_depth: command_options
+                .turn_queue
+                .or(config_file.turn_queue)
+                .unwrap_or(DEFAULT_TURN_QUEUE_DEPTH),
+            style: command_options
+                .snake_style
+                .or(config_file.snake_style)
+                .unwrap_or(SnakeStyle::Green),
+        },
+        columns: (columns as usize).max(MIN_COLUMNS),
+        rows: (rows as usize).max(MIN_ROWS),
+        show_frame_rate: command_options
+            .show_frame_rate
+            .or(config_file.show_frame_rate)
+            .unwrap_or_default(),
+        show_border: command_options
+            .show_border
+            .or(config_file.show_border)
+            .unwrap_or_default(),
+        frame_rate: command_options
+            .frame_rate
+            .or(config_file.frame_rate)
+            .unwrap_or(DEFAULT_FRAME_RATE),
+        mode: command_options
+            .mode
+            .or(config_file.mode)
+            .unwrap_or_default(),
+        seed: command_options.seed,
+        level,
+        food: command_options
+            .power_ups
+            .or(config_file.power_ups)
+            .unwrap_or_default()
+            .then(|| config_file.food.unwrap_or_default()),
+        curve,
+    })
+}
+
+/// The settings that are saved between runs. Every key is optional so the
+/// file only needs to list the settings that differ from the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub speed: Option<f32>,
+    pub grow_rate: Option<usize>,
+    pub snake_style: Option<SnakeStyle>,
+    pub frame_rate: Option<u8>,
+    pub show_frame_rate: Option<bool>,
+    pub show_border: Option<bool>,
+    pub mode: Option<GameMode>,
+    pub turn_queue: Option<usize>,
+    pub power_ups: Option<bool>,
+    pub curve: Option<String>,
+    /// The weights to place each kind of food with when power-ups are on.
+    pub food: Option<SpawnTable>,
+    /// Speed curves to pick from by name, along with the built-in ones.
+    pub curves: Option<CustomCurves>,
 }
 
-impl GameConfig {
-    pub fn new(command_options: CommandOptions, (columns, rows): (u16, u16)) -> Self {
-        Self {
-            snake: SnakeConfig {
-                grow_rate: command_options.grow_rate,
-                speed: command_options.speed,
-                size: 6,
-                style: command_options.snake_style,
-            },
-            columns: columns as usize,
-            rows: rows as usize,
-            show_frame_rate: command_options.show_frame_rate,
-            show_border: command_options.show_border,
-            frame_rate: command_options.frame_rate,
+impl ConfigFile {
+    /// The config file in the user's config directory, e.g.
+    /// `$XDG_CONFIG_HOME/snake/config.toml` on Linux.
+    pub fn default_path() -> Result<PathBuf> {
+        files::config_file(CONFIG_FILE_NAME)
+    }
+
+    /// Reads the config file, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        let Some(content) = files::read_optional(path, CONFIG_FILE_DESCRIPTION)? else {
+            return Ok(Self::default());
+        };
+
+        let config_file: Self = toml::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        config_file
+            .validate()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        Ok(config_file)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = toml::to_string(self)?;
+        files::write(path, CONFIG_FILE_DESCRIPTION, &content)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(speed) = self.speed
+            && !(speed.is_finite() && speed > 0.0)
+        {
+            bail!("speed must be greater than 0, got {speed}");
+        }
+
+        if self.frame_rate == Some(0) {
+            bail!("frame-rate must be greater than 0");
+        }
+
+        if self.turn_queue == Some(0) {
+            bail!("turn-queue must be greater than 0");
+        }
+
+        if let Some(food) = &self.food
+            && food.total_weight() == 0
+        {
+            bail!("food must give at least one kind a weight greater than 0");
+        }
+
+        if let Some(curves) = &self.curves {
+            SpeedCurve::validate(curves)?;
+        }
+
+        if let Some(curve) = &self.curve {
+            SpeedCurve::find(curve, self.curves.as_ref())?;
         }
+
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct SnakeConfig {
-    pub grow_rate: usize,
-    pub speed: f32,
-    pub size: usize,
-    pub style: SnakeStyle,
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use std::fs;
+
+    use super::*;
+
+    fn parse(config_file: &str) -> Result<ConfigFile> {
+        let config_file: ConfigFile = toml::from_str(config_file)?;
+        config_file.validate()?;
+        Ok(config_file)
+    }
+
+    #[test]
+    fn it_prefers_command_line_options_over_the_file() {
+        let config_file = parse("speed = 10.0\ngrow-rate = 4\nshow-border = true").unwrap();
+        let command_options =
+            CommandOptions::parse_from(["snake", "--speed", "20", "--show-border=false"]);
+
+        let config = game_config(command_options, config_file, None, (80, 24)).unwrap();
+
+        assert_eq!(config.snake.speed, 20.0);
+        assert_eq!(config.snake.grow_rate, 4);
+        assert!(!config.show_border);
+        assert_eq!(config.frame_rate, DEFAULT_FRAME_RATE);
+    }
+
+    #[test]
+    fn it_rejects_unknown_keys() {
+        let error = parse("sped = 10.0").unwrap_err();
+        assert!(error.to_string().contains("unknown field `sped`"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_values() {
+        assert!(parse("snake-style = \"purple\"").is_err());
+        assert!(parse("speed = -1.0").is_err());
+        assert!(parse("frame-rate = 0").is_err());
+        assert!(parse("[food]\napple = 0").is_err());
+        assert!(parse("[food]\npear = 1").is_err());
+        assert!(parse("curve = \"stiff\"").is_err());
+    }
+
+    #[test]
+    fn it_accepts_food_weights_that_add_up_past_one_weight() {
+        assert!(parse("[food]\napple = 4000000000\nbonus = 4000000000").is_ok());
+    }
+
+    #[test]
+    fn it_picks_curves_from_the_file_by_name() {
+        let config_file = parse(
+            "curve = \"sprint\"\n[curves.sprint]\nby = \"time\"\nevery = 10\nstep = 2.0\nmax-level = 3",
+        )
+        .unwrap();
+
+        let config = game_config(
+            CommandOptions::parse_from(["snake"]),
+            config_file.clone(),
+            None,
+            (80, 24),
+        )
+        .unwrap();
+        assert_eq!(config.curve.unwrap().name, "sprint");
+
+        let command_options = CommandOptions::parse_from(["snake", "--curve", "steady"]);
+        let config = game_config(command_options, config_file.clone(), None, (80, 24)).unwrap();
+        assert_eq!(config.curve, None);
+
+        let command_options = CommandOptions::parse_from(["snake", "--curve", "stiff"]);
+        assert!(game_config(command_options, config_file, None, (80, 24)).is_err());
+    }
+
+    #[test]
+    fn it_only_places_power_ups_when_turned_on() {
+        let config_file = parse("[food]\napple = 1\nghost = 1").unwrap();
+        let config = game_config(
+            CommandOptions::parse_from(["snake"]),
+            config_file.clone(),
+            None,
+            (80, 24),
+        )
+        .unwrap();
+        assert_eq!(config.food, None);
+
+        let command_options = CommandOptions::parse_from(["snake", "--power-ups"]);
+        let config = game_config(command_options, config_file.clone(), None, (80, 24)).unwrap();
+        assert_eq!(config.food, config_file.food);
+    }
+
+    #[test]
+    fn it_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!(
+            "snake-{}-config/{CONFIG_FILE_NAME}",
+            std::process::id()
+        ));
+
+        let config_file = ConfigFile {
+            speed: Some(12.0),
+            snake_style: Some(SnakeStyle::Flash),
+            ..Default::default()
+        };
+
+        config_file.save(&path).unwrap();
+        let loaded = ConfigFile::load(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, config_file);
+    }
 }
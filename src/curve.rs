@@ -0,0 +1,208 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+/// The curve that keeps the snake at the same speed for the whole game.
+pub const STEADY: &str = "steady";
+
+/// The built-in curves, as `(name, progress, every, step, max level)`.
+const BUILTIN_CURVES: [(&str, Progress, u32, f32, u32); 3] = [
+    ("gentle", Progress::Score, 5, 1.0, 10),
+    ("steep", Progress::Score, 2, 1.5, 15),
+    ("marathon", Progress::Time, 30, 1.0, 10),
+];
+
+/// What moves the snake up to the next speed level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Progress {
+    /// Points scored.
+    Score,
+    /// Seconds played, not counting time spent paused.
+    Time,
+}
+
+/// How a curve raises the speed, as written in the config file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CurveRule {
+    pub by: Progress,
+    /// The points or seconds between speed levels.
+    pub every: u32,
+    /// The tiles per second added at each speed level.
+    pub step: f32,
+    /// The speed level the snake stops getting faster at.
+    pub max_level: u32,
+}
+
+impl CurveRule {
+    fn validate(&self) -> Result<()> {
+        if self.every == 0 {
+            bail!("every must be greater than 0");
+        }
+
+        if !(self.step.is_finite() && self.step >= 0.0) {
+            bail!("step must be 0 or more, got {}", self.step);
+        }
+
+        Ok(())
+    }
+}
+
+/// Curves defined in the config file, by name.
+pub type CustomCurves = BTreeMap<String, CurveRule>;
+
+/// Speeds the snake up as the game goes on, a level at a time, until it
+/// reaches the curve's top speed level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedCurve {
+    pub name: String,
+    #[serde(flatten)]
+    pub rule: CurveRule,
+}
+
+impl SpeedCurve {
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_CURVES
+            .iter()
+            .find(|(builtin, ..)| *builtin == name)
+            .map(|&(name, by, every, step, max_level)| Self {
+                name: name.to_string(),
+                rule: CurveRule {
+                    by,
+                    every,
+                    step,
+                    max_level,
+                },
+            })
+    }
+
+    /// The steady curve followed by every built-in curve.
+    pub fn builtin_names() -> Vec<&'static str> {
+        [STEADY]
+            .into_iter()
+            .chain(BUILTIN_CURVES.iter().map(|(name, ..)| *name))
+            .collect()
+    }
+
+    /// The names of the built-in curves followed by the ones in `custom`.
+    pub fn names(custom: Option<&CustomCurves>) -> Vec<&str> {
+        let mut names = Self::builtin_names();
+        names.extend(
+            custom
+                .into_iter()
+                .flat_map(|custom| custom.keys().map(String::as_str)),
+        );
+
+        names
+    }
+
+    /// Finds the curve called `name` in `custom`, then in the built-in
+    /// curves. The steady curve is `None`, since it never changes the speed.
+    pub fn find(name: &str, custom: Option<&CustomCurves>) -> Result<Option<Self>> {
+        if name == STEADY {
+            return Ok(None);
+        }
+
+        if let Some(rule) = custom.and_then(|custom| custom.get(name)) {
+            return Ok(Some(Self {
+                name: name.to_string(),
+                rule: rule.clone(),
+            }));
+        }
+
+        match Self::builtin(name) {
+            Some(curve) => Ok(Some(curve)),
+            None => bail!(
+                "No speed curve {name}, expected one of {}",
+                Self::names(custom).join(", ")
+            ),
+        }
+    }
+
+    /// Checks every curve in `custom` can be played.
+    pub fn validate(custom: &CustomCurves) -> Result<()> {
+        for (name, rule) in custom {
+            if name == STEADY || Self::builtin(name).is_some() {
+                bail!("curves.{name} has the same name as a built-in curve");
+            }
+
+            rule.validate()
+                .map_err(|error| error.context(format!("curves.{name} is invalid")))?;
+        }
+
+        Ok(())
+    }
+
+    /// The speed level reached, starting from 0, after scoring `score`
+    /// points in `play_time`.
+    pub fn level(&self, score: u32, play_time: Duration) -> u32 {
+        let progress = match self.rule.by {
+            Progress::Score => score,
+            Progress::Time => play_time.as_secs() as u32,
+        };
+
+        (progress / self.rule.every).min(self.rule.max_level)
+    }
+
+    /// The snake's speed at `level` when it started at `base_speed`.
+    pub fn speed(&self, base_speed: f32, level: u32) -> f32 {
+        base_speed + self.rule.step * level as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_raises_the_speed_up_to_the_max_level() {
+        let curve = SpeedCurve::builtin("gentle").unwrap();
+
+        assert_eq!(curve.level(4, Duration::ZERO), 0);
+        assert_eq!(curve.level(12, Duration::ZERO), 2);
+        assert_eq!(curve.level(500, Duration::ZERO), 10);
+        assert_eq!(curve.speed(15.0, 2), 17.0);
+    }
+
+    #[test]
+    fn it_follows_the_time_played() {
+        let curve = SpeedCurve::builtin("marathon").unwrap();
+
+        assert_eq!(curve.level(100, Duration::from_secs(59)), 1);
+        assert_eq!(curve.level(0, Duration::from_secs(60)), 2);
+    }
+
+    #[test]
+    fn it_finds_curves_from_the_config_file() {
+        let custom: CustomCurves =
+            toml::from_str("[sprint]\nby = \"time\"\nevery = 10\nstep = 2.0\nmax-level = 3")
+                .unwrap();
+
+        let curve = SpeedCurve::find("sprint", Some(&custom)).unwrap().unwrap();
+        assert_eq!(curve.name, "sprint");
+        assert_eq!(curve.rule.by, Progress::Time);
+
+        assert_eq!(SpeedCurve::find(STEADY, Some(&custom)).unwrap(), None);
+
+        let error = SpeedCurve::find("sprnt", Some(&custom)).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("expected one of steady, gentle, steep, marathon, sprint")
+        );
+    }
+
+    #[test]
+    fn it_rejects_curves_that_cant_be_played() {
+        let custom: CustomCurves =
+            toml::from_str("[stuck]\nby = \"score\"\nevery = 0\nstep = 1.0\nmax-level = 3")
+                .unwrap();
+        assert!(SpeedCurve::validate(&custom).is_err());
+
+        let custom: CustomCurves =
+            toml::from_str("[gentle]\nby = \"score\"\nevery = 1\nstep = 1.0\nmax-level = 3")
+                .unwrap();
+        assert!(SpeedCurve::validate(&custom).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use anyhow::{Context, Result};
+use crossterm::{
+    ExecutableCommand, QueueableCommand, cursor,
+    event::EnableMouseCapture,
+    style::{self, Color, Print},
+    terminal,
+};
+
+use super::{
+    point::Point,
+    renderer::{FrameBuffer, Pixel},
+};
+
+/// A target that the changed pixels of a `FrameBuffer` are flushed into.
+pub trait RenderBackend {
+    fn start(&mut self) -> Result<()>;
+    fn stop(&mut self) -> Result<()>;
+    fn flush(&mut self, buffer: &FrameBuffer) -> Result<()>;
+
+    /// Called when the screen changes size. Anything already drawn should be
+    /// discarded since the next flush will contain the whole frame.
+    fn resize(&mut self, rows: usize, columns: usize) -> Result<()>;
+}
+
+/// Renders to a terminal emulator using crossterm.
+#[derive(Debug)]
+pub struct TerminalBackend<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TerminalBackend<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write> RenderBackend for TerminalBackend<W> {
+    fn start(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        self.writer
+            .queue(terminal::EnterAlternateScreen)?
+            .queue(EnableMouseCapture)?
+            .queue(cursor::Hide)?
+            .flush()
+            .with_context(|| "Failed to prepare terminal for game")
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        terminal::disable_raw_mode()?;
+        self.writer
+            .queue(terminal::LeaveAlternateScreen)?
+            .queue(cursor::Show)?
+            .flush()
+            .with_context(|| "Failed to restore terminal to original state")
+    }
+
+    fn flush(&mut self, buffer: &FrameBuffer) -> Result<()> {
+        let mut previous_fg = Color::Reset;
+        let mut previous_bg = Color::Reset;
+        let mut previous_pos: Option<Point> = None;
+
+        self.writer.execute(terminal::BeginSynchronizedUpdate)?;
+
+        for (position, pixel) in buffer {
+            if !matches!(previous_pos, Some(p) if p.x + 1 == position.x && p.y == position.y) {
+                self.writer
+                    .queue(cursor::MoveTo(position.x as u16, position.y as u16))?;
+            }
+
+            previous_pos = Some(*position);
+
+            if pixel.fg != previous_fg {
+                self.writer.queue(style::SetForegroundColor(pixel.fg))?;
+                previous_fg = pixel.fg;
+            }
+
+            if pixel.bg != previous_bg {
+                self.writer.queue(style::SetBackgroundColor(pixel.bg))?;
+                previous_bg = pixel.bg;
+            }
+
+            self.writer.queue(Print(&pixel.content))?;
+        }
+
+        self.writer.queue(style::ResetColor)?;
+        self.writer.flush()?;
+        self.writer.execute(terminal::EndSynchronizedUpdate)?;
+
+        Ok(())
+    }
+
+    fn resize(&mut self, _rows: usize, _columns: usize) -> Result<()> {
+        self.writer
+            .queue(terminal::Clear(terminal::ClearType::All))?
+            .flush()
+            .with_context(|| "Failed to clear terminal after resize")
+    }
+}
+
+/// Keeps the rendered screen in memory so scenes can be drawn without a TTY.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    pixels: BTreeMap<Point, Pixel>,
+    running: bool,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn pixel_at<T: Into<Point>>(&self, position: T) -> Option<&Pixel> {
+        self.pixels.get(&position.into())
+    }
+
+    /// Returns the content of a single row, using a space for every column
+    /// that hasn't been drawn to.
+    pub fn line(&self, row: usize) -> String {
+        let mut line = String::new();
+        let mut row_pixels: Vec<_> = self
+            .pixels
+            .iter()
+            .filter(|(position, _)| position.y == row)
+            .collect();
+
+        row_pixels.sort_by_key(|(position, _)| position.x);
+
+        for (position, pixel) in row_pixels {
+            while line.chars().count() < position.x {
+                line.push(' ');
+            }
+
+            line.push_str(&pixel.content);
+        }
+
+        line
+    }
+
+    /// Returns true if any row on the screen contains `text`.
+    pub fn contains(&self, text: &str) -> bool {
+        let rows = self.pixels.keys().map(|position| position.y + 1).max();
+        (0..rows.unwrap_or_default()).any(|row| self.line(row).contains(text))
+    }
+}
+
+impl RenderBackend for MemoryBackend {
+    fn start(&mut self) -> Result<()> {
+        self.running = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.running = false;
+        Ok(())
+    }
+
+    fn flush(&mut self, buffer: &FrameBuffer) -> Result<()> {
+        let clear = Pixel::default();
+        for (position, pixel) in buffer {
+            if *pixel == clear {
+                self.pixels.remove(position);
+            } else {
+                self.pixels.insert(*position, pixel.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resize(&mut self, _rows: usize, _columns: usize) -> Result<()> {
+        self.pixels.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::{DrawInstruction, Renderer, Style};
+
+    fn text(position: Point, content: &str) -> DrawInstruction<'_> {
+        DrawInstruction::Text {
+            position,
+            content,
+            style: Style::default(),
+        }
+    }
+
+    #[test]
+    fn it_flushes_drawn_text() {
+        let mut renderer = Renderer::new(MemoryBackend::new(), 4, 20);
+        renderer.draw(&[text(Point::new(2, 1), "hello")]).unwrap();
+
+        assert_eq!(renderer.backend().line(1), "  hello");
+        assert!(renderer.backend().contains("hello"));
+        assert_eq!(renderer.backend().pixel_at((2, 1)).unwrap().content, "h");
+    }
+
+    #[test]
+    fn it_clears_pixels_missing_from_the_next_frame() {
+        let mut renderer = Renderer::new(MemoryBackend::new(), 4, 20);
+        renderer.draw(&[text(Point::new(0, 0), "hello")]).unwrap();
+        renderer.draw(&[text(Point::new(0, 2), "world")]).unwrap();
+
+        assert_eq!(renderer.backend().line(0), "");
+        assert_eq!(renderer.backend().line(2), "world");
+    }
+
+    #[test]
+    fn it_tracks_running_state() {
+        let mut renderer = Renderer::new(MemoryBackend::new(), 4, 20);
+        renderer.start().unwrap();
+        assert!(renderer.backend().is_running());
+
+        renderer.stop().unwrap();
+        assert!(!renderer.backend().is_running());
+    }
+}
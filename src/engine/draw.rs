@@ -0,0 +1,33 @@
+use crossterm::style::Color;
+
+use super::point::Point;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fg: Color::Reset,
+            bg: Color::Reset,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DrawInstruction<'a> {
+    Rectangle {
+        position: Point,
+        width: usize,
+        height: usize,
+        style: Style,
+    },
+    Text {
+        position: Point,
+        content: &'a str,
+        style: Style,
+    },
+}
@@ -0,0 +1,11 @@
+use std::time::Duration;
+
+use super::draw::DrawInstruction;
+
+pub trait Entity {
+    type Input;
+
+    fn draw(&self) -> Vec<DrawInstruction<'_>>;
+    fn update(&mut self, _elapsed: &Duration) {}
+    fn process_input(&mut self, _input: &Self::Input) {}
+}
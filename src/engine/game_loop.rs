@@ -1,11 +1,25 @@
-use anyhow::Result;
-use crossterm::event;
-use std::{any::TypeId, io::Write, thread::sleep, time::Duration};
+use anyhow::{Result, anyhow, bail};
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{any::TypeId, thread::sleep, time::Duration};
 
 use super::{
-    renderer::Renderer, scene_manager::SceneManager, timestep::Timestep, traits::GameScene,
+    backend::RenderBackend,
+    input::{InputSource, TerminalInput},
+    point::Point,
+    renderer::{DrawInstruction, Renderer, Style},
+    scene_manager::SceneManager,
+    timestep::{ManualClock, Timestep},
+    traits::GameScene,
 };
 
+pub const TOO_SMALL_TEXT: &str = "TERMINAL TOO SMALL";
+const QUIT_TEXT: &str = "PRESS Q TO QUIT";
+
+type UpdateHandler = Box<dyn FnMut(&Duration)>;
+
 pub struct GameLoopConfig {
     pub frame_rate: u8,
     pub input_poll_rate: Duration,
@@ -24,79 +38,253 @@ impl GameLoopSignal {
     }
 }
 
-pub struct GameLoop<W: Write> {
+pub struct GameLoop<B: RenderBackend> {
     config: GameLoopConfig,
-    renderer: Renderer<W>,
+    renderer: Renderer<B>,
     ms_per_update: Duration,
     scene_manager: SceneManager,
+    input: Box<dyn InputSource>,
+    frame_state: FrameState,
+    scene_id: Option<TypeId>,
+    next_scene: Option<TypeId>,
+    manual_clock: Option<ManualClock>,
+    resize_handlers: Vec<Box<dyn FnMut(usize, usize)>>,
+    update_handlers: Vec<UpdateHandler>,
 }
 
-impl<W: Write> GameLoop<W> {
-    pub fn new(renderer: Renderer<W>, config: GameLoopConfig) -> Self {
-        let ms_per_update = Duration::from_millis((1_000.0 / (config.frame_rate as f32)) as u64);
+impl<B: RenderBackend> GameLoop<B> {
+    pub fn new(renderer: Renderer<B>, config: GameLoopConfig) -> Self {
+        let ms_per_update = Self::ms_per_update(config.frame_rate);
 
         Self {
             config,
             renderer,
             ms_per_update,
             scene_manager: SceneManager::new(),
+            input: Box::new(TerminalInput),
+            frame_state: FrameState::new(Timestep::new()),
+            scene_id: None,
+            next_scene: None,
+            manual_clock: None,
+            resize_handlers: vec![],
+            update_handlers: vec![],
         }
     }
 
+    /// Creates a game loop that never touches the terminal or the system
+    /// clock. Time only moves forward when `step` is called and events are
+    /// read from `input`.
+    pub fn headless<I: InputSource + 'static>(
+        renderer: Renderer<B>,
+        config: GameLoopConfig,
+        input: I,
+    ) -> Self {
+        let clock = ManualClock::new();
+        let mut game_loop = Self::new(renderer, config);
+        game_loop.input = Box::new(input);
+        game_loop.frame_state = FrameState::new(Timestep::with_clock(clock.clone()));
+        game_loop.manual_clock = Some(clock);
+        game_loop
+    }
+
+    pub fn renderer(&self) -> &Renderer<B> {
+        &self.renderer
+    }
+
     pub fn register_scene<TScene: GameScene>(&mut self, scene: TScene) -> &mut Self {
         self.scene_manager.register(scene);
         self
     }
 
-    pub fn run<TInitScene: GameScene>(&mut self) -> Result<()> {
-        let mut frame_state = FrameState::new();
-        let mut next_scene = None;
-        let mut scene = self.scene_manager.load_mut::<TInitScene>()?;
-
-        self.renderer.start()?;
+    /// Registers a scene that is re-created each time it's loaded.
+    pub fn register_scene_factory<TScene, F>(&mut self, factory: F) -> &mut Self
+    where
+        TScene: GameScene,
+        F: Fn() -> TScene + 'static,
+    {
+        self.scene_manager.register_factory(factory);
+        self
+    }
 
-        'game_loop: loop {
-            if let Some(scene_id) = next_scene.take() {
-                scene = self.scene_manager.load_mut_by_id(&scene_id)?;
-            }
+    /// Calls `handler` with the new columns and rows whenever the terminal is
+    /// resized, before any scene is told about it.
+    pub fn on_resize<F: FnMut(usize, usize) + 'static>(&mut self, handler: F) -> &mut Self {
+        self.resize_handlers.push(Box::new(handler));
+        self
+    }
 
-            if event::poll(self.config.input_poll_rate)? {
-                frame_state.signal = scene.process_input(&event::read()?)?;
-            }
+    /// Calls `handler` after every fixed update of the active scene, with the
+    /// time the update covered.
+    pub fn on_update<F: FnMut(&Duration) + 'static>(&mut self, handler: F) -> &mut Self {
+        self.update_handlers.push(Box::new(handler));
+        self
+    }
 
-            next_scene = match frame_state.signal {
-                GameLoopSignal::Stop => break,
-                GameLoopSignal::Run => next_scene,
-                GameLoopSignal::Load(scene_id) => Some(scene_id),
-            };
+    /// Sets the scene that the next frame will be run against.
+    pub fn load_scene<TScene: GameScene>(&mut self) -> &mut Self {
+        self.next_scene = Some(TypeId::of::<TScene>());
+        self
+    }
 
-            frame_state.lag += frame_state.timestep.delta();
-            while frame_state.lag >= self.ms_per_update {
-                frame_state.lag -= self.ms_per_update;
-                frame_state.signal = scene.update(&self.ms_per_update)?;
-                next_scene = match frame_state.signal {
-                    GameLoopSignal::Stop => break 'game_loop,
-                    GameLoopSignal::Run => next_scene,
-                    GameLoopSignal::Load(scene_id) => Some(scene_id),
-                };
-            }
+    pub fn run<TInitScene: GameScene>(&mut self) -> Result<()> {
+        if !self
+            .scene_manager
+            .is_registered(&TypeId::of::<TInitScene>())
+        {
+            bail!("Initial scene is not registered");
+        }
 
-            self.renderer.draw(&scene.draw(&frame_state.timestep))?;
+        self.load_scene::<TInitScene>();
+        self.renderer.start()?;
 
+        while self.frame()? {
             let remaining_tick_time = self
                 .ms_per_update
-                .checked_sub(frame_state.timestep.elapsed_time())
+                .checked_sub(self.frame_state.timestep.elapsed_time())
                 .unwrap_or_default();
 
             if remaining_tick_time > Duration::from_millis(0) {
                 sleep(Duration::from_millis(1));
             }
 
-            frame_state.timestep.track_frame();
+            self.frame_state.timestep.track_frame();
         }
 
         self.renderer.stop()
     }
+
+    /// Advances a headless game loop by `ticks` fixed updates, drawing after
+    /// each one. Returns false once a scene signals the loop to stop.
+    pub fn step(&mut self, ticks: usize) -> Result<bool> {
+        let clock = self
+            .manual_clock
+            .clone()
+            .ok_or_else(|| anyhow!("Only a headless game loop can be stepped"))?;
+
+        self.renderer.start()?;
+
+        for _ in 0..ticks {
+            clock.advance(self.ms_per_update);
+            if !self.frame()? {
+                return Ok(false);
+            }
+
+            self.frame_state.timestep.track_frame();
+        }
+
+        Ok(true)
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        self.renderer.resize(rows, columns)?;
+        for handler in &mut self.resize_handlers {
+            handler(columns, rows);
+        }
+
+        self.scene_manager.resize(columns, rows)
+    }
+
+    fn draw_centered(lines: &[String], columns: usize, rows: usize) -> Vec<DrawInstruction<'_>> {
+        let top = rows.saturating_sub(lines.len()) / 2;
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| DrawInstruction::Text {
+                position: Point::new(columns.saturating_sub(line.chars().count()) / 2, top + i),
+                content: line,
+                style: Style {
+                    fg: if i == 0 { Color::Red } else { Color::Reset },
+                    ..Default::default()
+                },
+            })
+            .collect()
+    }
+
+    fn ms_per_update(frame_rate: u8) -> Duration {
+        Duration::from_millis((1_000.0 / (frame_rate as f32)) as u64)
+    }
+
+    /// Runs a single iteration of the loop: input, any pending fixed updates,
+    /// then a draw. Returns false once a scene signals the loop to stop.
+    fn frame(&mut self) -> Result<bool> {
+        if let Some(scene_id) = self.next_scene.take() {
+            let scene = self.scene_manager.enter(&scene_id)?;
+            if let Some(frame_rate) = scene.frame_rate() {
+                self.config.frame_rate = frame_rate;
+                self.ms_per_update = Self::ms_per_update(frame_rate);
+            }
+
+            self.scene_id = Some(scene_id);
+        }
+
+        let scene_id = self
+            .scene_id
+            .ok_or_else(|| anyhow!("No scene has been loaded"))?;
+
+        let event = self.input.poll(self.config.input_poll_rate)?;
+        if let Some(Event::Resize(columns, rows)) = event {
+            self.resize(columns as usize, rows as usize)?;
+        }
+
+        let frame_state = &mut self.frame_state;
+        let scene = self.scene_manager.load_mut_by_id(&scene_id)?;
+
+        let (min_columns, min_rows) = scene.min_size();
+        let (columns, rows) = (self.renderer.columns(), self.renderer.rows());
+        if columns < min_columns || rows < min_rows {
+            // Time spent waiting for a bigger terminal isn't caught up on
+            frame_state.timestep.delta();
+            frame_state.lag = Duration::from_millis(0);
+
+            if let Some(Event::Key(e)) = event
+                && matches!(e.code, KeyCode::Char('q') | KeyCode::Esc)
+            {
+                return Ok(false);
+            }
+
+            let lines = [
+                TOO_SMALL_TEXT.to_string(),
+                format!("{columns}x{rows}, NEEDS {min_columns}x{min_rows}"),
+                QUIT_TEXT.to_string(),
+            ];
+
+            self.renderer
+                .draw(&Self::draw_centered(&lines, columns, rows))?;
+
+            return Ok(true);
+        }
+
+        if let Some(event) = event {
+            frame_state.signal = scene.process_input(&event)?;
+        }
+
+        // The signal is taken so a load is only acted on once, rather than
+        // on every frame until the next update
+        self.next_scene = match frame_state.take_signal() {
+            GameLoopSignal::Stop => return Ok(false),
+            GameLoopSignal::Run => self.next_scene,
+            GameLoopSignal::Load(scene_id) => Some(scene_id),
+        };
+
+        frame_state.lag += frame_state.timestep.delta();
+        while frame_state.lag >= self.ms_per_update {
+            frame_state.lag -= self.ms_per_update;
+            frame_state.signal = scene.update(&self.ms_per_update)?;
+            for handler in &mut self.update_handlers {
+                handler(&self.ms_per_update);
+            }
+
+            self.next_scene = match frame_state.take_signal() {
+                GameLoopSignal::Stop => return Ok(false),
+                GameLoopSignal::Run => self.next_scene,
+                GameLoopSignal::Load(scene_id) => Some(scene_id),
+            };
+        }
+
+        self.renderer.draw(&scene.draw(&frame_state.timestep))?;
+
+        Ok(true)
+    }
 }
 
 struct FrameState {
@@ -106,11 +294,89 @@ struct FrameState {
 }
 
 impl FrameState {
-    pub fn new() -> Self {
+    pub fn new(timestep: Timestep) -> Self {
         Self {
-            timestep: Timestep::new(),
+            timestep,
             lag: Duration::from_millis(0),
             signal: GameLoopSignal::Run,
         }
     }
+
+    pub fn take_signal(&mut self) -> GameLoopSignal {
+        std::mem::replace(&mut self.signal, GameLoopSignal::Run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{backend::MemoryBackend, input::ScriptedInput};
+    use std::{cell::Cell, rc::Rc};
+
+    /// Asks to load `NextScene` on its first update.
+    struct FirstScene;
+
+    impl GameScene for FirstScene {
+        fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+            vec![]
+        }
+
+        fn update(&mut self, _elapsed: &Duration) -> Result<GameLoopSignal> {
+            Ok(GameLoopSignal::load_scene::<NextScene>())
+        }
+
+        fn process_input(&mut self, _event: &Event) -> Result<GameLoopSignal> {
+            Ok(GameLoopSignal::Run)
+        }
+    }
+
+    struct NextScene;
+
+    impl GameScene for NextScene {
+        fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+            vec![]
+        }
+
+        fn update(&mut self, _elapsed: &Duration) -> Result<GameLoopSignal> {
+            Ok(GameLoopSignal::Run)
+        }
+
+        fn process_input(&mut self, _event: &Event) -> Result<GameLoopSignal> {
+            Ok(GameLoopSignal::Run)
+        }
+    }
+
+    #[test]
+    fn it_only_creates_a_loaded_scene_once() {
+        let created = Rc::new(Cell::new(0));
+        let mut game_loop = GameLoop::headless(
+            Renderer::new(MemoryBackend::new(), 10, 10),
+            GameLoopConfig {
+                frame_rate: 15,
+                input_poll_rate: Duration::from_millis(0),
+            },
+            ScriptedInput::new(),
+        );
+
+        let counter = created.clone();
+        game_loop
+            .register_scene(FirstScene)
+            .register_scene_factory(move || {
+                counter.set(counter.get() + 1);
+                NextScene
+            })
+            .load_scene::<FirstScene>();
+
+        game_loop.step(1).unwrap();
+
+        // Frames run far more often than updates outside of tests
+        let clock = game_loop.manual_clock.clone().unwrap();
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(1));
+            assert!(game_loop.frame().unwrap());
+        }
+
+        assert_eq!(created.get(), 1);
+        assert_eq!(game_loop.scene_id, Some(TypeId::of::<NextScene>()));
+    }
 }
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::Duration};
+
+/// Supplies the events a `GameLoop` passes to the active scene.
+pub trait InputSource {
+    /// Waits up to `timeout` for the next event.
+    fn poll(&mut self, timeout: Duration) -> Result<Option<Event>>;
+}
+
+/// Reads events from the terminal using crossterm.
+#[derive(Debug, Default)]
+pub struct TerminalInput;
+
+impl InputSource for TerminalInput {
+    fn poll(&mut self, timeout: Duration) -> Result<Option<Event>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        Ok(Some(event::read()?))
+    }
+}
+
+/// A queue of predetermined events. Clones share the same queue so events can
+/// be pushed while a `GameLoop` owns another handle.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    events: Rc<RefCell<VecDeque<Event>>>,
+}
+
+impl ScriptedInput {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&self, event: Event) {
+        self.events.borrow_mut().push_back(event);
+    }
+
+    pub fn push_key(&self, code: KeyCode) {
+        self.push(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.borrow().is_empty()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, _timeout: Duration) -> Result<Option<Event>> {
+        Ok(self.events.borrow_mut().pop_front())
+    }
+}
@@ -1,4 +1,8 @@
+pub mod backend;
+pub mod draw;
+pub mod entity;
 pub mod game_loop;
+pub mod input;
 pub mod point;
 pub mod renderer;
 pub mod scene_manager;
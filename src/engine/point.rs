@@ -28,6 +28,32 @@ where
     }
 }
 
+impl Point {
+    /// Subtracts `other` from each axis, stopping at zero instead of
+    /// underflowing.
+    pub fn saturating_sub(&self, other: &Point) -> Point {
+        Point {
+            x: self.x.saturating_sub(other.x),
+            y: self.y.saturating_sub(other.y),
+        }
+    }
+
+    /// Moves the point by `vector`, wrapping around to the opposite edge of
+    /// the area from `min` (inclusive) to `max` (exclusive).
+    pub fn wrapping_offset(&self, vector: &Vector, min: &Point, max: &Point) -> Point {
+        let wrap = |value: usize, offset: isize, min: usize, max: usize| {
+            let size = max.saturating_sub(min).max(1) as isize;
+            let relative = value as isize - min as isize + offset;
+            min + relative.rem_euclid(size) as usize
+        };
+
+        Point {
+            x: wrap(self.x, vector.x, min.x, max.x),
+            y: wrap(self.y, vector.y, min.y, max.y),
+        }
+    }
+}
+
 impl From<&Point<usize>> for (usize, usize) {
     fn from(value: &Point<usize>) -> Self {
         (value.x, value.y)
@@ -70,9 +96,52 @@ impl_op_ex_commutative!(*|a: &Point, b: &usize| -> Point {
     }
 });
 
+// Stops at zero rather than underflowing, use `Point::wrapping_offset` to
+// come back around on the other side.
 impl_op_ex!(+|a: &Point, b: &Vector| -> Point {
     Point {
-        x: (a.x as isize + b.x) as usize,
-        y: (a.y as isize + b.y) as usize,
+        x: a.x.saturating_add_signed(b.x),
+        y: a.y.saturating_add_signed(b.y),
     }
 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stops_at_zero_when_adding_a_negative_vector() {
+        assert_eq!(Point::new(1, 3) + Vector::new(-2, -1), Point::new(0, 2));
+    }
+
+    #[test]
+    fn it_wraps_around_each_edge() {
+        let min = Point::new(2, 1);
+        let max = Point::new(10, 5);
+
+        assert_eq!(
+            Point::new(8, 2).wrapping_offset(&Vector::new(2, 0), &min, &max),
+            Point::new(2, 2)
+        );
+        assert_eq!(
+            Point::new(2, 2).wrapping_offset(&Vector::new(-2, 0), &min, &max),
+            Point::new(8, 2)
+        );
+        assert_eq!(
+            Point::new(4, 1).wrapping_offset(&Vector::new(0, -1), &min, &max),
+            Point::new(4, 4)
+        );
+        assert_eq!(
+            Point::new(4, 4).wrapping_offset(&Vector::new(0, 1), &min, &max),
+            Point::new(4, 1)
+        );
+    }
+
+    #[test]
+    fn it_stops_at_zero_when_subtracting_a_larger_point() {
+        assert_eq!(
+            Point::new(3, 10).saturating_sub(&Point::new(5, 4)),
+            Point::new(0, 6)
+        );
+    }
+}
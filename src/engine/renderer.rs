@@ -1,16 +1,12 @@
 use std::collections::BTreeMap;
 use std::hash::Hash;
-use std::io::Write;
 
-use anyhow::{Context, Result};
-use crossterm::{
-    cursor,
-    event::EnableMouseCapture,
-    style::{self, Color, Print},
-    terminal, ExecutableCommand, QueueableCommand,
-};
+use anyhow::Result;
+use crossterm::style::Color;
 
-use super::point::Point;
+pub use super::draw::{DrawInstruction, Style};
+
+use super::{backend::RenderBackend, point::Point};
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Pixel {
@@ -46,21 +42,6 @@ impl Default for Pixel {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Style {
-    pub fg: Color,
-    pub bg: Color,
-}
-
-impl Default for Style {
-    fn default() -> Self {
-        Self {
-            fg: Color::Reset,
-            bg: Color::Reset,
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct FrameBuffer {
     rows: usize,
@@ -98,6 +79,23 @@ impl FrameBuffer {
         });
     }
 
+    /// Changes the size of the buffer and forgets what was drawn so the next
+    /// frame is written out in full.
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        self.rows = rows;
+        self.columns = columns;
+        self.pixels.clear();
+        self.previous.clear();
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
     pub fn frame_changed(&self) -> bool {
         self.previous != self.pixels
     }
@@ -118,40 +116,29 @@ impl<'a> IntoIterator for &'a FrameBuffer {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum DrawInstruction<'a> {
-    Rectangle {
-        position: Point,
-        width: usize,
-        height: usize,
-        style: Style,
-    },
-    Text {
-        position: Point,
-        content: &'a str,
-        style: Style,
-    },
-}
-
-impl<'a> DrawInstruction<'a> {
-    pub fn apply(&self, buffer: &mut FrameBuffer) {
-        match self {
+impl FrameBuffer {
+    pub fn apply(&mut self, instruction: &DrawInstruction) {
+        match instruction {
             DrawInstruction::Rectangle {
                 position: origin,
                 width,
                 height,
                 style,
             } => {
+                if *width == 0 || *height == 0 {
+                    return;
+                }
+
                 let diagonal = Point::new(origin.x + width, origin.y + height);
 
                 // top/bottom
                 for column in (origin.x + 1)..(diagonal.x - 1) {
-                    buffer.set_at(
+                    self.set_at(
                         Point::new(column, origin.y),
                         Pixel::new("─").with_fg(style.fg).with_bg(style.bg),
                     );
 
-                    buffer.set_at(
+                    self.set_at(
                         Point::new(column, diagonal.y - 1),
                         Pixel::new("─").with_fg(style.fg).with_bg(style.bg),
                     );
@@ -159,33 +146,33 @@ impl<'a> DrawInstruction<'a> {
 
                 // left/right
                 for row in (origin.y + 1)..(diagonal.y - 1) {
-                    buffer.set_at(
+                    self.set_at(
                         Point::new(origin.x, row),
                         Pixel::new("│").with_fg(style.fg).with_bg(style.bg),
                     );
-                    buffer.set_at(
+                    self.set_at(
                         Point::new(diagonal.x - 1, row),
                         Pixel::new("│").with_fg(style.fg).with_bg(style.bg),
                     );
                 }
 
                 // top left corner
-                buffer.set_at(*origin, Pixel::new("╭").with_fg(style.fg).with_bg(style.bg));
+                self.set_at(*origin, Pixel::new("╭").with_fg(style.fg).with_bg(style.bg));
 
                 // top right corner
-                buffer.set_at(
+                self.set_at(
                     Point::new(diagonal.x - 1, origin.y),
                     Pixel::new("╮").with_fg(style.fg).with_bg(style.bg),
                 );
 
                 // bottom left corner
-                buffer.set_at(
+                self.set_at(
                     Point::new(origin.x, diagonal.y - 1),
                     Pixel::new("╰").with_fg(style.fg).with_bg(style.bg),
                 );
 
                 // bottom right corner
-                buffer.set_at(
+                self.set_at(
                     Point::new(diagonal.x - 1, diagonal.y - 1),
                     Pixel::new("╯").with_fg(style.fg).with_bg(style.bg),
                 );
@@ -206,7 +193,7 @@ impl<'a> DrawInstruction<'a> {
                     }
 
                     let position = position + Point::new(i - x_offset, y_offset);
-                    buffer.set_at(
+                    self.set_at(
                         position,
                         Pixel::new(&c.to_string())
                             .with_fg(style.fg)
@@ -219,36 +206,47 @@ impl<'a> DrawInstruction<'a> {
 }
 
 #[derive(Debug)]
-pub struct Renderer<W: Write> {
-    writer: W,
+pub struct Renderer<B: RenderBackend> {
+    backend: B,
     buffer: FrameBuffer,
     running: bool,
 }
 
-impl<W: Write> Renderer<W> {
-    pub fn new(writer: W, rows: usize, columns: usize) -> Self {
+impl<B: RenderBackend> Renderer<B> {
+    pub fn new(backend: B, rows: usize, columns: usize) -> Self {
         let buffer = FrameBuffer::new(rows, columns);
 
         Self {
-            writer,
+            backend,
             buffer,
             running: false,
         }
     }
 
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
     pub fn start(&mut self) -> Result<()> {
         if self.running {
             return Ok(());
         }
 
         self.running = true;
-        terminal::enable_raw_mode()?;
-        self.writer
-            .queue(terminal::EnterAlternateScreen)?
-            .queue(EnableMouseCapture)?
-            .queue(cursor::Hide)?
-            .flush()
-            .with_context(|| "Failed to prepare terminal for game")
+        self.backend.start()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.buffer.rows()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.buffer.columns()
+    }
+
+    pub fn resize(&mut self, rows: usize, columns: usize) -> Result<()> {
+        self.buffer.resize(rows, columns);
+        self.backend.resize(rows, columns)
     }
 
     pub fn stop(&mut self) -> Result<()> {
@@ -257,61 +255,25 @@ impl<W: Write> Renderer<W> {
         }
 
         self.running = false;
-        terminal::disable_raw_mode()?;
-        self.writer
-            .queue(terminal::LeaveAlternateScreen)?
-            .queue(cursor::Show)?
-            .flush()
-            .with_context(|| "Failed to restore terminal to original state")
+        self.backend.stop()
     }
 
     pub fn draw(&mut self, draw_instructions: &[DrawInstruction]) -> Result<()> {
         self.buffer.clear();
 
         for instruction in draw_instructions {
-            instruction.apply(&mut self.buffer);
+            self.buffer.apply(instruction);
         }
 
         if !self.buffer.frame_changed() {
             return Ok(());
         }
 
-        let mut previous_fg = Color::Reset;
-        let mut previous_bg = Color::Reset;
-        let mut previous_pos: Option<Point> = None;
-
-        self.writer.execute(terminal::BeginSynchronizedUpdate)?;
-
-        for (position, pixel) in &self.buffer {
-            if !matches!(previous_pos, Some(p) if p.x + 1 == position.x && p.y == position.y) {
-                self.writer
-                    .queue(cursor::MoveTo(position.x as u16, position.y as u16))?;
-            }
-
-            previous_pos = Some(*position);
-
-            if pixel.fg != previous_fg {
-                self.writer.queue(style::SetForegroundColor(pixel.fg))?;
-                previous_fg = pixel.fg;
-            }
-
-            if pixel.bg != previous_bg {
-                self.writer.queue(style::SetBackgroundColor(pixel.bg))?;
-                previous_bg = pixel.bg;
-            }
-
-            self.writer.queue(Print(&pixel.content))?;
-        }
-
-        self.writer.queue(style::ResetColor)?;
-        self.writer.flush()?;
-        self.writer.execute(terminal::EndSynchronizedUpdate)?;
-
-        Ok(())
+        self.backend.flush(&self.buffer)
     }
 }
 
-impl<W: Write> Drop for Renderer<W> {
+impl<B: RenderBackend> Drop for Renderer<B> {
     fn drop(&mut self) {
         self.stop().unwrap();
     }
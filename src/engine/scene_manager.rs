@@ -1,11 +1,14 @@
-use anyhow::{anyhow, Result};
+use anyhow::{Result, anyhow};
 use std::{any::TypeId, collections::HashMap};
 
 use super::traits::GameScene;
 
+type SceneFactory = Box<dyn Fn() -> Box<dyn GameScene>>;
+
 #[derive(Default)]
 pub struct SceneManager {
     scenes: HashMap<TypeId, Box<dyn GameScene>>,
+    factories: HashMap<TypeId, SceneFactory>,
 }
 
 impl SceneManager {
@@ -18,8 +21,42 @@ impl SceneManager {
         self
     }
 
-    pub fn load_mut<TScene: GameScene>(&mut self) -> Result<&mut Box<dyn GameScene + 'static>> {
-        self.load_mut_by_id(&TypeId::of::<TScene>())
+    /// Registers a scene that is created fresh by `factory` every time it's
+    /// entered, rather than resuming where it was left.
+    pub fn register_factory<TScene, F>(&mut self, factory: F) -> &mut Self
+    where
+        TScene: GameScene,
+        F: Fn() -> TScene + 'static,
+    {
+        let id = TypeId::of::<TScene>();
+        self.scenes.remove(&id);
+        self.factories
+            .insert(id, Box::new(move || Box::new(factory())));
+
+        self
+    }
+
+    pub fn is_registered(&self, id: &TypeId) -> bool {
+        self.scenes.contains_key(id) || self.factories.contains_key(id)
+    }
+
+    /// Prepares the scene to become the active one, re-creating it if it was
+    /// registered with a factory.
+    pub fn enter(&mut self, id: &TypeId) -> Result<&mut Box<dyn GameScene + 'static>> {
+        if let Some(factory) = self.factories.get(id) {
+            self.scenes.insert(*id, factory());
+        }
+
+        self.load_mut_by_id(id)
+    }
+
+    /// Lets every created scene know the terminal has changed size.
+    pub fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        for scene in self.scenes.values_mut() {
+            scene.resize(columns, rows)?;
+        }
+
+        Ok(())
     }
 
     pub fn load_mut_by_id(&mut self, id: &TypeId) -> Result<&mut Box<dyn GameScene + 'static>> {
@@ -1,19 +1,89 @@
-use std::time::{Duration, Instant};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// A monotonic source of time, measured from an arbitrary starting point.
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+/// Reads the time from the operating system.
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when it's told to. Clones share the same time so
+/// one handle can be given to a `Timestep` while another advances it.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
 
 pub struct Timestep {
-    last_time: Instant,
+    clock: Box<dyn Clock>,
+    last_time: Duration,
     delta_time: Duration,
     frame_time: Duration,
     frame_count: u16,
     pub frame_rate: u16,
 }
 
+impl Default for Timestep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Timestep {
     const ONE_SECOND: Duration = Duration::from_secs(1);
 
     pub fn new() -> Self {
+        Self::with_clock(SystemClock::new())
+    }
+
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
         Self {
-            last_time: Instant::now(),
+            last_time: clock.now(),
+            clock: Box::new(clock),
             delta_time: Duration::from_millis(0),
             frame_time: Duration::from_millis(0),
             frame_count: 0,
@@ -22,15 +92,15 @@ impl Timestep {
     }
 
     pub fn delta(&mut self) -> Duration {
-        let now = Instant::now();
-        self.delta_time = now.duration_since(self.last_time);
+        let now = self.clock.now();
+        self.delta_time = now.saturating_sub(self.last_time);
         self.last_time = now;
 
         self.delta_time
     }
 
     pub fn elapsed_time(&self) -> Duration {
-        Instant::now().duration_since(self.last_time)
+        self.clock.now().saturating_sub(self.last_time)
     }
 
     pub fn track_frame(&mut self) -> Option<u16> {
@@ -2,18 +2,31 @@ use anyhow::Result;
 use crossterm::event::Event;
 use std::time::Duration;
 
-use super::{game_loop::GameLoopSignal, renderer::DrawInstruction, timestep::Timestep};
-
-pub trait Entity {
-    type Input;
+pub use super::entity::Entity;
 
-    fn draw(&self) -> Vec<DrawInstruction<'_>>;
-    fn update(&mut self, _elapsed: &Duration) {}
-    fn process_input(&mut self, _input: &Self::Input) {}
-}
+use super::{game_loop::GameLoopSignal, renderer::DrawInstruction, timestep::Timestep};
 
 pub trait GameScene: 'static {
     fn draw(&mut self, timestep: &Timestep) -> Vec<DrawInstruction<'_>>;
     fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal>;
     fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal>;
+
+    /// The number of fixed updates per second the scene wants to run at once
+    /// it's entered. `None` keeps the rate of the previous scene.
+    fn frame_rate(&self) -> Option<u8> {
+        None
+    }
+
+    /// The smallest terminal, in columns and rows, the scene can be drawn in.
+    /// The game loop shows a message asking for a bigger terminal instead of
+    /// running the scene while it doesn't fit.
+    fn min_size(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    /// Called on every scene that's been created when the terminal changes
+    /// size, whether or not it's the active scene.
+    fn resize(&mut self, _columns: usize, _rows: usize) -> Result<()> {
+        Ok(())
+    }
 }
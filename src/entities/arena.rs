@@ -0,0 +1,276 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    GameConfig, PlayerInput,
+    bot::{BotSnake, Tile},
+    config::SnakeConfig,
+    engine::{point::Point, renderer::DrawInstruction, traits::Entity},
+};
+
+use super::{
+    food::{Food, FoodKind},
+    snake::Snake,
+    world::World,
+};
+
+/// What a snake ran into to be taken out of the round.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Crash {
+    Wall,
+    Itself,
+    /// Any part of another snake other than its head.
+    Snake,
+    HeadOn,
+}
+
+impl Display for Crash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wall => write!(f, "wall"),
+            Self::Itself => write!(f, "self"),
+            Self::Snake => write!(f, "snake"),
+            Self::HeadOn => write!(f, "head-on"),
+        }
+    }
+}
+
+/// The world, food and snakes of a round that several snakes play at once.
+/// It doesn't know who is steering each snake, so versus matches and
+/// tournaments play by the same rules.
+#[derive(Debug)]
+pub struct Arena {
+    world: World,
+    configs: Vec<SnakeConfig>,
+    snakes: Vec<Snake>,
+    /// False once the snake has crashed, until the next round.
+    alive: Vec<bool>,
+    food: Food,
+    grow_rate: usize,
+}
+
+impl Arena {
+    /// Spawns a snake for each config, in the order players are spawned by
+    /// `World::create_player_snake`.
+    pub fn new(config: &GameConfig, snakes: Vec<SnakeConfig>) -> Self {
+        let world = World::new(config, Point::new(0, 0));
+
+        Self {
+            snakes: snakes
+                .iter()
+                .enumerate()
+                .map(|(index, snake)| world.create_player_snake(index, snake))
+                .collect(),
+            alive: vec![true; snakes.len()],
+            food: Food::new(world.get_random_position()),
+            configs: snakes,
+            grow_rate: config.snake.grow_rate,
+            world,
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The food's screen position.
+    pub fn food(&self) -> Point {
+        self.food.get_position()
+    }
+
+    pub fn food_kind(&self) -> FoodKind {
+        self.food.kind()
+    }
+
+    /// The food's tile.
+    pub fn food_tile(&self) -> Option<Tile> {
+        self.world.to_tile(self.food()).map(|tile| (tile.x, tile.y))
+    }
+
+    pub fn snake(&self, index: usize) -> &Snake {
+        &self.snakes[index]
+    }
+
+    /// The snake in the world's tiles, while it's still in the round.
+    pub fn bot_snake(&self, index: usize) -> Option<BotSnake> {
+        self.alive[index].then(|| BotSnake::new(&self.world, &self.snakes[index]))
+    }
+
+    pub fn is_alive(&self, index: usize) -> bool {
+        self.alive[index]
+    }
+
+    /// The snakes still in the round, with their index.
+    pub fn living(&self) -> impl Iterator<Item = (usize, &Snake)> {
+        self.snakes
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| self.alive[index])
+    }
+
+    /// Puts every snake back at its spawn point and places new food.
+    pub fn start_round(&mut self) {
+        for (index, config) in self.configs.iter().enumerate() {
+            self.snakes[index] = self.world.create_player_snake(index, config);
+            self.alive[index] = true;
+        }
+
+        self.food = self.spawn_food();
+    }
+
+    /// Takes a snake out of the round without it crashing.
+    pub fn take_out(&mut self, index: usize) {
+        self.alive[index] = false;
+    }
+
+    /// Moves every snake still in the round, taking out and returning the
+    /// ones that crashed. Every crash is found before any snake is taken out
+    /// so a head-on crash takes out both snakes.
+    pub fn move_snakes(&mut self, elapsed: &Duration) -> Vec<(usize, Crash)> {
+        for (snake, _) in self
+            .snakes
+            .iter_mut()
+            .zip(&self.alive)
+            .filter(|(_, alive)| **alive)
+        {
+            snake.update(elapsed);
+        }
+
+        let crashed: Vec<_> = self
+            .living()
+            .filter_map(|(index, _)| Some((index, self.crash(index)?)))
+            .collect();
+
+        for &(index, _) in &crashed {
+            self.alive[index] = false;
+        }
+
+        crashed
+    }
+
+    /// Grows the first snake still in the round with its head on the food
+    /// and places new food, returning the snake's index.
+    pub fn eat(&mut self) -> Option<usize> {
+        let food = self.food.get_position();
+        let index = self
+            .living()
+            .find(|(_, snake)| snake.detect_head_collision(food))
+            .map(|(index, _)| index)?;
+
+        self.snakes[index].grow(self.grow_rate);
+        self.food = self.spawn_food();
+        Some(index)
+    }
+
+    /// What the snake ran into, if it ran into a wall, itself or any part of
+    /// another snake still in the round.
+    fn crash(&self, index: usize) -> Option<Crash> {
+        let snake = &self.snakes[index];
+        let head = snake.head();
+
+        if self.world.detect_collision(head) {
+            return Some(Crash::Wall);
+        }
+
+        if snake.detect_self_collision() {
+            return Some(Crash::Itself);
+        }
+
+        self.living()
+            .filter(|&(other, _)| other != index)
+            .find(|(_, other)| other.detect_collision(head))
+            .map(|(_, other)| match other.detect_head_collision(head) {
+                true => Crash::HeadOn,
+                false => Crash::Snake,
+            })
+    }
+
+    fn spawn_food(&self) -> Food {
+        Food::new(self.world.get_free_position(|position| {
+            self.living()
+                .any(|(_, snake)| snake.detect_collision(position))
+        }))
+    }
+}
+
+impl Entity for Arena {
+    /// A turn for the snake at the index.
+    type Input = (usize, PlayerInput);
+
+    fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        let snakes = self.living().flat_map(|(_, snake)| snake.draw());
+
+        self.food
+            .draw()
+            .into_iter()
+            .chain(self.world.draw())
+            .chain(snakes)
+            .collect()
+    }
+
+    fn process_input(&mut self, (index, input): &Self::Input) {
+        self.snakes[*index].process_input(input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_config;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            rows: 10,
+            columns: 30,
+            ..test_config()
+        }
+    }
+
+    const ONE_TILE: Duration = Duration::from_millis(67);
+
+    fn create_arena(level: &str, snakes: usize) -> Arena {
+        let config = GameConfig {
+            level: Some(level.parse().unwrap()),
+            ..config()
+        };
+
+        Arena::new(&config, vec![config.snake.clone(); snakes])
+    }
+
+    #[test]
+    fn it_takes_out_both_snakes_in_a_head_on_crash() {
+        let mut arena = create_arena("---\n..S......", 2);
+
+        let mut crashes = vec![];
+        while crashes.is_empty() {
+            crashes = arena.move_snakes(&ONE_TILE);
+        }
+
+        assert_eq!(crashes, vec![(0, Crash::HeadOn), (1, Crash::HeadOn)]);
+        assert_eq!(arena.living().count(), 0);
+    }
+
+    #[test]
+    fn it_reports_what_each_snake_ran_into() {
+        let mut arena = create_arena("---\n.........\n..S......\n.........", 2);
+
+        // The second snake turns off the top of the level
+        arena.process_input(&(1, PlayerInput::Up));
+        assert_eq!(arena.move_snakes(&ONE_TILE), vec![]);
+        assert_eq!(arena.move_snakes(&ONE_TILE), vec![(1, Crash::Wall)]);
+
+        assert!(arena.is_alive(0));
+        assert!(!arena.is_alive(1));
+    }
+
+    #[test]
+    fn it_grows_the_snake_that_eats() {
+        let mut arena = create_arena("---\n.........\n..S......\n.........", 1);
+        let head = arena.snake(0).head();
+        arena.food = Food::new(Point::new(head.x / 2 + 1, head.y));
+
+        arena.move_snakes(&ONE_TILE);
+        assert_eq!(arena.eat(), Some(0));
+        assert_eq!(arena.snake(0).size(), 4);
+        assert_eq!(arena.eat(), None);
+    }
+}
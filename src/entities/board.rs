@@ -0,0 +1,336 @@
+use crossterm::style::Color;
+
+use crate::{
+    GameConfig, GameMode, PlayerInput,
+    bot::Tile,
+    engine::{
+        point::Point,
+        renderer::{DrawInstruction, Style},
+        traits::Entity,
+    },
+    level::Level,
+    net::{Phase, Snapshot},
+    scenes::versus::PLAYER_COLORS,
+};
+
+use super::{text::Text, world::World};
+
+const WAITING_TEXT: &str = "WAITING FOR PLAYERS";
+const DRAW_TEXT: &str = "DRAW";
+const GAME_OVER_TEXT: &str = "GAME OVER";
+
+/// Draws a networked game from the snapshots the server sends, laid out the
+/// same way as a versus match.
+///
+/// A board with a camera only shows as much of the level as fits in its
+/// columns and rows, with `follow` moving the camera around the rest.
+#[derive(Debug)]
+pub struct Board {
+    config: GameConfig,
+    level: Level,
+    /// The top left tile of the part of the level being shown.
+    camera: Point,
+    /// How many tiles across and down are shown.
+    view: Point,
+    world: World,
+    /// The id of the player whose snake stands out from the others.
+    highlight: Option<usize>,
+    snapshot: Snapshot,
+    round_text: Text,
+    state_text: Text,
+    scores: Vec<Text>,
+}
+
+impl Board {
+    /// A board for the server's level, centered in the config's columns and
+    /// rows.
+    pub fn new(config: &GameConfig, level: Level, wrap: bool) -> Self {
+        let view = Point::new(level.width(), level.height());
+        Self::with_view(config, level, wrap, view)
+    }
+
+    /// A board that shows as much of the level as fits in the config's
+    /// columns and rows, starting from the top left.
+    pub fn with_camera(config: &GameConfig, level: Level, wrap: bool) -> Self {
+        // Leaving room for the border on every side
+        let view = Point::new(
+            (config.columns.saturating_sub(4) / 2).clamp(1, level.width().max(1)),
+            config
+                .rows
+                .saturating_sub(2)
+                .clamp(1, level.height().max(1)),
+        );
+
+        Self::with_view(config, level, wrap, view)
+    }
+
+    fn with_view(config: &GameConfig, level: Level, wrap: bool, view: Point) -> Self {
+        let config = GameConfig {
+            level: None,
+            mode: match wrap {
+                true => GameMode::Wrap,
+                false => GameMode::Walls,
+            },
+            ..config.clone()
+        };
+
+        let camera = Point::new(0, 0);
+        let world = Self::create_world(&config, &level, camera, view);
+
+        // Scores go in the corners, leaving room on the right for them to
+        // reach three digits
+        let scores = PLAYER_COLORS
+            .iter()
+            .enumerate()
+            .map(|(slot, &(_, color))| {
+                Text::default()
+                    .at_position((
+                        match slot % 2 {
+                            0 => 2,
+                            _ => config.columns.saturating_sub(14),
+                        },
+                        match slot / 2 {
+                            0 => 0,
+                            _ => config.rows.saturating_sub(1),
+                        },
+                    ))
+                    .with_fg(color)
+            })
+            .collect();
+
+        let mut board = Self {
+            round_text: Text::default()
+                .center((config.columns / 2, 0))
+                .with_fg(Color::Yellow)
+                .show(),
+            state_text: Text::default().center(world.get_center_position()),
+            snapshot: Snapshot::default(),
+            scores,
+            config,
+            level,
+            camera,
+            view,
+            world,
+            highlight: None,
+        };
+
+        board.update(Snapshot::default());
+        board
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// The whole level, even when the camera only shows part of it.
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.world.wraps()
+    }
+
+    /// Makes the player's snake stand out by dimming everyone else's.
+    pub fn highlight(&mut self, id: Option<usize>) {
+        self.highlight = id;
+    }
+
+    /// Moves the camera to keep `tile` as close to the middle of the view as
+    /// the edges of the level allow.
+    pub fn follow(&mut self, (x, y): Tile) {
+        let camera = Point::new(
+            x.saturating_sub(self.view.x / 2)
+                .min(self.level.width().saturating_sub(self.view.x)),
+            y.saturating_sub(self.view.y / 2)
+                .min(self.level.height().saturating_sub(self.view.y)),
+        );
+
+        if camera != self.camera {
+            self.camera = camera;
+            self.world = Self::create_world(&self.config, &self.level, camera, self.view);
+        }
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    pub fn update(&mut self, snapshot: Snapshot) {
+        for (slot, score) in self.scores.iter_mut().enumerate() {
+            let player = snapshot.players.iter().find(|player| player.slot == slot);
+            score.visible = player.is_some();
+            if let Some(player) = player {
+                score.update_value(format!(" {}: {} ", player.name, player.score));
+            }
+        }
+
+        let rounds = snapshot
+            .players
+            .iter()
+            .map(|player| format!("{} {}", player.name, player.rounds))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        self.round_text.visible = snapshot.round > 0;
+        self.round_text
+            .update_value(format!(" ROUND {}  {rounds} ", snapshot.round));
+
+        let winner = snapshot
+            .winner
+            .and_then(|id| snapshot.players.iter().find(|player| player.id == id));
+
+        let state = match (snapshot.phase, winner) {
+            (Phase::Waiting, _) => Some(WAITING_TEXT.to_string()),
+            (Phase::Playing, _) => None,
+            (Phase::RoundOver, _) if snapshot.round == 0 => Some(GAME_OVER_TEXT.to_string()),
+            (Phase::RoundOver, Some(winner)) => Some(format!("{} WINS THE ROUND", winner.name)),
+            (Phase::RoundOver, None) => Some(DRAW_TEXT.to_string()),
+        };
+
+        self.state_text.visible = state.is_some();
+        if let Some(state) = state {
+            self.state_text.update_value(state);
+        }
+
+        self.snapshot = snapshot;
+    }
+
+    /// A world for the part of the level the camera shows.
+    fn create_world(config: &GameConfig, level: &Level, camera: Point, view: Point) -> World {
+        let level = match view == Point::new(level.width(), level.height()) {
+            true => level.clone(),
+            false => {
+                let mut cropped = Level::empty(view.x, view.y);
+                for &wall in level.walls() {
+                    if let Some(tile) = Self::in_view(camera, view, wall) {
+                        cropped.set_wall(tile, true);
+                    }
+                }
+
+                cropped
+            }
+        };
+
+        let config = GameConfig {
+            level: Some(level),
+            ..config.clone()
+        };
+
+        World::new(&config, Point::new(0, 0))
+    }
+
+    /// Where a tile of the level is within the view, if it's in it at all.
+    fn in_view(camera: Point, view: Point, tile: Point) -> Option<Point> {
+        let visible = (camera.x..camera.x + view.x).contains(&tile.x)
+            && (camera.y..camera.y + view.y).contains(&tile.y);
+
+        visible.then(|| Point::new(tile.x - camera.x, tile.y - camera.y))
+    }
+
+    fn tile(&self, (x, y): Tile) -> Option<Point> {
+        Self::in_view(self.camera, self.view, Point::new(x, y))
+            .map(|tile| self.world.to_position(tile))
+    }
+}
+
+/// The darker shade of a player's color.
+fn dim(color: Color) -> Color {
+    match color {
+        Color::Green => Color::DarkGreen,
+        Color::Blue => Color::DarkBlue,
+        Color::Yellow => Color::DarkYellow,
+        Color::Magenta => Color::DarkMagenta,
+        _ => Color::DarkGrey,
+    }
+}
+
+impl Entity for Board {
+    type Input = PlayerInput;
+
+    fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        let food = self
+            .snapshot
+            .food
+            .and_then(|food| self.tile(food))
+            .map(|position| DrawInstruction::Text {
+                content: self.snapshot.food_kind.glyph(),
+                position,
+                style: Style {
+                    fg: self.snapshot.food_kind.color(),
+                    ..Style::default()
+                },
+            });
+
+        // The highlighted snake is drawn last so it's on top of the others
+        let mut players: Vec<_> = self.snapshot.players.iter().collect();
+        players.sort_by_key(|player| Some(player.id) == self.highlight);
+
+        let snakes = players.into_iter().flat_map(|player| {
+            let color = PLAYER_COLORS[player.slot % PLAYER_COLORS.len()].1;
+            let color = match self.highlight {
+                Some(id) if id != player.id => dim(color),
+                _ => color,
+            };
+
+            player
+                .snake
+                .iter()
+                .flat_map(|snake| &snake.body)
+                .filter_map(|&tile| self.tile(tile))
+                .map(move |position| DrawInstruction::Text {
+                    content: "██",
+                    position,
+                    style: Style {
+                        fg: color,
+                        ..Style::default()
+                    },
+                })
+        });
+
+        food.into_iter()
+            .chain(self.world.draw())
+            .chain(snakes)
+            .chain(self.scores.iter().flat_map(|score| score.draw()))
+            .chain(self.round_text.draw())
+            .chain(self.state_text.draw())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_config;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            rows: 12,
+            columns: 40,
+            ..test_config()
+        }
+    }
+
+    #[test]
+    fn it_shows_levels_that_fit_in_full() {
+        let board = Board::with_camera(&config(), Level::empty(10, 5), false);
+
+        assert!(board.tile((0, 0)).is_some());
+        assert!(board.tile((9, 4)).is_some());
+    }
+
+    #[test]
+    fn it_follows_a_tile_around_levels_bigger_than_the_screen() {
+        let mut board = Board::with_camera(&config(), Level::empty(38, 22), false);
+        assert!(board.tile((30, 20)).is_none());
+
+        // The view is 18x10 tiles, which is kept inside the level's edges
+        board.follow((30, 20));
+        assert_eq!(board.camera, Point::new(20, 12));
+        assert!(board.tile((30, 20)).is_some());
+        assert!(board.tile((0, 0)).is_none());
+
+        board.follow((1, 1));
+        assert_eq!(board.camera, Point::new(0, 0));
+    }
+}
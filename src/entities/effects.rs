@@ -0,0 +1,201 @@
+use crossterm::style::Color;
+use std::time::Duration;
+
+use crate::{
+    PlayerInput,
+    engine::{
+        draw::{DrawInstruction, Style},
+        entity::Entity,
+        point::Point,
+    },
+};
+
+/// Something eating food does to the snake for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    SlowMotion,
+    SpeedBoost,
+    /// The snake can pass through itself.
+    Ghost,
+    /// Points scored are doubled.
+    Multiplier,
+}
+
+impl Effect {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::SlowMotion => "SLOW",
+            Self::SpeedBoost => "FAST",
+            Self::Ghost => "GHOST",
+            Self::Multiplier => "x2",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::SlowMotion => Color::Blue,
+            Self::SpeedBoost => Color::DarkYellow,
+            Self::Ghost => Color::White,
+            Self::Multiplier => Color::Cyan,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Ghost => Duration::from_secs(5),
+            Self::Multiplier => Duration::from_secs(10),
+            Self::SlowMotion | Self::SpeedBoost => Duration::from_secs(6),
+        }
+    }
+
+    /// The effect that's cancelled when this one starts.
+    fn opposite(&self) -> Option<Self> {
+        match self {
+            Self::SlowMotion => Some(Self::SpeedBoost),
+            Self::SpeedBoost => Some(Self::SlowMotion),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveEffect {
+    effect: Effect,
+    time_left: Duration,
+    content: String,
+}
+
+/// The effects the snake is under, drawn as a row of labels counting down
+/// the seconds each has left.
+#[derive(Debug, Clone)]
+pub struct Effects {
+    active: Vec<ActiveEffect>,
+    position: Point,
+}
+
+impl Effects {
+    pub fn new(position: Point) -> Self {
+        Self {
+            active: vec![],
+            position,
+        }
+    }
+
+    pub fn move_to(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    /// Starts the effect, or restarts its timer if it's already going.
+    pub fn start(&mut self, effect: Effect) {
+        self.active
+            .retain(|active| active.effect != effect && Some(active.effect) != effect.opposite());
+
+        self.active.push(ActiveEffect {
+            effect,
+            time_left: effect.duration(),
+            content: String::new(),
+        });
+
+        self.update_labels();
+    }
+
+    pub fn is_active(&self, effect: Effect) -> bool {
+        self.active.iter().any(|active| active.effect == effect)
+    }
+
+    /// How much faster or slower than usual the snake moves.
+    pub fn speed_factor(&self) -> f32 {
+        if self.is_active(Effect::SlowMotion) {
+            0.5
+        } else if self.is_active(Effect::SpeedBoost) {
+            1.5
+        } else {
+            1.0
+        }
+    }
+
+    /// What the points for eating food are multiplied by.
+    pub fn multiplier(&self) -> u32 {
+        match self.is_active(Effect::Multiplier) {
+            true => 2,
+            false => 1,
+        }
+    }
+
+    fn update_labels(&mut self) {
+        for active in &mut self.active {
+            let seconds = active.time_left.as_secs_f32().ceil();
+            active.content = format!(" {} {seconds}s ", active.effect.label());
+        }
+    }
+}
+
+impl Entity for Effects {
+    type Input = PlayerInput;
+
+    fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        let mut x = self.position.x;
+
+        self.active
+            .iter()
+            .map(|active| {
+                let position = Point::new(x, self.position.y);
+                x += active.content.chars().count();
+
+                DrawInstruction::Text {
+                    content: &active.content,
+                    position,
+                    style: Style {
+                        fg: active.effect.color(),
+                        ..Style::default()
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) {
+        for active in &mut self.active {
+            active.time_left = active.time_left.saturating_sub(*elapsed);
+        }
+
+        self.active.retain(|active| !active.time_left.is_zero());
+        self.update_labels();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_down_each_effect() {
+        let mut effects = Effects::new(Point::new(0, 0));
+        effects.start(Effect::Ghost);
+        effects.start(Effect::Multiplier);
+
+        effects.update(&Duration::from_millis(1500));
+        assert!(effects.is_active(Effect::Ghost));
+        assert_eq!(effects.multiplier(), 2);
+        assert!(matches!(
+            effects.draw()[..],
+            [DrawInstruction::Text { content: " GHOST 4s ", .. }, DrawInstruction::Text { content: " x2 9s ", position, .. }] if position.x == 10
+        ));
+
+        effects.update(&Duration::from_secs(4));
+        assert!(!effects.is_active(Effect::Ghost));
+        assert_eq!(effects.draw().len(), 1);
+    }
+
+    #[test]
+    fn it_cancels_the_opposite_speed_effect() {
+        let mut effects = Effects::new(Point::new(0, 0));
+
+        effects.start(Effect::SlowMotion);
+        assert_eq!(effects.speed_factor(), 0.5);
+
+        effects.start(Effect::SpeedBoost);
+        assert_eq!(effects.speed_factor(), 1.5);
+        assert!(!effects.is_active(Effect::SlowMotion));
+    }
+}
@@ -1,42 +1,283 @@
 use crossterm::style::Color;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
 
 use crate::{
     engine::{
+        draw::{DrawInstruction, Style},
+        entity::Entity,
         point::Point,
-        renderer::{DrawInstruction, Style},
-        traits::Entity,
     },
     PlayerInput,
 };
 
-#[derive(Debug)]
+use super::effects::Effect;
+
+/// How long food with a lifetime blinks for before it disappears.
+const BLINK_TIME: Duration = Duration::from_secs(2);
+
+/// Every kind of food that can be placed in the world.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FoodKind {
+    /// Grows the snake and scores a point. The only food in a classic game.
+    #[default]
+    Apple,
+    /// Worth several apples, but only for a few seconds.
+    Bonus,
+    /// Takes a few parts off the end of the snake.
+    Shrink,
+    SlowMotion,
+    SpeedBoost,
+    /// Lets the snake pass through itself for a while.
+    Ghost,
+    /// Doubles the points scored for a while.
+    Multiplier,
+}
+
+impl FoodKind {
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            Self::Apple => "⬤",
+            Self::Bonus => "★",
+            Self::Shrink => "▼",
+            Self::SlowMotion => "◐",
+            Self::SpeedBoost => "»",
+            Self::Ghost => "◌",
+            Self::Multiplier => "×",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Apple => Color::Red,
+            Self::Bonus => Color::Yellow,
+            Self::Shrink => Color::Magenta,
+            Self::SlowMotion => Color::Blue,
+            Self::SpeedBoost => Color::DarkYellow,
+            Self::Ghost => Color::White,
+            Self::Multiplier => Color::Cyan,
+        }
+    }
+
+    /// How long the food stays in the world before it's replaced, `None` for
+    /// food that waits until it's eaten.
+    pub fn lifetime(&self) -> Option<Duration> {
+        match self {
+            Self::Apple => None,
+            Self::Bonus => Some(Duration::from_secs(6)),
+            _ => Some(Duration::from_secs(10)),
+        }
+    }
+
+    /// The points scored for eating the food, before any multiplier.
+    pub fn points(&self) -> u32 {
+        match self {
+            Self::Bonus => 5,
+            _ => 1,
+        }
+    }
+
+    /// Whether eating the food grows the snake.
+    pub fn grows(&self) -> bool {
+        matches!(self, Self::Apple | Self::Bonus)
+    }
+
+    /// How many parts eating the food takes off the snake.
+    pub fn shrinks(&self) -> usize {
+        match self {
+            Self::Shrink => 3,
+            _ => 0,
+        }
+    }
+
+    /// The effect eating the food starts.
+    pub fn effect(&self) -> Option<Effect> {
+        match self {
+            Self::SlowMotion => Some(Effect::SlowMotion),
+            Self::SpeedBoost => Some(Effect::SpeedBoost),
+            Self::Ghost => Some(Effect::Ghost),
+            Self::Multiplier => Some(Effect::Multiplier),
+            _ => None,
+        }
+    }
+}
+
+/// How likely each kind of food is to be placed, relative to the others.
+/// Kinds that aren't listed are never placed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SpawnTable(BTreeMap<FoodKind, u32>);
+
+impl Default for SpawnTable {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (FoodKind::Apple, 20),
+            (FoodKind::Bonus, 4),
+            (FoodKind::Shrink, 2),
+            (FoodKind::SlowMotion, 2),
+            (FoodKind::SpeedBoost, 2),
+            (FoodKind::Ghost, 1),
+            (FoodKind::Multiplier, 1),
+        ]))
+    }
+}
+
+impl SpawnTable {
+    /// The sum of the weights, which is wider than each weight so large
+    /// ones can't overflow it.
+    pub fn total_weight(&self) -> u64 {
+        self.0.values().copied().map(u64::from).sum()
+    }
+
+    /// Picks a kind of food, falling back to an apple when every weight is 0.
+    pub fn pick(&self, rng: &mut impl Rng) -> FoodKind {
+        let total = self.total_weight();
+        if total == 0 {
+            return FoodKind::Apple;
+        }
+
+        let mut roll = rng.random_range(0..total);
+        for (&kind, &weight) in &self.0 {
+            let weight = u64::from(weight);
+            if roll < weight {
+                return kind;
+            }
+
+            roll -= weight;
+        }
+
+        FoodKind::Apple
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Food {
     position: Point,
+    kind: FoodKind,
+    /// How long is left before the food disappears.
+    time_left: Option<Duration>,
 }
 
 impl Food {
     pub fn new(position: Point) -> Self {
+        Self::of_kind(position, FoodKind::Apple)
+    }
+
+    pub fn of_kind(position: Point, kind: FoodKind) -> Self {
         Self {
             position: position * Point::new(2, 1),
+            kind,
+            time_left: kind.lifetime(),
         }
     }
 
     pub fn get_position(&self) -> Point {
         self.position
     }
+
+    pub fn kind(&self) -> FoodKind {
+        self.kind
+    }
+
+    /// True once the food's lifetime has run out.
+    pub fn is_expired(&self) -> bool {
+        self.time_left.is_some_and(|time_left| time_left.is_zero())
+    }
 }
 
 impl Entity for Food {
     type Input = PlayerInput;
 
     fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        // Food that's about to disappear blinks a few times a second
+        if let Some(time_left) = self.time_left
+            && time_left < BLINK_TIME
+            && time_left.as_millis() / 250 % 2 == 1
+        {
+            return vec![];
+        }
+
         vec![DrawInstruction::Text {
-            content: "⬤",
+            content: self.kind.glyph(),
             position: self.position,
             style: Style {
-                fg: Color::Red,
+                fg: self.kind.color(),
                 ..Style::default()
             },
         }]
     }
+
+    fn update(&mut self, elapsed: &Duration) {
+        if let Some(time_left) = &mut self.time_left {
+            *time_left = time_left.saturating_sub(*elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn it_expires_once_its_lifetime_runs_out() {
+        let mut food = Food::of_kind(Point::new(1, 1), FoodKind::Bonus);
+
+        food.update(&Duration::from_secs(5));
+        assert!(!food.is_expired());
+        assert!(!food.draw().is_empty());
+
+        food.update(&Duration::from_secs(1));
+        assert!(food.is_expired());
+    }
+
+    #[test]
+    fn it_keeps_apples_until_they_are_eaten() {
+        let mut food = Food::new(Point::new(1, 1));
+        food.update(&Duration::from_secs(600));
+        assert!(!food.is_expired());
+    }
+
+    #[test]
+    fn it_picks_kinds_by_weight() {
+        let table = SpawnTable(BTreeMap::from([
+            (FoodKind::Apple, 3),
+            (FoodKind::Ghost, 1),
+            (FoodKind::Bonus, 0),
+        ]));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let picks: Vec<_> = (0..400).map(|_| table.pick(&mut rng)).collect();
+        let ghosts = picks
+            .iter()
+            .filter(|&&kind| kind == FoodKind::Ghost)
+            .count();
+
+        assert!(!picks.contains(&FoodKind::Bonus));
+        assert!((60..140).contains(&ghosts), "{ghosts} ghosts");
+    }
+
+    #[test]
+    fn it_adds_up_weights_too_large_for_one_weight() {
+        let table: SpawnTable = toml::from_str("apple = 4000000000\nbonus = 4000000000").unwrap();
+        assert_eq!(table.total_weight(), 8_000_000_000);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let picks: Vec<_> = (0..100).map(|_| table.pick(&mut rng)).collect();
+        assert!(picks.contains(&FoodKind::Apple) && picks.contains(&FoodKind::Bonus));
+    }
+
+    #[test]
+    fn it_reads_weights_by_name() {
+        let table: SpawnTable = toml::from_str("apple = 5\nspeed-boost = 1").unwrap();
+        assert_eq!(table.total_weight(), 6);
+        assert_eq!(
+            toml::to_string(&table).unwrap(),
+            "apple = 5\nspeed-boost = 1\n"
+        );
+    }
 }
@@ -0,0 +1,112 @@
+use crossterm::style::Color;
+
+use crate::engine::{
+    point::Point,
+    renderer::{DrawInstruction, Style},
+    traits::Entity,
+};
+
+use super::text::Text;
+
+const SELECTED_STYLE: Style = Style {
+    fg: Color::Black,
+    bg: Color::Yellow,
+};
+
+const UNSELECTED_STYLE: Style = Style {
+    fg: Color::Yellow,
+    bg: Color::Reset,
+};
+
+/// A vertical list of options, one of which is highlighted.
+#[derive(Debug)]
+pub struct Menu {
+    options: Vec<Text>,
+    selected_index: usize,
+    pub visible: bool,
+}
+
+impl Menu {
+    /// Creates a menu with each option centered on `center`, starting at its
+    /// row and moving down.
+    pub fn new<T: ToString>(options: impl IntoIterator<Item = T>, center: Point) -> Self {
+        let options = options
+            .into_iter()
+            .enumerate()
+            .map(|(i, option)| {
+                Text::default()
+                    .with_value(option.to_string())
+                    .center(center + Point::new(0, i))
+                    .show()
+            })
+            .collect();
+
+        let mut menu = Self {
+            options,
+            selected_index: 0,
+            visible: true,
+        };
+
+        menu.update_styles();
+        menu
+    }
+
+    pub fn hide(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected_index = index.min(self.options.len().saturating_sub(1));
+        self.update_styles();
+    }
+
+    pub fn update_option<T: ToString>(&mut self, index: usize, option: T) {
+        if let Some(text) = self.options.get_mut(index) {
+            text.update_value(option.to_string());
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_index = self
+            .selected_index
+            .wrapping_sub(1)
+            .clamp(0, self.options.len() - 1);
+
+        self.update_styles();
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected_index = (self.selected_index + 1) % self.options.len();
+        self.update_styles();
+    }
+
+    fn update_styles(&mut self) {
+        for (i, option) in self.options.iter_mut().enumerate() {
+            option.style = if self.selected_index == i {
+                SELECTED_STYLE
+            } else {
+                UNSELECTED_STYLE
+            };
+        }
+    }
+}
+
+impl Entity for Menu {
+    type Input = ();
+
+    fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        if !self.visible {
+            return vec![];
+        }
+
+        self.options
+            .iter()
+            .flat_map(|option| option.draw())
+            .collect()
+    }
+}
@@ -1,5 +1,6 @@
-pub mod food;
+pub mod arena;
+pub mod board;
+pub use ::snake::entities::{food, snake, world};
+pub mod menu;
 pub mod score;
-pub mod snake;
 pub mod text;
-pub mod world;
@@ -1,3 +1,5 @@
+use crossterm::style::Color;
+
 use crate::{
     engine::{
         point::Point,
@@ -10,27 +12,65 @@ use crate::{
 #[derive(Debug)]
 pub struct Score {
     value: u32,
+    label: &'static str,
     content: String,
     position: Point,
+    style: Style,
 }
 
 impl Score {
     pub fn new(position: Point) -> Self {
         let value = 0;
+        let label = "Score";
         Self {
             value,
-            content: Self::format_score(value),
+            label,
+            content: Self::format_score(label, value),
             position,
+            style: Style::default(),
         }
     }
 
+    /// Shows `label` in place of "Score", such as to tell players apart.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self.content = Self::format_score(label, self.value);
+        self
+    }
+
+    pub fn with_fg(mut self, color: Color) -> Self {
+        self.style.fg = color;
+        self
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// How many columns the score takes up.
+    pub fn width(&self) -> usize {
+        self.content.chars().count()
+    }
+
     pub fn increment(&mut self) {
-        self.value += 1;
-        self.content = Self::format_score(self.value);
+        self.add(1);
+    }
+
+    pub fn add(&mut self, points: u32) {
+        self.set(self.value + points);
+    }
+
+    pub fn set(&mut self, value: u32) {
+        self.value = value;
+        self.content = Self::format_score(self.label, self.value);
+    }
+
+    pub fn move_to(&mut self, position: Point) {
+        self.position = position;
     }
 
-    fn format_score(value: u32) -> String {
-        format!(" Score: {value} ")
+    fn format_score(label: &str, value: u32) -> String {
+        format!(" {label}: {value} ")
     }
 }
 
@@ -41,7 +81,7 @@ impl Entity for Score {
         vec![DrawInstruction::Text {
             content: &self.content,
             position: self.position,
-            style: Style::default(),
+            style: self.style,
         }]
     }
 }
@@ -1,53 +1,91 @@
 use crossterm::style::Color;
-use std::{iter::repeat_with, time::Duration};
+use std::{collections::VecDeque, time::Duration};
 
 use crate::{
     PlayerInput, SnakeStyle,
-    config::SnakeConfig,
     engine::{
+        draw::{DrawInstruction, Style},
+        entity::Entity,
         point::{Point, Vector},
-        renderer::{DrawInstruction, Style},
-        traits::Entity,
     },
+    game::SnakeConfig,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Snake {
     body: Vec<Point>,
     size: usize,
     velocity: Vector,
+    turns: VecDeque<Vector>,
     speed: f32,
+    /// Scales `speed`, such as while a power-up slows the snake down.
+    speed_factor: f32,
     movement_progress: f32,
     color: Color,
     color_time: Duration,
+    wrap_area: Option<(Point, Point)>,
     config: SnakeConfig,
 }
 
 impl Snake {
-    pub fn new<T: Into<Point>>(head: T, config: &SnakeConfig) -> Self {
-        let head: Point = head.into();
-        let body = repeat_with(|| head)
-            .enumerate()
-            .map(|(index, point)| point + Point::new((config.size - index) * 2, 0))
-            .take(config.size)
+    /// Creates a snake with its head at `head` moving along `velocity`, and
+    /// the rest of its body trailing behind it.
+    pub fn spawn(head: Point, velocity: Vector, config: &SnakeConfig) -> Self {
+        let body = (0..config.size as isize)
+            .map(|index| head + Vector::new(-velocity.x * index, -velocity.y * index))
             .collect();
 
         Self {
             body,
             size: config.size,
             speed: config.speed,
-            velocity: Vector::new(2, 0),
+            speed_factor: 1.0,
+            velocity,
+            turns: VecDeque::with_capacity(config.turn_queue_depth),
             movement_progress: 0.0,
             color: config.style.initial_color(),
             color_time: Duration::from_secs(0),
+            wrap_area: None,
             config: config.clone(),
         }
     }
 
+    /// Makes the snake come back on the opposite edge when it leaves the area
+    /// from `min` (inclusive) to `max` (exclusive).
+    pub fn with_wrap_area(mut self, min: Point, max: Point) -> Self {
+        self.wrap_area = Some((min, max));
+        self
+    }
+
+    pub fn length(&self) -> usize {
+        self.body.len()
+    }
+
+    /// The length the snake is growing to, which can be ahead of `length`
+    /// right after eating.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
     pub fn head(&self) -> Point {
         self.body[0]
     }
 
+    /// Every part of the snake, starting with its head.
+    pub fn body(&self) -> &[Point] {
+        &self.body
+    }
+
+    /// The direction the snake will be moving once every queued turn has
+    /// been applied.
+    pub fn heading(&self) -> Vector {
+        self.turns.back().copied().unwrap_or(self.velocity)
+    }
+
     pub fn detect_head_collision(&self, point: Point) -> bool {
         self.head() == point
     }
@@ -66,6 +104,53 @@ impl Snake {
     pub fn grow(&mut self, amount: usize) {
         self.size += amount;
     }
+
+    /// Takes `amount` parts off the end of the snake, without going below the
+    /// length it started at.
+    pub fn shrink(&mut self, amount: usize) {
+        self.size = self.size.saturating_sub(amount).max(self.config.size);
+        self.body.truncate(self.size);
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    pub fn set_speed_factor(&mut self, factor: f32) {
+        self.speed_factor = factor;
+    }
+
+    /// True while the snake is shorter than the length it's growing to, so
+    /// its tail stays put on its next move.
+    pub fn is_growing(&self) -> bool {
+        self.size != self.body.len()
+    }
+
+    /// Where the head would be after moving one tile along `velocity`.
+    pub fn next_head(&self, velocity: Vector) -> Point {
+        match &self.wrap_area {
+            Some((min, max)) => self.head().wrapping_offset(&velocity, min, max),
+            None => self.head() + velocity,
+        }
+    }
+
+    /// Moves the snake one tile, whatever its speed.
+    pub fn advance(&mut self) {
+        // Only one turn is applied per tile so quick successive turns each
+        // get a tile to happen on
+        if let Some(turn) = self.turns.pop_front() {
+            self.velocity = turn;
+        }
+
+        let head = self.next_head(self.velocity);
+
+        if self.is_growing() {
+            self.body.insert(0, head);
+        } else {
+            self.body.rotate_right(1);
+            self.body[0] = head;
+        }
+    }
 }
 
 impl Entity for Snake {
@@ -87,7 +172,7 @@ impl Entity for Snake {
 
     fn update(&mut self, elapsed: &Duration) {
         self.color_time += *elapsed;
-        self.movement_progress += self.speed * elapsed.as_secs_f32();
+        self.movement_progress += self.speed * self.speed_factor * elapsed.as_secs_f32();
 
         if self.config.style == SnakeStyle::Flash {
             self.color = match (self.color, self.color_time > Duration::from_secs(1)) {
@@ -105,28 +190,124 @@ impl Entity for Snake {
 
         while self.movement_progress > 1.0 {
             self.movement_progress -= 1.0;
-
-            let head = self.head();
-
-            if self.size != self.body.len() {
-                self.body.insert(0, head);
-            } else {
-                self.body.rotate_right(1);
-            }
-
-            self.body[0] = head + self.velocity;
+            self.advance();
         }
     }
 
     fn process_input(&mut self, input: &Self::Input) {
+        if self.turns.len() >= self.config.turn_queue_depth {
+            return;
+        }
+
+        // Turns are checked against the direction the snake will be moving
+        // once every queued turn has been applied
+        let direction = self.heading();
+
         // "squares" are 2x1 since fonts are taller than they are wide so we need to
         // move double the distance when going east or west
-        self.velocity = match input {
-            PlayerInput::Up if self.velocity.y == 0 => Vector::new(0, -1),
-            PlayerInput::Down if self.velocity.y == 0 => Vector::new(0, 1),
-            PlayerInput::Right if self.velocity.x == 0 => Vector::new(2, 0),
-            PlayerInput::Left if self.velocity.x == 0 => Vector::new(-2, 0),
-            _ => self.velocity,
+        let turn = match input {
+            PlayerInput::Up if direction.y == 0 => Vector::new(0, -1),
+            PlayerInput::Down if direction.y == 0 => Vector::new(0, 1),
+            PlayerInput::Right if direction.x == 0 => Vector::new(2, 0),
+            PlayerInput::Left if direction.x == 0 => Vector::new(-2, 0),
+            _ => return,
         };
+
+        self.turns.push_back(turn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: SnakeConfig = SnakeConfig {
+        grow_rate: 1,
+        speed: 10.0,
+        size: 3,
+        turn_queue_depth: 2,
+        style: SnakeStyle::Green,
+    };
+
+    // Slightly over a tenth of a second so each update moves exactly one tile
+    const ONE_TILE: Duration = Duration::from_millis(101);
+
+    fn create_snake() -> Snake {
+        Snake::spawn(Point::new(16, 10), Vector::new(2, 0), &CONFIG)
+    }
+
+    #[test]
+    fn it_applies_one_queued_turn_per_tile() {
+        let mut snake = create_snake();
+        let start = snake.head();
+
+        snake.process_input(&PlayerInput::Up);
+        snake.process_input(&PlayerInput::Left);
+
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start + Vector::new(0, -1));
+
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start + Vector::new(-2, -1));
+        assert!(!snake.detect_self_collision());
+    }
+
+    #[test]
+    fn it_checks_turns_against_the_last_queued_direction() {
+        let mut snake = create_snake();
+        let start = snake.head();
+
+        // Down is rejected since it would reverse the queued Up
+        snake.process_input(&PlayerInput::Up);
+        snake.process_input(&PlayerInput::Down);
+
+        snake.update(&ONE_TILE);
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start + Vector::new(0, -2));
+    }
+
+    #[test]
+    fn it_drops_turns_once_the_queue_is_full() {
+        let mut snake = create_snake();
+        let start = snake.head();
+
+        snake.process_input(&PlayerInput::Up);
+        snake.process_input(&PlayerInput::Right);
+        snake.process_input(&PlayerInput::Down);
+
+        snake.update(&ONE_TILE);
+        snake.update(&ONE_TILE);
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start + Vector::new(4, -1));
+    }
+
+    #[test]
+    fn it_shrinks_down_to_its_starting_length() {
+        let mut snake = create_snake();
+        snake.grow(4);
+        for _ in 0..4 {
+            snake.update(&ONE_TILE);
+        }
+        assert_eq!(snake.length(), 7);
+
+        snake.shrink(3);
+        assert_eq!(snake.length(), 4);
+
+        snake.shrink(3);
+        assert_eq!(snake.length(), CONFIG.size);
+        assert_eq!(snake.size(), CONFIG.size);
+    }
+
+    #[test]
+    fn it_scales_its_speed() {
+        let mut snake = create_snake();
+        let start = snake.head();
+
+        snake.set_speed_factor(0.5);
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start);
+
+        snake.update(&ONE_TILE);
+        assert_eq!(snake.head(), start + Vector::new(2, 0));
     }
 }
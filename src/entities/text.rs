@@ -82,7 +82,7 @@ impl Text {
     }
 
     fn calcuate_center(&self, center_point: Point) -> Point {
-        center_point - Point::new(self.longest_width / 2, 0)
+        center_point.saturating_sub(&Point::new(self.longest_width / 2, 0))
     }
 }
 
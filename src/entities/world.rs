@@ -1,48 +1,158 @@
-use rand::prelude::*;
+use crossterm::style::Color;
+use rand::{SeedableRng, prelude::*, rngs::StdRng};
 use std::cell::RefCell;
 
 use crate::{
-    GameConfig, PlayerInput,
-    engine::{point::Point, renderer::DrawInstruction, traits::Entity},
+    GameConfig, GameMode, PlayerInput,
+    engine::{
+        draw::{DrawInstruction, Style},
+        entity::Entity,
+        point::Point,
+    },
+    game::SnakeConfig,
+    level::Level,
 };
 
-use super::snake::Snake;
+use super::{
+    food::{FoodKind, SpawnTable},
+    snake::Snake,
+};
+
+const WALL: &str = "██";
+
+/// Where the level's grid starts relative to the world's origin, leaving room
+/// for the border.
+const GRID_OFFSET: Point = Point { x: 2, y: 1 };
 
 // TODO make world an entity manager
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct World {
     config: GameConfig,
+    level: Level,
     origin: Point,
     diagonal: Point,
     show_border: bool,
-    rng: RefCell<ThreadRng>,
+    walls: Vec<(Point, String)>,
+    seed: u64,
+    rng: RefCell<StdRng>,
 }
 
 impl World {
+    /// Creates a world for the config's level, centered in the config's
+    /// columns and rows. Without a level, an empty one fills the whole area.
     pub fn new(config: &GameConfig, origin: Point) -> Self {
-        let diagonal = Point::new(config.columns - origin.x, config.rows - origin.y);
-        Self {
+        let area = Point::new(config.columns, config.rows).saturating_sub(&origin);
+        let level = config.level.clone().unwrap_or_else(|| {
+            let grid = area.saturating_sub(&(GRID_OFFSET * Point::new(2, 2)));
+            Level::empty(grid.x / 2, grid.y)
+        });
+
+        let size = Point::new(level.width() * 2, level.height()) + GRID_OFFSET * Point::new(2, 2);
+        let margin = area.saturating_sub(&size);
+
+        // Tiles need to start on an even column to line up with the food
+        let origin = origin + Point::new(margin.x / 4 * 2, margin.y / 2);
+        let seed = config.seed.unwrap_or_else(rand::random);
+
+        let mut world = Self {
             config: config.clone(),
+            diagonal: origin + size,
             origin,
-            diagonal,
-            show_border: config.show_border,
-            rng: RefCell::new(rand::rng()),
-        }
+            show_border: config.show_border || config.level.is_some(),
+            walls: vec![],
+            level,
+            seed,
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        };
+
+        world.walls = world.wall_runs();
+        world
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// True if snakes leaving one edge come back on the opposite edge.
+    pub fn wraps(&self) -> bool {
+        self.config.mode == GameMode::Wrap
+    }
+
+    /// The terminal size needed to show the whole world.
+    pub fn min_size(&self) -> (usize, usize) {
+        (
+            self.diagonal.x.max(self.config.columns),
+            self.diagonal.y.max(self.config.rows),
+        )
+    }
+
+    /// The seed used for food placement. Passing it back in through
+    /// `GameConfig::seed` reproduces the same sequence of positions.
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
 
     pub fn detect_collision(&self, point: Point) -> bool {
-        point.x <= self.origin.x
-            || point.x >= self.diagonal.x - 2
-            || point.y <= self.origin.y
-            || point.y >= self.diagonal.y - 1
+        match self.to_tile(point) {
+            Some(tile) => self.level.is_wall(tile),
+            None => self.config.mode == GameMode::Walls,
+        }
     }
 
+    /// Picks a tile for food that isn't a wall. A world without any free
+    /// tiles returns the first tile rather than panicking.
     pub fn get_random_position(&self) -> Point {
         let mut rng = self.rng.borrow_mut();
-        Point::new(
-            rng.random_range((self.origin.x + 1)..((self.diagonal.x - 1) / 2)),
-            rng.random_range((self.origin.y + 1)..(self.diagonal.y - 1)),
-        )
+        let width = self.level.width().max(1);
+        let height = self.level.height().max(1);
+
+        let mut tile = Point::new(rng.random_range(0..width), rng.random_range(0..height));
+
+        // Picking again from only the free tiles keeps every free tile as
+        // likely as any other
+        if self.level.is_wall(tile) {
+            let free: Vec<_> = self
+                .tiles()
+                .filter(|tile| !self.level.is_wall(*tile))
+                .collect();
+            tile = free.choose(&mut *rng).copied().unwrap_or_default();
+        }
+
+        self.to_food_position(tile)
+    }
+
+    /// Picks a tile for food like `get_random_position`, also avoiding the
+    /// positions `is_occupied` says are taken, such as by snakes. Food only
+    /// goes on an occupied tile when there aren't any others left.
+    pub fn get_free_position(&self, is_occupied: impl Fn(Point) -> bool) -> Point {
+        let position = self.get_random_position();
+        if !is_occupied(position * Point::new(2, 1)) {
+            return position;
+        }
+
+        let free: Vec<_> = self
+            .tiles()
+            .filter(|tile| !self.level.is_wall(*tile) && !is_occupied(self.to_position(*tile)))
+            .collect();
+
+        free.choose(&mut *self.rng.borrow_mut())
+            .map_or(position, |tile| self.to_food_position(*tile))
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = Point> {
+        let width = self.level.width();
+        (0..self.level.height()).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    /// Food is placed by half the column, since tiles are two columns wide.
+    fn to_food_position(&self, tile: Point) -> Point {
+        let position = self.to_position(tile);
+        Point::new(position.x / 2, position.y)
+    }
+
+    /// Picks the kind of food to place next from `table`.
+    pub fn get_random_food(&self, table: &SpawnTable) -> FoodKind {
+        table.pick(&mut *self.rng.borrow_mut())
     }
 
     pub fn get_center_position(&self) -> Point {
@@ -52,8 +162,92 @@ impl World {
         )
     }
 
+    /// The area the snake can move in, from the top left tile (inclusive) to
+    /// the bottom right (exclusive). Tiles are two columns wide so the width
+    /// is always even.
+    pub fn play_area(&self) -> (Point, Point) {
+        let min = self.origin + GRID_OFFSET;
+        let max = min + Point::new(self.level.width() * 2, self.level.height());
+
+        (min, max)
+    }
+
     pub fn create_snake(&self) -> Snake {
-        Snake::new(self.origin + Point::new(2usize, 2), &self.config.snake)
+        self.create_player_snake(0, &self.config.snake)
+    }
+
+    /// Creates the snake for one of several players sharing the world. The
+    /// first player starts at the level's spawn and the others start at its
+    /// reflections, the second one across the center.
+    pub fn create_player_snake(&self, player: usize, config: &SnakeConfig) -> Snake {
+        // Without a spawn point the snake's tail starts in the second row
+        let spawn = self
+            .level
+            .spawn()
+            .unwrap_or_else(|| Point::new(self.config.snake.size, 1));
+
+        let direction = self.level.direction;
+        let mirrored = Point::new(
+            self.level.width().saturating_sub(spawn.x + 1),
+            self.level.height().saturating_sub(spawn.y + 1),
+        );
+
+        let (spawn, direction) = match player % 4 {
+            0 => (spawn, direction),
+            1 => (mirrored, direction.opposite()),
+            2 => (Point::new(spawn.x, mirrored.y), direction),
+            _ => (Point::new(mirrored.x, spawn.y), direction.opposite()),
+        };
+
+        let snake = Snake::spawn(self.to_position(spawn), direction.velocity(), config);
+
+        match self.config.mode {
+            GameMode::Walls => snake,
+            GameMode::Wrap => {
+                let (min, max) = self.play_area();
+                snake.with_wrap_area(min, max)
+            }
+        }
+    }
+
+    /// The screen position of the left column of a level tile.
+    pub fn to_position(&self, tile: Point) -> Point {
+        self.origin + GRID_OFFSET + tile * Point::new(2, 1)
+    }
+
+    /// The level tile at a screen position, if it's inside the level.
+    pub fn to_tile(&self, position: Point) -> Option<Point> {
+        let (min, max) = self.play_area();
+        if position.x < min.x || position.y < min.y || position.x >= max.x || position.y >= max.y {
+            return None;
+        }
+
+        let offset = position - min;
+        Some(Point::new(offset.x / 2, offset.y))
+    }
+
+    /// Joins walls that are next to each other in a row so each run is drawn
+    /// as a single piece of text.
+    fn wall_runs(&self) -> Vec<(Point, String)> {
+        let mut runs: Vec<(Point, Point, String)> = vec![];
+        let mut walls: Vec<_> = self.level.walls().copied().collect();
+        walls.sort_by_key(|tile| (tile.y, tile.x));
+
+        for tile in walls {
+            match runs.last_mut() {
+                Some((_, last, content))
+                    if *last + Point::new(1usize, 0) == tile && last.y == tile.y =>
+                {
+                    *last = tile;
+                    content.push_str(WALL);
+                }
+                _ => runs.push((tile, tile, WALL.to_string())),
+            }
+        }
+
+        runs.into_iter()
+            .map(|(start, _, content)| (self.to_position(start), content))
+            .collect()
     }
 }
 
@@ -61,22 +255,38 @@ impl Entity for World {
     type Input = PlayerInput;
 
     fn draw(&self) -> Vec<DrawInstruction<'_>> {
+        let walls = self
+            .walls
+            .iter()
+            .map(|(position, content)| DrawInstruction::Text {
+                position: *position,
+                content,
+                style: Style {
+                    fg: Color::Grey,
+                    ..Default::default()
+                },
+            });
+
         if !self.show_border {
-            return vec![];
+            return walls.collect();
         }
 
-        vec![DrawInstruction::Rectangle {
+        std::iter::once(DrawInstruction::Rectangle {
             position: self.origin,
-            width: self.diagonal.x - self.origin.x,
-            height: self.diagonal.y - self.origin.y,
+            width: self.diagonal.x.saturating_sub(self.origin.x),
+            height: self.diagonal.y.saturating_sub(self.origin.y),
             style: Default::default(),
-        }]
+        })
+        .chain(walls)
+        .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{SnakeStyle, config::SnakeConfig};
+    use std::time::Duration;
+
+    use crate::SnakeStyle;
 
     use super::*;
 
@@ -85,13 +295,19 @@ mod tests {
             speed: 5.0,
             grow_rate: 1,
             size: 6,
+            turn_queue_depth: 3,
             style: SnakeStyle::Green,
         },
         rows: 6,
         columns: 6,
         frame_rate: 15,
+        mode: GameMode::Walls,
         show_frame_rate: false,
         show_border: false,
+        seed: None,
+        level: None,
+        food: None,
+        curve: None,
     };
 
     #[cfg(test)]
@@ -113,6 +329,233 @@ mod tests {
             assert!(world.detect_collision(Point::new(0, 2)));
             assert!(world.detect_collision(Point::new(5, 2)));
         }
+
+        #[test]
+        fn it_ignores_edges_when_wrapping() {
+            let config = GameConfig {
+                mode: GameMode::Wrap,
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            assert!(!world.detect_collision(Point::new(2, 0)));
+            assert!(!world.detect_collision(Point::new(0, 2)));
+        }
+    }
+
+    #[cfg(test)]
+    mod play_area {
+        use super::*;
+
+        #[test]
+        fn it_matches_the_area_inside_the_walls() {
+            let config = GameConfig {
+                rows: 24,
+                columns: 80,
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            let (min, max) = world.play_area();
+
+            assert_eq!((min, max), (Point::new(2, 1), Point::new(78, 23)));
+            assert!(!world.detect_collision(min));
+            assert!(!world.detect_collision(max - Point::new(2, 1)));
+            assert!(world.detect_collision(Point::new(max.x, min.y)));
+            assert!(world.detect_collision(Point::new(min.x, max.y)));
+        }
+
+        #[test]
+        fn it_wraps_the_snake_to_the_opposite_edge() {
+            let config = GameConfig {
+                rows: 24,
+                columns: 80,
+                mode: GameMode::Wrap,
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            let (min, max) = world.play_area();
+            let mut snake = world.create_snake();
+
+            let mut previous = snake.head();
+            while previous.x != max.x - 2 {
+                snake.update(&Duration::from_millis(250));
+                previous = snake.head();
+                assert!(previous.x < max.x);
+            }
+
+            while snake.head() == previous {
+                snake.update(&Duration::from_millis(250));
+            }
+
+            assert_eq!(snake.head(), Point::new(min.x, previous.y));
+            assert!(!world.detect_collision(snake.head()));
+        }
+    }
+
+    #[cfg(test)]
+    mod get_random_position {
+        use super::*;
+
+        fn food_sequence(world: &World) -> Vec<Point> {
+            (0..10).map(|_| world.get_random_position()).collect()
+        }
+
+        #[test]
+        fn it_repeats_positions_for_the_same_seed() {
+            let config = GameConfig {
+                rows: 20,
+                columns: 40,
+                seed: Some(42),
+                ..CONFIG.clone()
+            };
+
+            let first = World::new(&config, Point::new(0, 0));
+            let second = World::new(&config, Point::new(0, 0));
+
+            assert_eq!(first.seed(), 42);
+            assert_eq!(food_sequence(&first), food_sequence(&second));
+        }
+
+        #[test]
+        fn it_picks_a_seed_when_none_is_given() {
+            let config = GameConfig {
+                rows: 20,
+                columns: 40,
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            let replay = World::new(
+                &GameConfig {
+                    seed: Some(world.seed()),
+                    ..config
+                },
+                Point::new(0, 0),
+            );
+
+            assert_eq!(food_sequence(&world), food_sequence(&replay));
+        }
+    }
+
+    #[cfg(test)]
+    mod levels {
+        use super::*;
+
+        fn create_world(level: &str) -> World {
+            let config = GameConfig {
+                rows: 10,
+                columns: 20,
+                seed: Some(7),
+                level: Some(level.parse().unwrap()),
+                ..CONFIG.clone()
+            };
+
+            World::new(&config, Point::new(0, 0))
+        }
+
+        #[test]
+        fn it_centers_the_level() {
+            let world = create_world("---\n....\n....");
+            assert_eq!(world.play_area(), (Point::new(6, 4), Point::new(14, 6)));
+            assert_eq!(world.min_size(), (20, 10));
+        }
+
+        #[test]
+        fn it_collides_with_walls() {
+            let world = create_world("---\n.#..\n....");
+            let (min, _) = world.play_area();
+
+            assert!(world.detect_collision(min + Point::new(2usize, 0)));
+            assert!(!world.detect_collision(min));
+            assert!(!world.detect_collision(min + Point::new(4usize, 0)));
+        }
+
+        #[test]
+        fn it_never_places_food_on_walls() {
+            let world = create_world("---\n###.\n####");
+            for _ in 0..20 {
+                let food = world.get_random_position() * Point::new(2, 1);
+                assert_eq!(food, world.play_area().0 + Point::new(6usize, 0));
+            }
+        }
+
+        #[test]
+        fn it_spawns_the_snake_facing_the_level_direction() {
+            let world = create_world("direction: down\n---\n....\n.S..");
+            let mut snake = world.create_snake();
+            let head = snake.head();
+
+            assert_eq!(head, world.play_area().0 + Point::new(2usize, 1));
+            snake.update(&Duration::from_millis(250));
+            assert_eq!(snake.head(), head + Point::new(0usize, 1));
+        }
+
+        #[test]
+        fn it_spawns_the_second_player_across_the_center() {
+            let world = create_world("---\n....\n.S..");
+            let mut snake = world.create_player_snake(1, &CONFIG.snake);
+            let head = snake.head();
+
+            assert_eq!(head, world.play_area().0 + Point::new(4usize, 0));
+            snake.update(&Duration::from_millis(250));
+            assert_eq!(snake.head(), head - Point::new(2usize, 0));
+        }
+    }
+
+    #[cfg(test)]
+    mod get_free_position {
+        use super::*;
+
+        #[test]
+        fn it_avoids_occupied_tiles() {
+            let config = GameConfig {
+                seed: Some(3),
+                level: Some("---\n....\n....".parse().unwrap()),
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            let free = world.to_position(Point::new(2, 1));
+
+            for _ in 0..20 {
+                let food = world.get_free_position(|position| position != free);
+                assert_eq!(food * Point::new(2, 1), free);
+            }
+        }
+
+        #[test]
+        fn it_falls_back_to_an_occupied_tile_when_the_world_is_full() {
+            let config = GameConfig {
+                seed: Some(3),
+                level: Some("---\n..".parse().unwrap()),
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            let food = world.get_free_position(|_| true) * Point::new(2, 1);
+            assert!(world.to_tile(food).is_some());
+        }
+    }
+
+    #[cfg(test)]
+    mod small_worlds {
+        use super::*;
+
+        #[test]
+        fn it_places_food_without_panicking() {
+            let config = GameConfig {
+                rows: 2,
+                columns: 3,
+                seed: Some(1),
+                ..CONFIG.clone()
+            };
+
+            let world = World::new(&config, Point::new(0, 0));
+            assert_eq!(world.get_random_position(), Point::new(1, 1));
+            assert!(world.detect_collision(Point::new(1, 1)));
+        }
     }
 
     #[cfg(test)]
@@ -0,0 +1,476 @@
+use anyhow::{Result, bail};
+
+use crate::{
+    GameMode, PlayerInput, SnakeStyle,
+    curve::SpeedCurve,
+    engine::point::Point,
+    entities::{effects::Effect, food::SpawnTable},
+    game::{Game, GameConfig, Outcome, SnakeConfig},
+    level::{Direction, Level},
+};
+
+/// Planes in a grid observation: walls, the snake's body, its head and the
+/// food.
+pub const GRID_CHANNELS: usize = 4;
+
+/// Values in a feature observation.
+pub const FEATURES: usize = 11;
+
+pub const FOOD_REWARD: f32 = 1.0;
+pub const CRASH_REWARD: f32 = -1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObservationKind {
+    /// `GRID_CHANNELS` planes of `height` rows by `width` tiles, with 1.0
+    /// wherever the plane's contents are.
+    Grid,
+
+    /// Whether going straight, left or right would crash, which way the
+    /// snake is heading (up, down, left, right) and which way the food is
+    /// (up, down, left, right), each as 0.0 or 1.0.
+    #[default]
+    Features,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// `[GRID_CHANNELS, height, width]` for grids or `[FEATURES]` for
+    /// features.
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub level: Level,
+    /// True if leaving one edge comes back on the opposite edge.
+    pub wrap: bool,
+    pub size: usize,
+    pub grow_rate: usize,
+    /// Tiles per second. Steps always move one tile, but power-ups run out
+    /// and the speed curve goes up with the time that would take.
+    pub speed: f32,
+    /// How likely each kind of food is to be placed. `None` only places
+    /// apples.
+    pub food: Option<SpawnTable>,
+    /// How the snake speeds up as the episode goes on. `None` keeps it at
+    /// the same speed.
+    pub curve: Option<SpeedCurve>,
+    pub observation: ObservationKind,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            level: Level::empty(20, 20),
+            wrap: false,
+            size: 6,
+            grow_rate: 2,
+            speed: 15.0,
+            food: None,
+            curve: None,
+            observation: ObservationKind::default(),
+        }
+    }
+}
+
+impl EnvConfig {
+    /// The game to play an episode of, sized to fit the level exactly.
+    fn game_config(&self, seed: u64) -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: self.grow_rate,
+                speed: self.speed,
+                size: self.size,
+                turn_queue_depth: 1,
+                style: SnakeStyle::Green,
+            },
+            rows: self.level.height() + 2,
+            columns: self.level.width() * 2 + 4,
+            show_frame_rate: false,
+            show_border: true,
+            frame_rate: 0,
+            mode: match self.wrap {
+                true => GameMode::Wrap,
+                false => GameMode::Walls,
+            },
+            seed: Some(seed),
+            level: Some(self.level.clone()),
+            food: self.food.clone(),
+            curve: self.curve.clone(),
+        }
+    }
+}
+
+/// A game of Snake that moves one tile per step, played by the same rules as
+/// the game: the snake spawns on the level's spawn point, turning back on
+/// itself is ignored, eating grows it and scores and running into a wall, an
+/// edge (unless wrapping) or itself ends the episode. Reaching the level's
+/// target ends it too.
+///
+/// ```
+/// use snake::{env::{Env, EnvConfig}, level::Direction};
+///
+/// let mut env = Env::new(EnvConfig::default()).unwrap();
+/// let observation = env.reset(42);
+/// let (observation, reward, done) = env.step(Direction::Down);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Env {
+    config: EnvConfig,
+    game: Game,
+    done: bool,
+}
+
+impl Env {
+    /// Creates an environment that's ready to step, as if reset with a seed
+    /// of 0.
+    pub fn new(config: EnvConfig) -> Result<Self> {
+        let (width, height) = (config.level.width(), config.level.height());
+        if width == 0 || height == 0 {
+            bail!("Level must have at least one tile, got {width}x{height}");
+        }
+
+        if !(config.speed.is_finite() && config.speed > 0.0) {
+            bail!("Speed must be greater than 0, got {}", config.speed);
+        }
+
+        Ok(Self {
+            game: Game::new(&config.game_config(0)),
+            config,
+            done: false,
+        })
+    }
+
+    /// Starts a new episode. Food is placed from `seed`, so the same seed
+    /// and actions always play out the same way.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(&self.config.game_config(seed));
+        self.done = false;
+        self.observe()
+    }
+
+    /// Turns towards `action` and moves one tile, returning what the snake
+    /// sees after the move, the reward for it and whether the episode is
+    /// over.
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observe(), 0.0, true);
+        }
+
+        self.game.steer(&match action {
+            Direction::Up => PlayerInput::Up,
+            Direction::Down => PlayerInput::Down,
+            Direction::Left => PlayerInput::Left,
+            Direction::Right => PlayerInput::Right,
+        });
+
+        let reward = match self.game.step() {
+            Some(Outcome::Crashed) => {
+                self.done = true;
+                CRASH_REWARD
+            }
+            Some(Outcome::Ate { points, .. }) => {
+                self.done = self.game.is_level_clear();
+                points as f32 * FOOD_REWARD
+            }
+            None => 0.0,
+        };
+
+        (self.observe(), reward, self.done)
+    }
+
+    /// What the snake sees, in the form given by the config.
+    pub fn observe(&self) -> Observation {
+        match self.config.observation {
+            ObservationKind::Grid => self.grid(),
+            ObservationKind::Features => self.features(),
+        }
+    }
+
+    /// The game being played, such as to look at the effects the snake is
+    /// under.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn width(&self) -> usize {
+        self.config.level.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.config.level.height()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.score()
+    }
+
+    pub fn length(&self) -> usize {
+        self.game.snake().length()
+    }
+
+    pub fn direction(&self) -> Direction {
+        Direction::from_velocity(self.game.snake().heading())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The snake's head as `(x, y)`, if it's still on the level.
+    pub fn head_position(&self) -> Option<(usize, usize)> {
+        self.tile(self.game.snake().head())
+    }
+
+    /// The food as `(x, y)`.
+    pub fn food_position(&self) -> Option<(usize, usize)> {
+        self.tile(self.game.food().get_position())
+    }
+
+    fn tile(&self, position: Point) -> Option<(usize, usize)> {
+        self.game
+            .world()
+            .to_tile(position)
+            .map(|tile| (tile.x, tile.y))
+    }
+
+    /// True if moving `direction` would end the episode. The tail doesn't
+    /// count when it's about to move out of the way, and none of the body
+    /// counts while the snake is a ghost.
+    fn is_blocked(&self, direction: Direction) -> bool {
+        let snake = self.game.snake();
+        let next = snake.next_head(direction.velocity());
+        if self.game.world().detect_collision(next) {
+            return true;
+        }
+
+        if self.game.effects().is_active(Effect::Ghost) {
+            return false;
+        }
+
+        let body = match snake.is_growing() {
+            true => snake.body(),
+            false => &snake.body()[..snake.length() - 1],
+        };
+
+        body.contains(&next)
+    }
+
+    fn grid(&self) -> Observation {
+        let (width, height) = (self.width(), self.height());
+        let plane = width * height;
+        let mut data = vec![0.0; GRID_CHANNELS * plane];
+        let index = |(x, y): (usize, usize)| y * width + x;
+
+        for wall in self.config.level.walls() {
+            data[index((wall.x, wall.y))] = 1.0;
+        }
+
+        let body = self.game.snake().body();
+        for (x, y) in body.iter().filter_map(|part| self.tile(*part)) {
+            data[plane + index((x, y))] = 1.0;
+        }
+
+        if let Some(head) = self.head_position() {
+            data[2 * plane + index(head)] = 1.0;
+        }
+
+        if let Some(food) = self.food_position() {
+            data[3 * plane + index(food)] = 1.0;
+        }
+
+        Observation {
+            shape: vec![GRID_CHANNELS, height, width],
+            data,
+        }
+    }
+
+    fn features(&self) -> Observation {
+        let direction = self.direction();
+        let head = self.head_position();
+        let food = self.food_position();
+
+        let flag = |value: bool| if value { 1.0 } else { 0.0 };
+        let food_is = |check: fn((usize, usize), (usize, usize)) -> bool| {
+            flag(head.zip(food).is_some_and(|(head, food)| check(head, food)))
+        };
+
+        let data = vec![
+            flag(self.is_blocked(direction)),
+            flag(self.is_blocked(direction.opposite().rotate())),
+            flag(self.is_blocked(direction.rotate())),
+            flag(direction == Direction::Up),
+            flag(direction == Direction::Down),
+            flag(direction == Direction::Left),
+            flag(direction == Direction::Right),
+            food_is(|(_, y), (_, food_y)| food_y < y),
+            food_is(|(_, y), (_, food_y)| food_y > y),
+            food_is(|(x, _), (food_x, _)| food_x < x),
+            food_is(|(x, _), (food_x, _)| food_x > x),
+        ];
+
+        Observation {
+            shape: vec![FEATURES],
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::food::{Food, FoodKind};
+
+    fn create_env(level: &str, wrap: bool) -> Env {
+        Env::new(EnvConfig {
+            level: level.parse().unwrap(),
+            wrap,
+            size: 3,
+            grow_rate: 1,
+            observation: ObservationKind::Features,
+            ..EnvConfig::default()
+        })
+        .unwrap()
+    }
+
+    /// Puts `kind` of food on the level tile at `(x, y)`.
+    fn place_food(env: &mut Env, (x, y): (usize, usize), kind: FoodKind) {
+        let position = env.game.world().to_position(Point::new(x, y));
+        env.game
+            .set_food(Food::of_kind(Point::new(position.x / 2, position.y), kind));
+    }
+
+    #[test]
+    fn it_moves_one_tile_per_step_and_ignores_reversing() {
+        let mut env = create_env("---\n......\n..S...\n......", false);
+        assert_eq!(env.head_position(), Some((2, 1)));
+        assert_eq!(env.length(), 3);
+
+        place_food(&mut env, (5, 2), FoodKind::Apple);
+        let (_, reward, done) = env.step(Direction::Left);
+
+        assert_eq!((reward, done), (0.0, false));
+        assert_eq!(env.head_position(), Some((3, 1)));
+        assert_eq!(env.direction(), Direction::Right);
+    }
+
+    #[test]
+    fn it_rewards_eating_and_grows() {
+        let mut env = create_env("---\n......\n..S...\n......", false);
+        place_food(&mut env, (3, 1), FoodKind::Apple);
+
+        let (_, reward, done) = env.step(Direction::Right);
+        assert_eq!((reward, done), (FOOD_REWARD, false));
+        assert_eq!(env.score(), 1);
+
+        place_food(&mut env, (5, 0), FoodKind::Apple);
+        env.step(Direction::Down);
+        assert_eq!(env.length(), 4);
+    }
+
+    #[test]
+    fn it_rewards_the_points_scored() {
+        let mut env = create_env("---\n......\n..S...\n......", false);
+        place_food(&mut env, (3, 1), FoodKind::Bonus);
+
+        let (_, reward, _) = env.step(Direction::Right);
+        assert_eq!(reward, 5.0 * FOOD_REWARD);
+        assert_eq!(env.score(), 5);
+    }
+
+    #[test]
+    fn it_places_food_off_a_snake_that_fills_most_of_the_board() {
+        let mut env = Env::new(EnvConfig {
+            level: "---\n......S..".parse().unwrap(),
+            size: 6,
+            grow_rate: 1,
+            ..EnvConfig::default()
+        })
+        .unwrap();
+
+        for seed in 0..20 {
+            env.reset(seed);
+            place_food(&mut env, (7, 0), FoodKind::Apple);
+            env.step(Direction::Right);
+
+            let (x, _) = env.food_position().unwrap();
+            assert!([0, 1, 8].contains(&x), "seed {seed} placed food at {x}");
+        }
+    }
+
+    #[test]
+    fn it_ends_the_episode_on_a_crash() {
+        let mut env = create_env("---\n......\n..S.#.\n......", false);
+        place_food(&mut env, (0, 0), FoodKind::Apple);
+
+        assert_eq!(env.step(Direction::Right).1, 0.0);
+        let (_, reward, done) = env.step(Direction::Right);
+
+        assert_eq!((reward, done), (CRASH_REWARD, true));
+        assert!(env.is_done());
+        assert_eq!(env.step(Direction::Up).1, 0.0);
+    }
+
+    #[test]
+    fn it_ends_the_episode_when_the_level_is_clear() {
+        let mut env = create_env("target: 1\n---\n......\n..S...\n......", false);
+        place_food(&mut env, (3, 1), FoodKind::Apple);
+
+        let (_, reward, done) = env.step(Direction::Right);
+        assert_eq!((reward, done), (FOOD_REWARD, true));
+    }
+
+    #[test]
+    fn it_crashes_into_edges_unless_wrapping() {
+        let level = "direction: up\n---\n..S...\n......\n......";
+        let mut env = create_env(level, false);
+        assert!(env.step(Direction::Up).2);
+
+        // Wrapping onto the tail is fine since it moves out of the way
+        let mut env = create_env(level, true);
+        place_food(&mut env, (5, 1), FoodKind::Apple);
+        assert!(!env.step(Direction::Up).2);
+        assert_eq!(env.head_position(), Some((2, 2)));
+    }
+
+    #[test]
+    fn it_repeats_episodes_for_the_same_seed() {
+        let mut env = Env::new(EnvConfig::default()).unwrap();
+        let actions = [Direction::Down, Direction::Right, Direction::Up];
+
+        let mut play = |seed| {
+            let mut steps = vec![env.reset(seed)];
+            for action in actions.iter().cycle().take(50) {
+                steps.push(env.step(*action).0);
+            }
+
+            (steps, env.food_position())
+        };
+
+        assert_eq!(play(7), play(7));
+    }
+
+    #[test]
+    fn it_observes_the_grid_and_features() {
+        let mut env = Env::new(EnvConfig {
+            observation: ObservationKind::Grid,
+            ..EnvConfig::default()
+        })
+        .unwrap();
+
+        let grid = env.reset(1);
+        assert_eq!(grid.shape, vec![GRID_CHANNELS, 20, 20]);
+        assert_eq!(grid.data.iter().sum::<f32>(), 6.0 + 1.0 + 1.0);
+
+        let mut env = create_env("---\n#.....\n.S....\n......", false);
+        place_food(&mut env, (5, 0), FoodKind::Apple);
+
+        let features = env.observe();
+        assert_eq!(features.shape, vec![FEATURES]);
+        assert_eq!(
+            features.data,
+            vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0]
+        );
+    }
+}
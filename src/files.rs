@@ -0,0 +1,113 @@
+//! Reading and writing the files the game keeps between runs, with errors
+//! that name the file. `what` describes the file in those errors, e.g.
+//! "high scores".
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A file in the user's data directory, e.g. `$XDG_DATA_HOME/snake/<name>`
+/// on Linux.
+pub fn data_file(name: impl AsRef<Path>) -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().ok_or_else(|| anyhow!("Failed to find the data directory"))?;
+    Ok(data_dir.join(env!("CARGO_PKG_NAME")).join(name))
+}
+
+/// A file in the user's config directory, e.g.
+/// `$XDG_CONFIG_HOME/snake/<name>` on Linux.
+pub fn config_file(name: impl AsRef<Path>) -> Result<PathBuf> {
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| anyhow!("Failed to find the config directory"))?;
+    Ok(config_dir.join(env!("CARGO_PKG_NAME")).join(name))
+}
+
+pub fn read(path: &Path, what: &str) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {what} {}", path.display()))
+}
+
+/// Reads the file, or `None` if it doesn't exist yet.
+pub fn read_optional(path: &Path, what: &str) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    read(path, what).map(Some)
+}
+
+/// Writes the file, creating the directories it goes in.
+pub fn write(path: &Path, what: &str, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    fs::write(path, content).with_context(|| format!("Failed to write {what} {}", path.display()))
+}
+
+/// Reads a JSON file, treating a missing file as the default value.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T> {
+    let Some(content) = read_optional(path, what)? else {
+        return Ok(T::default());
+    };
+
+    parse_json(path, what, &content)
+}
+
+pub fn parse_json<T: DeserializeOwned>(path: &Path, what: &str, content: &str) -> Result<T> {
+    serde_json::from_str(content)
+        .with_context(|| format!("Failed to parse {what} {}", path.display()))
+}
+
+pub fn save_json<T: Serialize>(path: &Path, what: &str, value: &T) -> Result<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    write(path, what, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, PartialEq, Serialize, serde::Deserialize)]
+    struct Counter {
+        count: u32,
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn it_loads_a_missing_file_as_the_default() {
+        let path = temp_dir("files-missing").join("counter.json");
+        assert_eq!(
+            load_json::<Counter>(&path, "counter").unwrap(),
+            Counter::default()
+        );
+    }
+
+    #[test]
+    fn it_creates_the_directories_a_file_is_saved_in() {
+        let dir = temp_dir("files-save");
+        let path = dir.join("nested").join("counter.json");
+
+        save_json(&path, "counter", &Counter { count: 3 }).unwrap();
+        let counter: Counter = load_json(&path, "counter").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(counter, Counter { count: 3 });
+    }
+
+    #[test]
+    fn it_names_the_file_when_it_cant_be_parsed() {
+        let path = temp_dir("files-corrupt.json");
+        fs::write(&path, "not json").unwrap();
+
+        let error = load_json::<Counter>(&path, "counter").unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert!(format!("{error}").starts_with("Failed to parse counter"));
+    }
+}
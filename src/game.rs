@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{
+    GameMode, PlayerInput, SnakeStyle,
+    curve::SpeedCurve,
+    engine::{entity::Entity, point::Point},
+    entities::{
+        effects::{Effect, Effects},
+        food::{Food, FoodKind, SpawnTable},
+        snake::Snake,
+        world::World,
+    },
+    level::Level,
+};
+
+/// The smallest world a game can be played in. It leaves room for the game
+/// over text and high score name prompt.
+pub const MIN_COLUMNS: usize = 40;
+pub const MIN_ROWS: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub snake: SnakeConfig,
+    pub rows: usize,
+    pub columns: usize,
+    pub show_frame_rate: bool,
+    pub show_border: bool,
+    pub frame_rate: u8,
+    #[serde(default)]
+    pub mode: GameMode,
+    pub seed: Option<u64>,
+    /// The walls to play inside of. `None` plays on an empty level that
+    /// fills the terminal.
+    #[serde(default)]
+    pub level: Option<Level>,
+    /// How likely each kind of food is to be placed. `None` only places
+    /// apples, like the classic game.
+    #[serde(default)]
+    pub food: Option<SpawnTable>,
+    /// How the snake speeds up as the game goes on. `None` keeps it at the
+    /// same speed.
+    #[serde(default)]
+    pub curve: Option<SpeedCurve>,
+}
+
+impl GameConfig {
+    /// Sizes the world to fill a terminal of `columns` by `rows`, without
+    /// going below the smallest playable size.
+    pub fn resize(&mut self, columns: usize, rows: usize) {
+        self.columns = columns.max(MIN_COLUMNS);
+        self.rows = rows.max(MIN_ROWS);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeConfig {
+    pub grow_rate: usize,
+    pub speed: f32,
+    pub size: usize,
+    /// How many direction changes can be buffered ahead of the snake.
+    pub turn_queue_depth: usize,
+    pub style: SnakeStyle,
+}
+
+/// What happened to the snake during an update of a `Game`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The snake ran into a wall, an edge of a level that doesn't wrap or
+    /// itself.
+    Crashed,
+    /// The snake ate `kind` of food, scoring `points`.
+    Ate { kind: FoodKind, points: u32 },
+}
+
+/// A single player game: the world, the snake and its food, and the rules
+/// they're played by. Running into a wall or itself crashes the snake, eating
+/// grows it and scores, food with a lifetime is replaced when it runs out and
+/// the speed curve speeds the snake up.
+#[derive(Debug, Clone)]
+pub struct Game {
+    config: GameConfig,
+    world: World,
+    snake: Snake,
+    food: Food,
+    effects: Effects,
+    score: u32,
+    /// How far up the speed curve the snake is, counting from 0.
+    speed_level: u32,
+    play_time: Duration,
+}
+
+impl Game {
+    pub fn new(config: &GameConfig) -> Self {
+        let world = World::new(config, Point::new(0, 0));
+        let food = Food::new(world.get_random_position());
+        let snake = world.create_snake();
+
+        Self {
+            config: config.clone(),
+            world,
+            snake,
+            food,
+            effects: Effects::new(Point::new(0, 0)),
+            score: 0,
+            speed_level: 0,
+            play_time: Duration::from_secs(0),
+        }
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.snake
+    }
+
+    pub fn food(&self) -> &Food {
+        &self.food
+    }
+
+    pub fn effects(&self) -> &Effects {
+        &self.effects
+    }
+
+    /// The active effects, such as to move where they're shown.
+    pub fn effects_mut(&mut self) -> &mut Effects {
+        &mut self.effects
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn speed_level(&self) -> u32 {
+        self.speed_level
+    }
+
+    pub fn play_time(&self) -> Duration {
+        self.play_time
+    }
+
+    /// True once the score has reached the level's target.
+    pub fn is_level_clear(&self) -> bool {
+        self.world
+            .level()
+            .target
+            .is_some_and(|target| self.score >= target)
+    }
+
+    /// Queues a turn for the snake. Turning back on itself is ignored.
+    pub fn steer(&mut self, input: &PlayerInput) {
+        self.snake.process_input(input);
+    }
+
+    /// Grows the snake to `length` over its next few moves.
+    pub fn grow_to(&mut self, length: usize) {
+        self.snake.grow(length.saturating_sub(self.snake.size()));
+    }
+
+    /// Puts `food` in place of the current food, such as to set up a
+    /// situation to test or train on.
+    pub fn set_food(&mut self, food: Food) {
+        self.food = food;
+    }
+
+    /// Moves the game on by `elapsed`, returning what happened to the snake.
+    /// The snake moves as many tiles as its speed covers in that time.
+    pub fn update(&mut self, elapsed: &Duration) -> Option<Outcome> {
+        self.pass_time(elapsed);
+        self.snake.update(elapsed);
+        self.food.update(elapsed);
+        self.resolve()
+    }
+
+    /// Moves the snake exactly one tile, with the rest of the game moving on
+    /// by however long that takes at the snake's speed.
+    pub fn step(&mut self) -> Option<Outcome> {
+        let speed = self.snake.speed() * self.effects.speed_factor();
+        let elapsed = Duration::from_secs_f32(1.0 / speed);
+
+        self.pass_time(&elapsed);
+        self.snake.advance();
+        self.food.update(&elapsed);
+        self.resolve()
+    }
+
+    fn pass_time(&mut self, elapsed: &Duration) {
+        self.play_time += *elapsed;
+        self.update_speed();
+        self.effects.update(elapsed);
+        self.snake.set_speed_factor(self.effects.speed_factor());
+    }
+
+    /// Moves the snake up the speed curve as the score and time played go
+    /// up.
+    fn update_speed(&mut self) {
+        let Some(curve) = &self.config.curve else {
+            return;
+        };
+
+        let level = curve.level(self.score, self.play_time);
+        if level != self.speed_level {
+            self.snake
+                .set_speed(curve.speed(self.config.snake.speed, level));
+            self.speed_level = level;
+        }
+    }
+
+    /// Checks where the snake's moves have taken it.
+    fn resolve(&mut self) -> Option<Outcome> {
+        let ghost = self.effects.is_active(Effect::Ghost);
+        if self.world.detect_collision(self.snake.head())
+            || (!ghost && self.snake.detect_self_collision())
+        {
+            return Some(Outcome::Crashed);
+        }
+
+        if self.snake.detect_head_collision(self.food.get_position()) {
+            return Some(self.eat_food());
+        }
+
+        if self.food.is_expired() {
+            self.food = self.spawn_food();
+        }
+
+        None
+    }
+
+    /// Scores the food and applies whatever it does to the snake, then
+    /// places the next one.
+    fn eat_food(&mut self) -> Outcome {
+        let kind = self.food.kind();
+        if kind.grows() {
+            self.snake.grow(self.config.snake.grow_rate);
+        }
+
+        if kind.shrinks() > 0 {
+            self.snake.shrink(kind.shrinks());
+        }
+
+        let points = kind.points() * self.effects.multiplier();
+        self.score += points;
+        if let Some(effect) = kind.effect() {
+            self.effects.start(effect);
+        }
+
+        self.food = self.spawn_food();
+        Outcome::Ate { kind, points }
+    }
+
+    fn spawn_food(&self) -> Food {
+        let position = self
+            .world
+            .get_free_position(|position| self.snake.detect_collision(position));
+
+        let kind = match &self.config.food {
+            Some(table) => self.world.get_random_food(table),
+            None => FoodKind::Apple,
+        };
+
+        Food::of_kind(position, kind)
+    }
+}
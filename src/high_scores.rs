@@ -0,0 +1,320 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{GameConfig, GameMode, files};
+
+/// The number of scores kept for each rule set.
+pub const MAX_ENTRIES: usize = 10;
+
+const HIGH_SCORES_FILE_NAME: &str = "high_scores.json";
+const HIGH_SCORES_DESCRIPTION: &str = "high scores";
+
+/// The settings that change how hard a game is. Scores are only ranked
+/// against other games played with the same rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub speed: f32,
+    pub grow_rate: usize,
+    pub border: bool,
+    #[serde(default)]
+    pub mode: GameMode,
+    /// The name of the level played, `None` for the empty level.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Whether bonus fruit and power-ups were placed as well as apples.
+    #[serde(default)]
+    pub power_ups: bool,
+    /// The name of the speed curve played, `None` for a steady speed.
+    #[serde(default)]
+    pub curve: Option<String>,
+}
+
+impl From<&GameConfig> for RuleSet {
+    fn from(config: &GameConfig) -> Self {
+        Self {
+            speed: config.snake.speed,
+            grow_rate: config.snake.grow_rate,
+            border: config.show_border,
+            mode: config.mode,
+            level: config.level.as_ref().map(|level| level.name.clone()),
+            power_ups: config.food.is_some(),
+            curve: config.curve.as_ref().map(|curve| curve.name.clone()),
+        }
+    }
+}
+
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SPEED {}  GROW {}  BORDER {}  {}",
+            self.speed,
+            self.grow_rate,
+            if self.border { "ON" } else { "OFF" },
+            match self.mode {
+                GameMode::Walls => "WALLS",
+                GameMode::Wrap => "WRAP",
+            },
+        )?;
+
+        if let Some(level) = &self.level {
+            write!(f, "  {}", level.to_uppercase())?;
+        }
+
+        if self.power_ups {
+            write!(f, "  POWER-UPS")?;
+        }
+
+        match &self.curve {
+            Some(curve) => write!(f, "  {} CURVE", curve.to_uppercase()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub length: usize,
+    pub duration: Duration,
+    /// Seconds since the Unix epoch.
+    pub date: u64,
+    pub seed: u64,
+}
+
+impl HighScore {
+    pub fn new(name: String, score: u32, length: usize, duration: Duration, seed: u64) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self {
+            name,
+            score,
+            length,
+            duration,
+            date,
+            seed,
+        }
+    }
+
+    pub fn format_duration(&self) -> String {
+        let seconds = self.duration.as_secs();
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    /// Formats the date as `YYYY-MM-DD` in UTC.
+    pub fn format_date(&self) -> String {
+        // Converts days since the epoch to a civil date, see
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = (self.date / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub rules: RuleSet,
+    pub entries: Vec<HighScore>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: Vec<HighScoreTable>,
+}
+
+impl HighScores {
+    /// The high score file in the user's data directory, e.g.
+    /// `$XDG_DATA_HOME/snake/high_scores.json` on Linux.
+    pub fn default_path() -> Result<PathBuf> {
+        files::data_file(HIGH_SCORES_FILE_NAME)
+    }
+
+    /// Reads the high scores, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        files::load_json(path, HIGH_SCORES_DESCRIPTION)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        files::save_json(path, HIGH_SCORES_DESCRIPTION, self)
+    }
+
+    pub fn tables(&self) -> &[HighScoreTable] {
+        &self.tables
+    }
+
+    pub fn table(&self, rules: &RuleSet) -> Option<&HighScoreTable> {
+        self.tables.iter().find(|table| table.rules == *rules)
+    }
+
+    /// Returns true if `score` would make it into the table for `rules`.
+    pub fn qualifies(&self, rules: &RuleSet, score: u32) -> bool {
+        if score == 0 {
+            return false;
+        }
+
+        match self.table(rules) {
+            Some(table) if table.entries.len() >= MAX_ENTRIES => table
+                .entries
+                .last()
+                .is_some_and(|lowest| score > lowest.score),
+            _ => true,
+        }
+    }
+
+    /// Adds the score to the table for `rules`, returning its zero based rank
+    /// or `None` if it didn't make the table.
+    pub fn insert(&mut self, rules: &RuleSet, high_score: HighScore) -> Option<usize> {
+        if !self.qualifies(rules, high_score.score) {
+            return None;
+        }
+
+        let table = match self.tables.iter().position(|table| table.rules == *rules) {
+            Some(index) => &mut self.tables[index],
+            None => {
+                self.tables.push(HighScoreTable {
+                    rules: rules.clone(),
+                    entries: vec![],
+                });
+
+                self.tables.last_mut()?
+            }
+        };
+
+        let rank = table
+            .entries
+            .iter()
+            .position(|entry| high_score.score > entry.score)
+            .unwrap_or(table.entries.len());
+
+        table.entries.insert(rank, high_score);
+        table.entries.truncate(MAX_ENTRIES);
+
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: RuleSet = RuleSet {
+        speed: 15.0,
+        grow_rate: 2,
+        border: false,
+        mode: GameMode::Walls,
+        level: None,
+        power_ups: false,
+        curve: None,
+    };
+
+    fn high_score(score: u32) -> HighScore {
+        HighScore {
+            name: "KYLE".into(),
+            score,
+            length: 6,
+            duration: Duration::from_secs(65),
+            date: 0,
+            seed: 1,
+        }
+    }
+
+    #[test]
+    fn it_ranks_scores_from_highest_to_lowest() {
+        let mut high_scores = HighScores::default();
+
+        assert_eq!(high_scores.insert(&RULES, high_score(5)), Some(0));
+        assert_eq!(high_scores.insert(&RULES, high_score(10)), Some(0));
+        assert_eq!(high_scores.insert(&RULES, high_score(7)), Some(1));
+
+        let scores: Vec<_> = high_scores
+            .table(&RULES)
+            .unwrap()
+            .entries
+            .iter()
+            .map(|entry| entry.score)
+            .collect();
+        assert_eq!(scores, [10, 7, 5]);
+    }
+
+    #[test]
+    fn it_only_keeps_the_top_scores() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=MAX_ENTRIES as u32 {
+            high_scores.insert(&RULES, high_score(score));
+        }
+
+        assert!(!high_scores.qualifies(&RULES, 1));
+        assert_eq!(high_scores.insert(&RULES, high_score(1)), None);
+        assert_eq!(
+            high_scores.insert(&RULES, high_score(2)),
+            Some(MAX_ENTRIES - 1)
+        );
+        assert_eq!(
+            high_scores.table(&RULES).unwrap().entries.len(),
+            MAX_ENTRIES
+        );
+    }
+
+    #[test]
+    fn it_keeps_rule_sets_separate() {
+        let mut high_scores = HighScores::default();
+        let faster = RuleSet {
+            speed: 20.0,
+            ..RULES
+        };
+
+        high_scores.insert(&RULES, high_score(5));
+        high_scores.insert(&faster, high_score(3));
+
+        assert_eq!(high_scores.tables().len(), 2);
+        assert_eq!(high_scores.table(&faster).unwrap().entries[0].score, 3);
+    }
+
+    #[test]
+    fn it_keeps_speed_curves_separate() {
+        let mut high_scores = HighScores::default();
+        let steep = RuleSet {
+            curve: Some("steep".into()),
+            ..RULES
+        };
+
+        high_scores.insert(&RULES, high_score(5));
+        high_scores.insert(&steep, high_score(3));
+
+        assert_eq!(high_scores.table(&steep).unwrap().entries[0].score, 3);
+        assert!(steep.to_string().ends_with("  STEEP CURVE"));
+    }
+
+    #[test]
+    fn it_formats_the_date_and_duration() {
+        let high_score = HighScore {
+            date: 1_700_000_000,
+            ..high_score(1)
+        };
+
+        assert_eq!(high_score.format_date(), "2023-11-14");
+        assert_eq!(high_score.format_duration(), "1:05");
+    }
+}
@@ -0,0 +1,493 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    engine::point::{Point, Vector},
+    files,
+};
+
+const WALL: char = '#';
+const EMPTY: char = '.';
+const SPAWN: char = 'S';
+const SEPARATOR: &str = "---";
+const EDITOR_FILE_NAME: &str = "custom.txt";
+
+/// The levels that ship with the game, by name.
+pub const BUILTIN_LEVELS: [(&str, &str); 5] = [
+    ("box", include_str!("../levels/box.txt")),
+    ("pillars", include_str!("../levels/pillars.txt")),
+    ("cross", include_str!("../levels/cross.txt")),
+    ("tunnels", include_str!("../levels/tunnels.txt")),
+    ("maze", include_str!("../levels/maze.txt")),
+];
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    #[default]
+    Right,
+}
+
+impl Direction {
+    /// The next direction clockwise.
+    pub fn rotate(&self) -> Self {
+        match self {
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    /// An arrow pointing this way.
+    pub fn arrow(&self) -> &'static str {
+        match self {
+            Self::Up => "↑",
+            Self::Down => "↓",
+            Self::Left => "←",
+            Self::Right => "→",
+        }
+    }
+
+    /// The direction a snake moving along `velocity` is heading. A snake that
+    /// isn't moving is treated as heading right.
+    pub fn from_velocity(velocity: Vector) -> Self {
+        match (velocity.x.signum(), velocity.y.signum()) {
+            (0, -1) => Self::Up,
+            (0, 1) => Self::Down,
+            (-1, _) => Self::Left,
+            _ => Self::Right,
+        }
+    }
+
+    /// How far the snake moves each tile when heading this way. Tiles are two
+    /// columns wide.
+    pub fn velocity(&self) -> Vector {
+        match self {
+            Self::Up => Vector::new(0, -1),
+            Self::Down => Vector::new(0, 1),
+            Self::Left => Vector::new(-2, 0),
+            Self::Right => Vector::new(2, 0),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "up" => Self::Up,
+            "down" => Self::Down,
+            "left" => Self::Left,
+            "right" => Self::Right,
+            _ => bail!("direction must be one of up, down, left or right, got {value}"),
+        })
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Up => write!(f, "up"),
+            Self::Down => write!(f, "down"),
+            Self::Left => write!(f, "left"),
+            Self::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// A grid of walls the snake plays inside of, along with where it starts and
+/// what it takes to clear the level.
+///
+/// Levels are stored as plain text, with a header of `key: value` lines and
+/// the grid after a `---` line:
+///
+/// ```text
+/// name: Pillars
+/// direction: right
+/// target: 20
+/// speed: 12
+/// ---
+/// ..........
+/// .S..#..#..
+/// ..........
+/// ```
+///
+/// `#` is a wall, `.` or a space is empty and `S` is where the snake's head
+/// starts. Every header key is optional. In a campaign, `carry: length, speed`
+/// keeps the snake's length and speed from the level before instead of
+/// starting over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Level {
+    pub name: String,
+    pub direction: Direction,
+    /// The score that clears the level.
+    pub target: Option<u32>,
+    /// Overrides the snake's speed from the config.
+    pub speed: Option<f32>,
+    /// Keeps the length the snake had at the end of the previous level.
+    pub carry_length: bool,
+    /// Keeps the speed the snake had at the end of the previous level.
+    pub carry_speed: bool,
+    width: usize,
+    height: usize,
+    walls: BTreeSet<Point>,
+    spawn: Option<Point>,
+}
+
+impl Level {
+    /// A level without any walls.
+    pub fn empty(width: usize, height: usize) -> Self {
+        Self {
+            name: String::new(),
+            direction: Direction::default(),
+            target: None,
+            speed: None,
+            carry_length: false,
+            carry_speed: false,
+            width,
+            height,
+            walls: BTreeSet::new(),
+            spawn: None,
+        }
+    }
+
+    /// Every built-in level, in the order they're played in the campaign.
+    pub fn builtins() -> impl Iterator<Item = Self> {
+        BUILTIN_LEVELS
+            .iter()
+            .map(|(_, content)| content.parse().expect("built-in levels are valid"))
+    }
+
+    /// The level the editor opens when no file is given, e.g.
+    /// `$XDG_DATA_HOME/snake/levels/custom.txt` on Linux.
+    pub fn default_editor_path() -> Result<PathBuf> {
+        files::data_file(Path::new("levels").join(EDITOR_FILE_NAME))
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        BUILTIN_LEVELS
+            .iter()
+            .find(|(builtin, _)| builtin.eq_ignore_ascii_case(name))
+            .map(|(_, content)| content.parse().expect("built-in levels are valid"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        files::read(path, "level")?
+            .parse()
+            .with_context(|| format!("Invalid level {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        files::write(path, "level", &self.to_string())
+    }
+
+    /// Loads a built-in level by name, or a level file if there's no built-in
+    /// level called `name_or_path`.
+    pub fn find(name_or_path: &str) -> Result<Self> {
+        if let Some(level) = Self::builtin(name_or_path) {
+            return Ok(level);
+        }
+
+        let path = Path::new(name_or_path);
+        if !path.exists() {
+            let names: Vec<_> = BUILTIN_LEVELS.iter().map(|(name, _)| *name).collect();
+            bail!(
+                "No level file {name_or_path}, expected a path or one of {}",
+                names.join(", ")
+            );
+        }
+
+        Self::load(path)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The tile the snake's head starts on, if the level sets one.
+    pub fn spawn(&self) -> Option<Point> {
+        self.spawn
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = &Point> {
+        self.walls.iter()
+    }
+
+    pub fn is_wall(&self, tile: Point) -> bool {
+        self.walls.contains(&tile)
+    }
+
+    /// Adds or removes a wall. Tiles outside the level are ignored, and a
+    /// wall placed on the spawn point removes it.
+    pub fn set_wall(&mut self, tile: Point, wall: bool) {
+        if tile.x >= self.width || tile.y >= self.height {
+            return;
+        }
+
+        if !wall {
+            self.walls.remove(&tile);
+            return;
+        }
+
+        if self.spawn == Some(tile) {
+            self.spawn = None;
+        }
+
+        self.walls.insert(tile);
+    }
+
+    /// Moves the spawn point to `tile`, replacing any wall there.
+    pub fn set_spawn(&mut self, tile: Point) {
+        if tile.x >= self.width || tile.y >= self.height {
+            return;
+        }
+
+        self.walls.remove(&tile);
+        self.spawn = Some(tile);
+    }
+
+    /// Removes every wall and the spawn point.
+    pub fn clear(&mut self) {
+        self.walls.clear();
+        self.spawn = None;
+    }
+
+    fn parse_header(&mut self, line_number: usize, line: &str) -> Result<()> {
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow!("line {line_number}: expected `key: value` or {SEPARATOR}"))?;
+
+        let value = value.trim();
+        let context = || format!("line {line_number}: invalid {}", key.trim());
+        match key.trim() {
+            "name" => self.name = value.to_string(),
+            "direction" => self.direction = value.parse().with_context(context)?,
+            "target" => self.target = Some(value.parse().with_context(context)?),
+            "speed" => {
+                let speed: f32 = value.parse().with_context(context)?;
+                if !(speed.is_finite() && speed > 0.0) {
+                    bail!("line {line_number}: speed must be greater than 0, got {speed}");
+                }
+
+                self.speed = Some(speed);
+            }
+            "carry" => {
+                for value in value.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+                    match value {
+                        "length" => self.carry_length = true,
+                        "speed" => self.carry_speed = true,
+                        value => bail!(
+                            "line {line_number}: carry must be a list of length and speed, got {value}"
+                        ),
+                    }
+                }
+            }
+            key => bail!("line {line_number}: unknown key `{key}`"),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Level {
+    type Err = anyhow::Error;
+
+    fn from_str(content: &str) -> Result<Self> {
+        let mut level = Self::empty(0, 0);
+        let mut lines = content.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+        loop {
+            let Some((line_number, line)) = lines.next() else {
+                bail!("missing {SEPARATOR} line between the header and grid");
+            };
+
+            match line.trim() {
+                "" => continue,
+                SEPARATOR => break,
+                line => level.parse_header(line_number, line)?,
+            }
+        }
+
+        let rows: Vec<_> = lines.collect();
+        let rows = match rows.iter().rposition(|(_, row)| !row.trim().is_empty()) {
+            Some(last) => &rows[..=last],
+            None => bail!("the grid is empty"),
+        };
+
+        for (y, (line_number, row)) in rows.iter().enumerate() {
+            for (x, tile) in row.trim_end().chars().enumerate() {
+                let position = Point::new(x, y);
+                match tile {
+                    WALL => {
+                        level.walls.insert(position);
+                    }
+                    SPAWN if level.spawn.is_some() => {
+                        bail!("line {line_number}: only one {SPAWN} is allowed")
+                    }
+                    SPAWN => level.spawn = Some(position),
+                    EMPTY | ' ' => (),
+                    tile => bail!(
+                        "line {line_number}: unknown tile `{tile}` in column {}",
+                        x + 1
+                    ),
+                }
+            }
+
+            level.width = level.width.max(row.trim_end().chars().count());
+        }
+
+        level.height = rows.len();
+        Ok(level)
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.name.is_empty() {
+            writeln!(f, "name: {}", self.name)?;
+        }
+
+        writeln!(f, "direction: {}", self.direction)?;
+
+        if let Some(target) = self.target {
+            writeln!(f, "target: {target}")?;
+        }
+
+        if let Some(speed) = self.speed {
+            writeln!(f, "speed: {speed}")?;
+        }
+
+        let carry: Vec<_> = [(self.carry_length, "length"), (self.carry_speed, "speed")]
+            .into_iter()
+            .filter_map(|(carry, value)| carry.then_some(value))
+            .collect();
+
+        if !carry.is_empty() {
+            writeln!(f, "carry: {}", carry.join(", "))?;
+        }
+
+        writeln!(f, "{SEPARATOR}")?;
+
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| match Point::new(x, y) {
+                    tile if self.is_wall(tile) => WALL,
+                    tile if self.spawn == Some(tile) => SPAWN,
+                    _ => EMPTY,
+                })
+                .collect();
+
+            writeln!(f, "{row}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for Level {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Level> for String {
+    fn from(level: Level) -> Self {
+        level.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "name: Test\ndirection: down\ntarget: 5\ncarry: length\n---\n..#\n.S.\n#\n";
+
+    #[test]
+    fn it_parses_the_header_and_grid() {
+        let level: Level = LEVEL.parse().unwrap();
+
+        assert_eq!(level.name, "Test");
+        assert_eq!(level.direction, Direction::Down);
+        assert_eq!(level.target, Some(5));
+        assert_eq!(level.speed, None);
+        assert!(level.carry_length);
+        assert!(!level.carry_speed);
+        assert_eq!((level.width(), level.height()), (3, 3));
+        assert_eq!(level.spawn(), Some(Point::new(1, 1)));
+        assert!(level.is_wall(Point::new(2, 0)));
+        assert!(level.is_wall(Point::new(0, 2)));
+        assert!(!level.is_wall(Point::new(2, 2)));
+    }
+
+    #[test]
+    fn it_round_trips_through_text() {
+        let level: Level = LEVEL.parse().unwrap();
+        assert_eq!(level.to_string().parse::<Level>().unwrap(), level);
+    }
+
+    #[test]
+    fn it_rejects_invalid_levels() {
+        let error = |content: &str| content.parse::<Level>().unwrap_err().to_string();
+
+        assert!(error("---\n.x.").contains("unknown tile `x` in column 2"));
+        assert!(error("---\nS.S").contains("only one S"));
+        assert!(error("colour: red\n---\n...").contains("unknown key `colour`"));
+        assert!(error("direction: north\n---\n...").contains("invalid direction"));
+        assert!(error("carry: score\n---\n...").contains("carry must be"));
+        assert!(error("...").contains("line 1"));
+        assert!(error("name: Empty\n---\n\n").contains("empty"));
+    }
+
+    #[test]
+    fn it_keeps_walls_and_the_spawn_apart() {
+        let mut level = Level::empty(4, 2);
+        level.set_spawn(Point::new(1, 1));
+        level.set_wall(Point::new(1, 1), true);
+        assert_eq!(level.spawn(), None);
+
+        level.set_spawn(Point::new(1, 1));
+        assert!(!level.is_wall(Point::new(1, 1)));
+
+        level.set_wall(Point::new(9, 0), true);
+        assert_eq!(level.walls().count(), 0);
+    }
+
+    #[test]
+    fn it_ships_valid_built_in_levels() {
+        for (name, _) in BUILTIN_LEVELS {
+            let level = Level::builtin(name).unwrap();
+            let spawn = level.spawn().unwrap();
+
+            assert!(!level.name.is_empty());
+            assert!(!level.is_wall(spawn));
+            assert!(level.target.is_some());
+        }
+    }
+}
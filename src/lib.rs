@@ -0,0 +1,79 @@
+//! The rules of Snake, for training agents against without a terminal, and
+//! the engine the `snake` binary draws them with. Scenes can be run on the
+//! in-memory backend with a headless `GameLoop` in tests and tools.
+
+use clap::ValueEnum;
+use crossterm::style::Color;
+use serde::{Deserialize, Serialize};
+
+pub mod curve;
+
+pub mod engine;
+
+pub mod entities {
+    pub mod effects;
+    pub mod food;
+    pub mod snake;
+    pub mod world;
+}
+
+pub mod env;
+pub mod files;
+pub mod game;
+pub mod level;
+
+pub use game::{GameConfig, SnakeConfig};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum PlayerInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    FastForward,
+    Step,
+    Select,
+    Noop,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SnakeStyle {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+    Flash,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameMode {
+    /// Hitting the edge of the screen ends the game
+    #[default]
+    Walls,
+    /// Leaving one edge of the screen comes back on the opposite edge
+    Wrap,
+}
+
+impl SnakeStyle {
+    pub fn initial_color(&self) -> Color {
+        match self {
+            Self::Flash | Self::Green => Color::Green,
+            Self::Black => Color::Black,
+            Self::Red => Color::Red,
+            Self::Yellow => Color::Yellow,
+            Self::Blue => Color::Blue,
+            Self::Magenta => Color::Magenta,
+            Self::Cyan => Color::Cyan,
+            Self::White => Color::White,
+            Self::Grey => Color::Grey,
+        }
+    }
+}
@@ -1,128 +1,506 @@
+mod ai;
+mod bot;
+mod campaign;
 mod config;
-mod engine;
 mod entities;
+mod high_scores;
+mod net;
+mod replay;
 mod scenes;
+mod spectators;
+#[cfg(test)]
+mod test_support;
+mod tournament;
 
+use ai::Difficulty;
 use anyhow::{Context, Result};
-use clap::{Parser, ValueEnum};
-use config::GameConfig;
-use crossterm::{style::Color, terminal};
+use bot::{Bot, DEFAULT_BOT_TIMEOUT};
+use campaign::CampaignProgress;
+use clap::Parser;
+use config::{ConfigFile, GameConfig};
+use crossterm::terminal;
 use engine::{
+    backend::{RenderBackend, TerminalBackend},
     game_loop::{GameLoop, GameLoopConfig},
+    point::Point,
     renderer::Renderer,
 };
-use scenes::{snake::SnakeScene, title::TitleScene};
+use entities::world::World;
+use high_scores::HighScores;
+use net::DEFAULT_PORT;
+use replay::Replay;
+use scenes::{
+    campaign::CampaignScene,
+    client::ClientScene,
+    high_scores::HighScoresScene,
+    level_editor::LevelEditorScene,
+    server::ServerScene,
+    settings::SettingsScene,
+    snake::SnakeScene,
+    spectator::SpectatorScene,
+    title::TitleScene,
+    versus::{CpuVersusScene, MAX_OPPONENTS, VersusScene},
+};
+use snake::{
+    GameMode, PlayerInput, SnakeStyle, curve, engine, files,
+    level::{self, Level},
+};
+use spectators::Spectators;
 use std::{
-    io::{BufWriter, stdout},
+    cell::RefCell,
+    io::{BufWriter, Stdout, stdout},
+    path::PathBuf,
+    rc::Rc,
     time::Duration,
 };
-
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub enum PlayerInput {
-    Up,
-    Down,
-    Left,
-    Right,
-    Pause,
-    Select,
-    Noop,
-    Quit,
-}
+use tournament::{TOURNAMENT_SIZE, TournamentOptions};
 
 #[derive(Debug, clap::Parser)]
 #[command(version, about)]
 pub struct CommandOptions {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(
+        global = true,
         short,
         long,
-        default_value_t = 15.0,
-        help = "Set how many tiles per second the snakes moves"
+        help = "Set how many tiles per second the snakes moves [default: 15]"
     )]
-    speed: f32,
+    speed: Option<f32>,
 
     #[arg(
+        global = true,
         short,
         long,
-        default_value_t = 2,
-        help = "Set the rate at which the snake grows when eating food"
+        help = "Set the rate at which the snake grows when eating food [default: 2]"
+    )]
+    grow_rate: Option<usize>,
+
+    #[arg(value_enum, long, help = "Set style of the snake [default: green]")]
+    snake_style: Option<SnakeStyle>,
+
+    #[arg(short, long, help = "Set the max frame rate to target [default: 15]")]
+    frame_rate: Option<u8>,
+
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "Display the current frame rate"
+    )]
+    show_frame_rate: Option<bool>,
+
+    #[arg(
+        short = 'b',
+        long,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "Wrap the game area in a border"
     )]
-    grow_rate: usize,
+    show_border: Option<bool>,
 
     #[arg(
+        global = true,
         value_enum,
         long,
-        default_value_t = SnakeStyle::Green,
-        help = "Set style of the snake"
+        help = "Set whether the edges of the screen are deadly or wrap around [default: walls]"
     )]
-    snake_style: SnakeStyle,
+    mode: Option<GameMode>,
 
     #[arg(
-        short,
+        global = true,
         long,
-        default_value_t = 15,
-        help = "Set the max frame rate to target"
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Set how many turns can be queued up ahead of the snake [default: 3]"
     )]
-    frame_rate: u8,
+    turn_queue: Option<usize>,
 
-    #[arg(long, help = "Display the current frame rate")]
-    show_frame_rate: bool,
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "true",
+        help = "Place bonus fruit and power-ups as well as apples"
+    )]
+    power_ups: Option<bool>,
 
-    #[arg(short = 'b', long, help = "Wrap the game area in a border")]
-    show_border: bool,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Speed the snake up as the game goes on (steady, gentle, steep, marathon or one from the config file) [default: steady]"
+    )]
+    curve: Option<String>,
+
+    #[arg(
+        global = true,
+        long,
+        value_name = "NAME|PATH",
+        help = "Play a built-in level (box, pillars, cross, tunnels, maze) or a level file"
+    )]
+    level: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_OPPONENTS as u64),
+        help = "Set how many computer controlled snakes play in VS CPU [default: 1]"
+    )]
+    opponents: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Set how well computer controlled snakes play [default: normal]"
+    )]
+    difficulty: Option<Difficulty>,
+
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        conflicts_with_all = ["replay", "edit"],
+        help = "Let a program steer the snake, reading the game as JSON lines and replying with moves"
+    )]
+    bot: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        requires = "bot",
+        help = "Set how long the bot has to reply to each move [default: 100]"
+    )]
+    bot_timeout: Option<u64>,
+
+    #[arg(long, help = "Seed the random number generator to replay a game")]
+    seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Record the game's inputs to a replay file"
+    )]
+    record: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "record",
+        help = "Play back a replay file recorded with --record"
+    )]
+    replay: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "replay",
+        help = "Open a level file in the level editor, creating it when saved"
+    )]
+    edit: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read and save settings from this file instead of the default location"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with = "replay",
+        help = "Let people watch the games played here with `snake watch`, listening on this port"
+    )]
+    spectate: Option<u16>,
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum, Eq, PartialEq)]
-pub enum SnakeStyle {
-    Black,
-    Red,
-    Green,
-    Yellow,
-    Blue,
-    Magenta,
-    Cyan,
-    White,
-    Grey,
-    Flash,
+#[derive(Debug, clap::Subcommand)]
+pub enum Command {
+    /// Play games between bots and built-in players without a terminal and
+    /// print how each player did
+    Tournament(TournamentOptions),
+    /// Host a game that up to four players can join over the network
+    Serve(ServeOptions),
+    /// Join a game hosted with `snake serve`
+    Join(JoinOptions),
+    /// Watch a game hosted with `snake serve` or played with `--spectate`
+    Watch(WatchOptions),
 }
 
-impl SnakeStyle {
-    pub fn initial_color(&self) -> Color {
-        match self {
-            Self::Flash | Self::Green => Color::Green,
-            Self::Black => Color::Black,
-            Self::Red => Color::Red,
-            Self::Yellow => Color::Yellow,
-            Self::Blue => Color::Blue,
-            Self::Magenta => Color::Magenta,
-            Self::Cyan => Color::Cyan,
-            Self::White => Color::White,
-            Self::Grey => Color::Grey,
-        }
-    }
+#[derive(Debug, clap::Args)]
+pub struct ServeOptions {
+    #[arg(long, default_value_t = DEFAULT_PORT, help = "Port to listen for players on")]
+    port: u16,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct JoinOptions {
+    #[arg(help = "Address of the server, such as localhost:7777")]
+    address: String,
+    #[arg(long, help = "Name shown to the other players")]
+    name: Option<String>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct WatchOptions {
+    #[arg(help = "Address of the game, such as localhost:7777")]
+    address: String,
 }
 
 fn main() -> Result<()> {
-    let command_options = CommandOptions::parse();
+    let mut command_options = CommandOptions::parse();
+
+    let level = command_options
+        .level
+        .as_deref()
+        .map(Level::find)
+        .transpose()?;
+
+    // Tournaments ignore the config file so their results only depend on
+    // the command line
+    let command = command_options.command.take();
+    if let Some(Command::Tournament(options)) = &command {
+        let config = config::game_config(
+            command_options,
+            ConfigFile::default(),
+            level,
+            TOURNAMENT_SIZE,
+        )?;
+        return tournament::run(&config, options);
+    }
 
     let terminal_size =
         terminal::size().with_context(|| "Failed to get terminal size".to_string())?;
 
-    let snake_config = GameConfig::new(command_options, terminal_size);
+    let replay = command_options
+        .replay
+        .as_deref()
+        .map(Replay::load)
+        .transpose()?;
+    let record = command_options.record.clone();
+    let edit = command_options.edit.clone();
+    let opponents = command_options.opponents.unwrap_or(1);
+    let difficulty = command_options.difficulty.unwrap_or_default();
+    let spectate = command_options.spectate;
+    let fixed_speed = command_options.speed.is_some();
+    let bot = command_options.bot.clone().map(|command| {
+        let timeout = command_options
+            .bot_timeout
+            .map_or(DEFAULT_BOT_TIMEOUT, Duration::from_millis);
+        (command, timeout)
+    });
+
+    let config_path = match &command_options.config {
+        Some(path) => path.clone(),
+        None => ConfigFile::default_path()?,
+    };
+
+    let snake_config = match &replay {
+        Some(replay) => replay.config.clone(),
+        None => config::game_config(
+            command_options,
+            ConfigFile::load(&config_path)?,
+            level,
+            terminal_size,
+        )?,
+    };
+
+    let mut game_loop = create_game_loop(&snake_config);
+
+    match command {
+        Some(Command::Serve(options)) => {
+            return game_loop
+                .register_scene(ServerScene::bind(snake_config, options.port)?)
+                .run::<ServerScene>();
+        }
+        Some(Command::Join(options)) => {
+            return game_loop
+                .register_scene(ClientScene::join(
+                    snake_config,
+                    &options.address,
+                    options.name,
+                )?)
+                .run::<ClientScene>();
+        }
+        Some(Command::Watch(options)) => {
+            return game_loop
+                .register_scene(SpectatorScene::watch(snake_config, &options.address)?)
+                .run::<SpectatorScene>();
+        }
+        _ => {}
+    }
+
+    if let Some(replay) = replay {
+        return game_loop
+            .register_scene(SnakeScene::from_replay(replay))
+            .run::<SnakeScene>();
+    }
+
+    let editor_path = match &edit {
+        Some(path) => path.clone(),
+        None => Level::default_editor_path()?,
+    };
 
-    let mut game_loop = GameLoop::new(
+    register_scenes(
+        &mut game_loop,
+        snake_config,
+        SceneOptions {
+            config_path,
+            editor_path,
+            record,
+            opponents,
+            difficulty,
+            bot: bot.clone(),
+            spectate,
+            fixed_speed,
+        },
+    )?;
+
+    match (edit, bot) {
+        (Some(_), _) => game_loop.run::<LevelEditorScene>(),
+        (_, Some(_)) => game_loop.run::<SnakeScene>(),
+        _ => game_loop.run::<TitleScene>(),
+    }
+}
+
+/// A game loop that draws to the terminal.
+fn create_game_loop(config: &GameConfig) -> GameLoop<TerminalBackend<BufWriter<Stdout>>> {
+    GameLoop::new(
         Renderer::new(
-            BufWriter::new(stdout()),
-            snake_config.rows,
-            snake_config.columns,
+            TerminalBackend::new(BufWriter::new(stdout())),
+            config.rows,
+            config.columns,
         ),
         GameLoopConfig {
-            frame_rate: snake_config.frame_rate,
+            frame_rate: config.frame_rate,
             input_poll_rate: Duration::from_millis(0),
         },
-    );
+    )
+}
+
+/// Settings from the command line that scenes are created with.
+struct SceneOptions {
+    config_path: PathBuf,
+    editor_path: PathBuf,
+    record: Option<PathBuf>,
+    opponents: usize,
+    difficulty: Difficulty,
+    /// The command and reply timeout of a bot that plays in place of the
+    /// keyboard.
+    bot: Option<(String, Duration)>,
+    /// The port spectators can watch the games played on.
+    spectate: Option<u16>,
+    /// Whether `--speed` was given, so the campaign's levels don't override
+    /// it.
+    fixed_speed: bool,
+}
+
+/// Registers the title screen and every scene that can be reached from it.
+fn register_scenes<B: RenderBackend>(
+    game_loop: &mut GameLoop<B>,
+    snake_config: GameConfig,
+    options: SceneOptions,
+) -> Result<()> {
+    let shared_config = Rc::new(RefCell::new(snake_config.clone()));
+    let high_scores_path = HighScores::default_path()?;
+    let campaign_path = CampaignProgress::default_path()?;
+
+    let spectators = options
+        .spectate
+        .map(|port| {
+            let world = World::new(&snake_config, Point::new(0, 0));
+            Spectators::listen(port, &world).map(|spectators| Rc::new(RefCell::new(spectators)))
+        })
+        .transpose()?;
+
+    // Spectators are greeted even while nothing they can watch is running
+    if let Some(spectators) = &spectators {
+        let spectators = spectators.clone();
+        game_loop.on_update(move |elapsed| spectators.borrow_mut().update(elapsed));
+    }
 
     game_loop
-        .register_scene(TitleScene::new(snake_config.clone()))
-        .register_scene(SnakeScene::new(snake_config.clone()))
-        .run::<TitleScene>()
+        .on_resize({
+            let config = shared_config.clone();
+            move |columns, rows| config.borrow_mut().resize(columns, rows)
+        })
+        .register_scene(TitleScene::new(snake_config))
+        .register_scene_factory({
+            let config = shared_config.clone();
+            move || SettingsScene::new(config.clone(), options.config_path.clone())
+        })
+        .register_scene_factory({
+            let config = shared_config.clone();
+            let path = high_scores_path.clone();
+            move || HighScoresScene::new(config.clone(), path.clone())
+        })
+        .register_scene_factory({
+            let config = shared_config.clone();
+            let spectators = spectators.clone();
+            move || {
+                let scene = CampaignScene::new(
+                    config.clone(),
+                    Level::builtins().collect(),
+                    campaign_path.clone(),
+                );
+                let scene = if options.fixed_speed {
+                    scene.with_fixed_speed()
+                } else {
+                    scene
+                };
+
+                match &spectators {
+                    Some(spectators) => scene.with_spectators(spectators.clone()),
+                    None => scene,
+                }
+            }
+        })
+        .register_scene_factory({
+            let config = shared_config.clone();
+            let spectators = spectators.clone();
+            move || {
+                let scene = VersusScene::new(config.borrow().clone());
+                match &spectators {
+                    Some(spectators) => scene.with_spectators(spectators.clone()),
+                    None => scene,
+                }
+            }
+        })
+        .register_scene_factory({
+            let config = shared_config.clone();
+            let spectators = spectators.clone();
+            move || {
+                let scene = CpuVersusScene::new(
+                    config.borrow().clone(),
+                    options.opponents,
+                    options.difficulty,
+                );
+
+                match &spectators {
+                    Some(spectators) => scene.with_spectators(spectators.clone()),
+                    None => scene,
+                }
+            }
+        })
+        .register_scene_factory({
+            let config = shared_config.clone();
+            move || LevelEditorScene::new(config.clone(), options.editor_path.clone())
+        })
+        .register_scene_factory(move || {
+            let scene = SnakeScene::new(shared_config.borrow().clone());
+
+            // Bots don't get to put their scores in the high score table
+            let scene = match &options.bot {
+                Some((command, timeout)) => scene.with_bot(Bot::new(command.clone(), *timeout)),
+                None => scene.with_high_scores(high_scores_path.clone()),
+            };
+
+            let scene = match &spectators {
+                Some(spectators) => scene.with_spectators(spectators.clone()),
+                None => scene,
+            };
+
+            match &options.record {
+                Some(path) => scene.with_recorder(path.clone()),
+                None => scene,
+            }
+        });
+
+    Ok(())
 }
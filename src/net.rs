@@ -0,0 +1,440 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    cell::Cell,
+    io::{BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError, TrySendError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    PlayerInput,
+    bot::{BotSnake, Tile},
+    entities::food::FoodKind,
+    level::Level,
+};
+
+/// The newest protocol version this build speaks. It goes up whenever a
+/// message changes in a way the previous version couldn't read.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest protocol version this build still speaks.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+pub const DEFAULT_PORT: u16 = 7777;
+
+/// How many connections can be waiting to say hello at once. Each one has
+/// its own reader and writer threads, so any more are closed straight away.
+pub const MAX_PENDING: usize = 8;
+
+/// How long either side goes without hearing from the other before giving up
+/// on the connection. Servers send a snapshot every update and clients ping
+/// every `PING_INTERVAL`, so this only passes when something's gone wrong.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a client that isn't sending inputs lets the server know it's
+/// still there.
+pub const PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many messages can be waiting to be written to a peer before it's
+/// treated as too slow to keep up and disconnected.
+const SEND_QUEUE_SIZE: usize = 64;
+
+/// The longest line a peer can send, well over the size of a snapshot of a
+/// full board. Anything longer is treated as a message that isn't valid
+/// rather than read into memory.
+const MAX_MESSAGE_LENGTH: usize = 1024 * 1024;
+
+/// How long writing one message can take before the peer is given up on.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a peer being dropped waits for its queued messages to be
+/// written before closing the connection anyway.
+const CLOSE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Messages sent from a client to the server, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ClientMessage {
+    /// Must be the first message, giving the range of protocol versions the
+    /// client speaks.
+    Hello {
+        min_version: u32,
+        max_version: u32,
+        name: Option<String>,
+    },
+    /// Sent instead of a hello to watch the game without playing in it.
+    /// Spectators are sent the same snapshots as players, and anything they
+    /// send other than a ping or goodbye is ignored.
+    Watch {
+        min_version: u32,
+        max_version: u32,
+    },
+    Input {
+        input: PlayerInput,
+    },
+    Ping,
+    /// Sent before leaving so the server doesn't wait for a timeout.
+    Bye,
+}
+
+/// Messages sent from the server to a client, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ServerMessage {
+    /// The reply to a hello the server accepts, with the protocol version
+    /// both sides will speak and everything about the game that doesn't
+    /// change from one snapshot to the next.
+    Welcome {
+        version: u32,
+        id: usize,
+        level: Level,
+        wrap: bool,
+    },
+    /// The reply to a hello the server doesn't accept. The connection is
+    /// closed after it's sent.
+    Rejected {
+        reason: String,
+    },
+    Snapshot(Snapshot),
+    /// Sent to spectators when the game moves on to another level, before
+    /// the first snapshot played on it.
+    Level {
+        level: Level,
+        wrap: bool,
+    },
+    /// Sent before the server closes the connection.
+    Bye {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Fewer than two players have joined.
+    #[default]
+    Waiting,
+    Playing,
+    /// A short break before the next round, showing who won.
+    RoundOver,
+}
+
+/// The whole state of the game, sent to every client after each update.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    /// The round being played, or 0 for a game that isn't played in rounds.
+    pub round: u32,
+    pub phase: Phase,
+    /// The id of the player that won the last round, if it wasn't a draw.
+    pub winner: Option<usize>,
+    pub players: Vec<PlayerSnapshot>,
+    pub food: Option<Tile>,
+    /// Older servers only place apples, so they leave this out.
+    #[serde(default)]
+    pub food_kind: FoodKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub id: usize,
+    pub name: String,
+    /// Which spawn point and color the player has, from 0 to 3.
+    pub slot: usize,
+    /// Food eaten over every round.
+    pub score: u32,
+    pub rounds: u32,
+    /// The player's snake, while it's in the round.
+    pub snake: Option<BotSnake>,
+}
+
+/// Picks the newest protocol version both sides speak.
+pub fn negotiate(min_version: u32, max_version: u32) -> Result<u32> {
+    let version = max_version.min(PROTOCOL_VERSION);
+    if version < min_version.max(MIN_PROTOCOL_VERSION) {
+        bail!(
+            "Client speaks protocol versions {min_version} to {max_version} but the server speaks \
+             {MIN_PROTOCOL_VERSION} to {PROTOCOL_VERSION}"
+        );
+    }
+
+    Ok(version)
+}
+
+/// Starts listening on `port`, or on any free port if it's 0, and returns
+/// the connections as they're accepted along with the port.
+pub fn listen(port: u16) -> Result<(Receiver<TcpStream>, u16)> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .with_context(|| format!("Failed to listen on port {port}"))?;
+    let port = listener.local_addr()?.port();
+
+    // Connections are accepted on their own thread and handed over to be
+    // greeted on the next update
+    let (sender, connections) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().filter_map(Result::ok) {
+            if sender.send(stream).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok((connections, port))
+}
+
+/// A connection to the other side of a game. Messages are read and written
+/// on their own threads so a slow peer never holds up the game.
+#[derive(Debug)]
+pub struct Peer<In> {
+    stream: TcpStream,
+    outgoing: Option<SyncSender<String>>,
+    writer: Option<JoinHandle<()>>,
+    incoming: Receiver<Result<In, String>>,
+    silence: Duration,
+    /// Set once the peer falls too far behind, so there's no point waiting
+    /// for it to read what's queued.
+    stalled: Cell<bool>,
+}
+
+impl<In: DeserializeOwned + Send + 'static> Peer<In> {
+    pub fn new(stream: TcpStream) -> Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            while let Some(line) = read_line(&mut reader) {
+                let message = line.and_then(|line| {
+                    serde_json::from_slice(&line)
+                        .map_err(|error| format!("Received a message that isn't valid: {error}"))
+                });
+                let invalid = message.is_err();
+
+                if sender.send(message).is_err() || invalid {
+                    break;
+                }
+            }
+        });
+
+        let mut writer = stream.try_clone()?;
+        let (outgoing, lines) = mpsc::sync_channel::<String>(SEND_QUEUE_SIZE);
+        let writer = thread::spawn(move || {
+            for line in lines {
+                if writeln!(writer, "{line}")
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            stream,
+            outgoing: Some(outgoing),
+            writer: Some(writer),
+            incoming,
+            silence: Duration::ZERO,
+            stalled: Cell::new(false),
+        })
+    }
+
+    /// Connects to `address`, given as `host:port`.
+    pub fn connect(address: &str) -> Result<Self> {
+        let address = address
+            .to_socket_addrs()
+            .with_context(|| format!("Failed to look up {address}"))?
+            .next()
+            .ok_or_else(|| anyhow!("Failed to look up {address}"))?;
+
+        let stream = TcpStream::connect_timeout(&address, TIMEOUT)
+            .with_context(|| format!("Failed to connect to {address}"))?;
+
+        Self::new(stream)
+    }
+
+    /// Queues a message to be written. Peers that have disconnected or
+    /// aren't reading what they're sent are errors.
+    pub fn send(&self, message: &impl Serialize) -> Result<()> {
+        let line = serde_json::to_string(message)?;
+        let outgoing = self
+            .outgoing
+            .as_ref()
+            .ok_or_else(|| anyhow!("Connection closed"))?;
+
+        outgoing.try_send(line).map_err(|error| match error {
+            TrySendError::Full(_) => {
+                self.stalled.set(true);
+                anyhow!("Fell too far behind the game")
+            }
+            TrySendError::Disconnected(_) => anyhow!("Connection closed"),
+        })
+    }
+
+    /// Every message that's arrived since the last call. It's an error once
+    /// the connection has closed, a message can't be read or nothing has
+    /// arrived for `TIMEOUT`, counting `elapsed` as time spent waiting.
+    pub fn receive(&mut self, elapsed: &Duration) -> Result<Vec<In>> {
+        let mut messages = vec![];
+        loop {
+            match self.incoming.try_recv() {
+                Ok(message) => messages.push(message.map_err(|error| anyhow!(error))?),
+                Err(TryRecvError::Empty) => break,
+                // Whatever arrived before the connection closed is still
+                // handed over, leaving the error for the next call
+                Err(TryRecvError::Disconnected) if messages.is_empty() => {
+                    bail!("Connection closed")
+                }
+                Err(TryRecvError::Disconnected) => return Ok(messages),
+            }
+        }
+
+        self.silence = match messages.is_empty() {
+            true => self.silence + *elapsed,
+            false => Duration::ZERO,
+        };
+
+        if self.silence > TIMEOUT {
+            bail!("Timed out after {}s without a message", TIMEOUT.as_secs());
+        }
+
+        Ok(messages)
+    }
+
+    /// Waits up to `TIMEOUT` for the next message.
+    pub fn wait(&mut self) -> Result<In> {
+        match self.incoming.recv_timeout(TIMEOUT) {
+            Ok(message) => message.map_err(|error| anyhow!(error)),
+            Err(RecvTimeoutError::Timeout) => {
+                bail!("Timed out after {}s without a message", TIMEOUT.as_secs())
+            }
+            Err(RecvTimeoutError::Disconnected) => bail!("Connection closed"),
+        }
+    }
+}
+
+/// Reads the next line, up to `MAX_MESSAGE_LENGTH` bytes of it. Returns
+/// `None` once the connection has closed.
+fn read_line(reader: &mut impl BufRead) -> Option<Result<Vec<u8>, String>> {
+    let mut line = vec![];
+    let limit = MAX_MESSAGE_LENGTH as u64 + 1;
+    match reader.by_ref().take(limit).read_until(b'\n', &mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) if line.len() > MAX_MESSAGE_LENGTH => Some(Err(format!(
+            "Received a message longer than {MAX_MESSAGE_LENGTH} bytes"
+        ))),
+        Ok(_) => Some(Ok(line)),
+    }
+}
+
+impl<In> Drop for Peer<In> {
+    /// Writes whatever is still queued, such as a goodbye, before closing the
+    /// connection. A peer that isn't reading only gets `CLOSE_TIMEOUT` for
+    /// that, or none at all once it's stalled, so dropping it never holds up
+    /// the game for the whole `WRITE_TIMEOUT`.
+    fn drop(&mut self) {
+        self.outgoing = None;
+        let Some(writer) = self.writer.take() else {
+            return;
+        };
+
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        while !self.stalled.get() && !writer.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // Closing the connection wakes the writer if it's still blocked
+        let _ = self.stream.shutdown(Shutdown::Both);
+        let _ = writer.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn it_picks_the_newest_version_both_sides_speak() {
+        assert_eq!(negotiate(1, 1).unwrap(), 1);
+        assert_eq!(negotiate(1, 9).unwrap(), PROTOCOL_VERSION);
+        assert!(negotiate(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 2).is_err());
+        assert!(negotiate(0, MIN_PROTOCOL_VERSION - 1).is_err());
+    }
+
+    #[test]
+    fn it_writes_one_tagged_message_per_line() {
+        let hello = ClientMessage::Hello {
+            min_version: 1,
+            max_version: 1,
+            name: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&hello).unwrap(),
+            r#"{"type":"hello","min_version":1,"max_version":1,"name":null}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientMessage::Input {
+                input: PlayerInput::Up
+            })
+            .unwrap(),
+            r#"{"type":"input","input":"Up"}"#
+        );
+    }
+
+    #[test]
+    fn it_sends_messages_between_peers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let client = Peer::<ServerMessage>::connect(&address).unwrap();
+        let mut server = Peer::<ClientMessage>::new(listener.accept().unwrap().0).unwrap();
+
+        client.send(&ClientMessage::Ping).unwrap();
+        assert_eq!(server.wait().unwrap(), ClientMessage::Ping);
+
+        drop(client);
+        assert!(server.wait().is_err());
+    }
+
+    #[test]
+    fn it_closes_peers_that_stopped_reading_without_waiting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let server = Peer::<ClientMessage>::new(listener.accept().unwrap().0).unwrap();
+
+        // Enough to fill the socket's buffers and then the send queue
+        let message = "x".repeat(16 * 1024);
+        while server.send(&message).is_ok() {}
+
+        let start = Instant::now();
+        drop(server);
+        assert!(start.elapsed() < WRITE_TIMEOUT / 2);
+    }
+
+    #[test]
+    fn it_rejects_messages_that_are_too_long() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut server = Peer::<ClientMessage>::new(listener.accept().unwrap().0).unwrap();
+
+        client.write_all(&[b' '; MAX_MESSAGE_LENGTH + 1]).unwrap();
+        let error = server.wait().unwrap_err();
+        assert!(error.to_string().contains("longer than"));
+    }
+
+    #[test]
+    fn it_times_out_quiet_peers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut server = Peer::<ClientMessage>::new(listener.accept().unwrap().0).unwrap();
+
+        assert!(server.receive(&(TIMEOUT / 2)).unwrap().is_empty());
+        assert!(server.receive(&TIMEOUT).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{GameConfig, PlayerInput, files};
+
+/// Bumped whenever a change to the simulation or file layout would cause an
+/// older replay to play back differently.
+pub const REPLAY_VERSION: u32 = 4;
+
+/// How many simulation ticks are run per frame while fast-forwarding.
+const FAST_FORWARD_RATE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub input: PlayerInput,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub config: GameConfig,
+    pub inputs: Vec<ReplayInput>,
+}
+
+impl Replay {
+    /// Starts an empty replay. The seed is stored on the config so the food
+    /// positions are reproduced on playback.
+    pub fn new(config: &GameConfig, seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            config: GameConfig {
+                seed: Some(seed),
+                ..config.clone()
+            },
+            inputs: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = files::read(path, "replay")?;
+        let replay: Self = files::parse_json(path, "replay", &content)?;
+
+        if replay.version != REPLAY_VERSION {
+            bail!(
+                "Replay {} has version {}, but only version {REPLAY_VERSION} is supported",
+                path.display(),
+                replay.version,
+            );
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        files::save_json(path, "replay", self)
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: PathBuf, replay: Replay) -> Self {
+        Self { path, replay }
+    }
+
+    pub fn record(&mut self, tick: u64, input: PlayerInput) {
+        self.replay.inputs.push(ReplayInput { tick, input });
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.replay.save(&self.path)
+    }
+}
+
+#[derive(Debug)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_input: usize,
+    paused: bool,
+    fast_forward: bool,
+    pending_steps: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_input: 0,
+            paused: false,
+            fast_forward: false,
+            pending_steps: 0,
+        }
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.replay.config
+    }
+
+    /// Returns the recorded inputs that were applied before `tick` was run.
+    pub fn inputs_for(&mut self, tick: u64) -> Vec<PlayerInput> {
+        let inputs: Vec<_> = self.replay.inputs[self.next_input..]
+            .iter()
+            .take_while(|recorded| recorded.tick <= tick)
+            .map(|recorded| recorded.input)
+            .collect();
+
+        self.next_input += inputs.len();
+        inputs
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+    }
+
+    /// Queues a single tick to run while paused.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    /// The number of simulation ticks to run for the current frame.
+    pub fn ticks_to_run(&mut self) -> usize {
+        if self.paused {
+            return std::mem::take(&mut self.pending_steps);
+        }
+
+        if self.fast_forward {
+            FAST_FORWARD_RATE
+        } else {
+            1
+        }
+    }
+
+    pub fn status(&self) -> String {
+        match (self.paused, self.fast_forward) {
+            (true, _) => " REPLAY ❚❚ [p]lay [n]ext ".into(),
+            (false, true) => format!(" REPLAY ▶▶ {FAST_FORWARD_RATE}x [f] "),
+            (false, false) => " REPLAY ▶ [p]ause [f]ast ".into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use std::{fs, time::Duration};
+
+    use super::*;
+    use crate::{
+        engine::traits::GameScene, net::Snapshot, scenes::snake::SnakeScene,
+        test_support::test_config,
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            rows: 16,
+            columns: 40,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    const FRAME: Duration = Duration::from_millis(66);
+
+    /// Runs `ticks` updates of the scene, returning the state of the game
+    /// after each one.
+    fn run(scene: &mut SnakeScene, ticks: usize) -> Vec<Snapshot> {
+        (0..ticks)
+            .map(|_| {
+                scene.update(&FRAME).unwrap();
+                scene.snapshot()
+            })
+            .collect()
+    }
+
+    fn press(scene: &mut SnakeScene, code: KeyCode) {
+        scene
+            .process_input(&Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap();
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{name}.replay", std::process::id()))
+    }
+
+    #[test]
+    fn it_reproduces_a_recorded_game() {
+        let path = temp_path("reproduce");
+        let mut recording = SnakeScene::new(config()).with_recorder(path.clone());
+
+        press(&mut recording, KeyCode::Down);
+        let mut expected = run(&mut recording, 4);
+        press(&mut recording, KeyCode::Left);
+        expected.extend(run(&mut recording, 3));
+        press(&mut recording, KeyCode::Down);
+        expected.extend(run(&mut recording, 40));
+        assert!(recording.is_over());
+
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.inputs.len(), 3);
+
+        let mut playback = SnakeScene::from_replay(replay);
+        let actual = run(&mut playback, expected.len());
+
+        assert!(playback.is_over());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn it_rejects_unsupported_versions() {
+        let path = temp_path("version");
+        let mut replay = Replay::new(&config(), 1);
+        replay.version = REPLAY_VERSION + 1;
+        replay.save(&path).unwrap();
+
+        let result = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,546 @@
+use anyhow::Result;
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{any::TypeId, cell::RefCell, path::PathBuf, rc::Rc, time::Duration};
+
+use crate::{
+    GameConfig, PlayerInput,
+    campaign::CampaignProgress,
+    config::SharedConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{menu::Menu, text::Text},
+    level::Level,
+    spectators::Spectators,
+};
+
+use super::{snake::SnakeScene, title::TitleScene};
+
+const TITLE: &str = "CAMPAIGN";
+const HELP_TEXT: &str = "↑/↓ select   enter play   esc back";
+const CONTINUE_TEXT: &str = "PRESS ENTER TO CONTINUE";
+
+#[derive(Debug)]
+enum CampaignState {
+    Selecting,
+    Playing(Box<SnakeScene>),
+    /// Between levels, holding the snake's length and speed at the end of
+    /// the level just cleared.
+    Transition {
+        length: usize,
+        speed: f32,
+    },
+    Complete,
+}
+
+/// Plays the levels in order, moving on to the next one each time a level's
+/// target is reached. Levels are unlocked as they're reached, and any
+/// unlocked level can be started from the level select menu.
+#[derive(Debug)]
+pub struct CampaignScene {
+    config: SharedConfig,
+    levels: Vec<Level>,
+    progress: CampaignProgress,
+    /// Where the progress is saved, or `None` when the file couldn't be read
+    /// so it isn't saved over.
+    progress_path: Option<PathBuf>,
+    level_index: usize,
+    /// The length the current level started with, so retrying it starts the
+    /// same way.
+    start_length: Option<usize>,
+    state: CampaignState,
+    menu: Menu,
+    texts: Vec<Text>,
+    /// The last error reading or saving the progress, shown below the text.
+    error: Option<String>,
+    /// Whether the speed was given on the command line, so it's used over
+    /// the levels' speeds.
+    fixed_speed: bool,
+    spectators: Option<Rc<RefCell<Spectators>>>,
+}
+
+impl CampaignScene {
+    pub fn new(config: SharedConfig, levels: Vec<Level>, progress_path: PathBuf) -> Self {
+        // A progress file that can't be read shouldn't lock the player out of
+        // the first level, but it's left alone in case it can be fixed
+        let (progress, progress_path, error) = match CampaignProgress::load(&progress_path) {
+            Ok(progress) => (progress, Some(progress_path), None),
+            Err(error) => (
+                CampaignProgress::default(),
+                None,
+                Some(format!("{error:#}")),
+            ),
+        };
+        let level_index = progress
+            .highest_unlocked()
+            .min(levels.len().saturating_sub(1));
+
+        let mut scene = Self {
+            config,
+            levels,
+            progress,
+            progress_path,
+            level_index,
+            start_length: None,
+            state: CampaignState::Selecting,
+            menu: Menu::new(Vec::<String>::new(), Point::default()),
+            texts: vec![],
+            error,
+            fixed_speed: false,
+            spectators: None,
+        };
+
+        scene.update_layout();
+        scene
+    }
+
+    /// Starts every level at the configured speed rather than the level's,
+    /// like `--speed` does for a single level.
+    pub fn with_fixed_speed(mut self) -> Self {
+        self.fixed_speed = true;
+        self
+    }
+
+    /// Sends every update of the levels played to the spectators.
+    pub fn with_spectators(mut self, spectators: Rc<RefCell<Spectators>>) -> Self {
+        self.spectators = Some(spectators);
+        self
+    }
+
+    fn center(&self) -> Point {
+        let config = self.config.borrow();
+        Point::new(config.columns / 2, config.rows / 2)
+    }
+
+    /// Rebuilds the text for the current state, centered on the screen.
+    fn update_layout(&mut self) {
+        let center = self.center();
+        let top = center.saturating_sub(&Point::new(0, self.levels.len() / 2 + 3));
+
+        let options = self.levels.iter().enumerate().map(|(i, level)| {
+            let status = match (self.progress.is_unlocked(i), level.target) {
+                (false, _) => "LOCKED".to_string(),
+                (true, Some(target)) => format!("TARGET {target}"),
+                (true, None) => String::new(),
+            };
+
+            format!(
+                " {:>2}  {:<12}{:>10} ",
+                i + 1,
+                level.name.to_uppercase(),
+                status
+            )
+        });
+
+        self.menu = Menu::new(options, top + Point::new(0usize, 2));
+        self.menu.select(self.level_index);
+
+        let lines = match &self.state {
+            CampaignState::Selecting => {
+                let bottom = top + Point::new(0usize, self.levels.len() + 3);
+                self.texts = vec![
+                    Self::line(TITLE, top).with_fg(Color::Yellow),
+                    Self::line(HELP_TEXT, bottom),
+                ];
+
+                self.push_error(bottom + Point::new(0usize, 2));
+                return;
+            }
+            CampaignState::Playing(_) => return,
+            CampaignState::Transition { .. } => {
+                let level = &self.levels[self.level_index];
+                let mut lines = vec![
+                    "LEVEL CLEAR".to_string(),
+                    String::new(),
+                    format!(
+                        "NEXT: LEVEL {} {}",
+                        self.level_index + 1,
+                        level.name.to_uppercase()
+                    ),
+                ];
+
+                if let Some(target) = level.target {
+                    lines.push(format!("SCORE {target} TO CLEAR"));
+                }
+
+                lines
+            }
+            CampaignState::Complete => vec![
+                "CAMPAIGN COMPLETE".to_string(),
+                String::new(),
+                format!("ALL {} LEVELS CLEARED", self.levels.len()),
+            ],
+        };
+
+        let top = center.saturating_sub(&Point::new(0, lines.len() / 2 + 1));
+        self.texts = lines
+            .iter()
+            .chain(&[String::new(), CONTINUE_TEXT.to_string()])
+            .enumerate()
+            .map(|(i, line)| Self::line(line, top + Point::new(0, i)))
+            .collect();
+
+        self.texts[0].style.fg = Color::Yellow;
+        self.push_error(top + Point::new(0, self.texts.len() + 1));
+    }
+
+    fn push_error(&mut self, center: Point) {
+        if let Some(error) = &self.error {
+            let text = Self::line(error, center).with_fg(Color::Red);
+            self.texts.push(text);
+        }
+    }
+
+    fn line(value: &str, center: Point) -> Text {
+        Text::default().with_value(value).center(center).show()
+    }
+
+    /// The config for a level, with the snake's speed and length kept from
+    /// the previous level when the level's rules say to. The level's speed
+    /// is used over the configured one unless the speed is fixed.
+    fn level_config(&self, level_index: usize, previous_speed: Option<f32>) -> GameConfig {
+        let config = self.config.borrow().clone();
+        let level = self.levels[level_index].clone();
+        let mut snake = config.snake.clone();
+
+        snake.speed = match previous_speed {
+            Some(speed) if level.carry_speed => speed,
+            _ if self.fixed_speed => snake.speed,
+            _ => level.speed.unwrap_or(snake.speed),
+        };
+
+        GameConfig {
+            snake,
+            level: Some(level),
+            ..config
+        }
+    }
+
+    fn create_scene(&self, config: GameConfig, start_length: Option<usize>) -> SnakeScene {
+        let scene = SnakeScene::new(config);
+        let scene = match start_length {
+            Some(length) => scene.with_length(length),
+            None => scene,
+        };
+
+        match &self.spectators {
+            Some(spectators) => scene.with_spectators(spectators.clone()),
+            None => scene,
+        }
+    }
+
+    fn start_level(&mut self, level_index: usize, previous: Option<(usize, f32)>) {
+        let carry_length = self.levels[level_index].carry_length;
+        let config = self.level_config(level_index, previous.map(|(_, speed)| speed));
+        let start_length = previous.map(|(length, _)| length).filter(|_| carry_length);
+
+        let scene = self.create_scene(config, start_length);
+
+        self.level_index = level_index;
+        self.start_length = start_length;
+        self.state = CampaignState::Playing(Box::new(scene));
+    }
+
+    fn restart_level(&mut self) {
+        let config = match &self.state {
+            CampaignState::Playing(scene) => scene.config().clone(),
+            _ => self.level_config(self.level_index, None),
+        };
+
+        let scene = self.create_scene(config, self.start_length);
+        self.state = CampaignState::Playing(Box::new(scene));
+    }
+
+    /// Moves on from the level just cleared. Progress that can't be saved is
+    /// shown as an error rather than ending the campaign.
+    fn clear_level(&mut self, length: usize, speed: f32) {
+        if self.progress.clear(self.level_index)
+            && let Some(path) = &self.progress_path
+            && let Err(error) = self.progress.save(path)
+        {
+            self.error = Some(format!("{error:#}"));
+        }
+
+        self.state = if self.level_index + 1 < self.levels.len() {
+            self.level_index += 1;
+            CampaignState::Transition { length, speed }
+        } else {
+            CampaignState::Complete
+        };
+
+        self.update_layout();
+    }
+
+    /// Playing again from the game over menu retries the level instead of
+    /// starting a regular game.
+    fn forward_signal(&mut self, signal: GameLoopSignal) -> GameLoopSignal {
+        match signal {
+            GameLoopSignal::Load(id) if id == TypeId::of::<SnakeScene>() => {
+                self.restart_level();
+                GameLoopSignal::Run
+            }
+            signal => signal,
+        }
+    }
+
+    fn process_menu_input(&mut self, input: PlayerInput) -> GameLoopSignal {
+        match (input, &self.state) {
+            (PlayerInput::Quit, _) => return GameLoopSignal::load_scene::<TitleScene>(),
+            (PlayerInput::Up, CampaignState::Selecting) => self.menu.select_previous(),
+            (PlayerInput::Down, CampaignState::Selecting) => self.menu.select_next(),
+            (PlayerInput::Select, CampaignState::Selecting) => {
+                let level_index = self.menu.selected_index();
+                if self.progress.is_unlocked(level_index) {
+                    self.start_level(level_index, None);
+                }
+            }
+            (PlayerInput::Select, CampaignState::Transition { length, speed }) => {
+                let previous = (*length, *speed);
+                self.start_level(self.level_index, Some(previous));
+            }
+            (PlayerInput::Select, CampaignState::Complete) => {
+                return GameLoopSignal::load_scene::<TitleScene>();
+            }
+            _ => (),
+        }
+
+        GameLoopSignal::Run
+    }
+}
+
+impl GameScene for CampaignScene {
+    fn draw(&mut self, timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        match &mut self.state {
+            CampaignState::Playing(scene) => scene.draw(timestep),
+            CampaignState::Selecting => self
+                .texts
+                .iter()
+                .flat_map(|text| text.draw())
+                .chain(self.menu.draw())
+                .collect(),
+            _ => self.texts.iter().flat_map(|text| text.draw()).collect(),
+        }
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        let CampaignState::Playing(scene) = &mut self.state else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        let signal = scene.update(elapsed)?;
+        if scene.is_level_clear() {
+            let (length, speed) = scene.snake_stats();
+            self.clear_level(length, speed);
+        }
+
+        Ok(self.forward_signal(signal))
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        if let CampaignState::Playing(scene) = &mut self.state {
+            let signal = scene.process_input(event)?;
+            return Ok(self.forward_signal(signal));
+        }
+
+        let input = match event {
+            Event::Key(e) => match e.code {
+                KeyCode::Enter => PlayerInput::Select,
+                KeyCode::Esc | KeyCode::Char('q') => PlayerInput::Quit,
+                KeyCode::Char('w') | KeyCode::Up => PlayerInput::Up,
+                KeyCode::Char('s') | KeyCode::Down => PlayerInput::Down,
+                _ => PlayerInput::Noop,
+            },
+            _ => PlayerInput::Noop,
+        };
+
+        Ok(self.process_menu_input(input))
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.borrow().frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        match &self.state {
+            CampaignState::Playing(scene) => scene.min_size(),
+            _ => (HELP_TEXT.chars().count() + 2, self.levels.len() + 8),
+        }
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        if let CampaignState::Playing(scene) = &mut self.state {
+            scene.resize(columns, rows)?;
+        }
+
+        self.update_layout();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use std::{cell::RefCell, fs, path::Path, rc::Rc};
+
+    use super::*;
+    use crate::{
+        config::SnakeConfig,
+        engine::{backend::MemoryBackend, game_loop::GameLoop, input::ScriptedInput},
+        test_support::{headless_loop, test_config},
+    };
+
+    // The seed puts the first food on the tile in front of the snake so each
+    // level is cleared on the first move
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: 2,
+                ..test_config().snake
+            },
+            rows: 32,
+            columns: 60,
+            show_border: false,
+            ..test_config()
+        }
+    }
+
+    fn levels() -> Vec<Level> {
+        vec![
+            "name: One\ntarget: 1\nspeed: 10\n---\nS.".parse().unwrap(),
+            "name: Two\ntarget: 1\ncarry: length, speed\n---\nS."
+                .parse()
+                .unwrap(),
+        ]
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{name}.json", std::process::id()))
+    }
+
+    fn create_game_loop(input: &ScriptedInput, path: &Path) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        let shared = Rc::new(RefCell::new(config()));
+        game_loop
+            .register_scene(CampaignScene::new(shared, levels(), path.to_path_buf()))
+            .register_scene(TitleScene::new(config()))
+            .load_scene::<CampaignScene>();
+
+        game_loop
+    }
+
+    #[test]
+    fn it_moves_through_the_levels_and_saves_progress() {
+        let path = temp_path("campaign-progress");
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, &path);
+
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains("LOCKED"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(8).unwrap();
+        assert!(game_loop.renderer().backend().contains("NEXT: LEVEL 2 TWO"));
+        assert!(CampaignProgress::load(&path).unwrap().is_unlocked(1));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(8).unwrap();
+        assert!(game_loop.renderer().backend().contains("CAMPAIGN COMPLETE"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(2).unwrap();
+        assert!(game_loop.renderer().backend().contains("NEW GAME"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn it_only_starts_unlocked_levels() {
+        let path = temp_path("campaign-locked");
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, &path);
+
+        input.push_key(KeyCode::Down);
+        game_loop.step(1).unwrap();
+        input.push_key(KeyCode::Enter);
+        game_loop.step(1).unwrap();
+
+        assert!(game_loop.renderer().backend().contains(TITLE));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn it_keeps_playing_when_the_progress_cant_be_saved() {
+        // The progress can't be saved under a path that's a file
+        let parent = temp_path("campaign-unwritable");
+        fs::write(&parent, "").unwrap();
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, &parent.join("campaign.json"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(8).unwrap();
+        fs::remove_file(&parent).unwrap();
+
+        assert!(game_loop.renderer().backend().contains("NEXT: LEVEL 2 TWO"));
+        assert!(game_loop.renderer().backend().contains("Failed to create"));
+    }
+
+    #[test]
+    fn it_leaves_progress_it_cant_read_alone() {
+        let path = temp_path("campaign-corrupt");
+        fs::write(&path, "not json").unwrap();
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, &path);
+
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains("Failed to parse"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(8).unwrap();
+        assert!(game_loop.renderer().backend().contains("NEXT: LEVEL 2 TWO"));
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(content, "not json");
+    }
+
+    #[test]
+    fn it_carries_length_and_speed_when_the_level_says_to() {
+        let shared = Rc::new(RefCell::new(config()));
+        let mut scene = CampaignScene::new(shared, levels(), temp_path("campaign-carry"));
+
+        assert_eq!(scene.level_config(0, None).snake.speed, 10.0);
+        assert_eq!(scene.level_config(1, Some(12.0)).snake.speed, 12.0);
+        assert_eq!(
+            scene.level_config(1, None).snake.speed,
+            config().snake.speed
+        );
+
+        scene.start_level(1, Some((9, 12.0)));
+        let CampaignState::Playing(level) = &scene.state else {
+            panic!("expected the level to be playing");
+        };
+
+        assert_eq!(level.snake_stats(), (9, 12.0));
+    }
+
+    #[test]
+    fn it_keeps_a_fixed_speed_over_the_levels() {
+        let shared = Rc::new(RefCell::new(config()));
+        let scene =
+            CampaignScene::new(shared, levels(), temp_path("campaign-fixed")).with_fixed_speed();
+
+        assert_eq!(
+            scene.level_config(0, None).snake.speed,
+            config().snake.speed
+        );
+        assert_eq!(scene.level_config(1, Some(12.0)).snake.speed, 12.0);
+    }
+}
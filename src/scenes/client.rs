@@ -0,0 +1,293 @@
+use anyhow::{Result, bail};
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::time::Duration;
+
+use crate::{
+    GameConfig, PlayerInput,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{board::Board, text::Text},
+    net::{
+        ClientMessage, MIN_PROTOCOL_VERSION, PING_INTERVAL, PROTOCOL_VERSION, Peer, ServerMessage,
+    },
+};
+
+const DISCONNECTED_TEXT: &str = "DISCONNECTED";
+const QUIT_TEXT: &str = "PRESS Q TO QUIT";
+
+/// Plays in a game hosted by a `ServerScene`. Key presses are sent to the
+/// server as they happen and the game is drawn from the server's snapshots,
+/// so the client never decides anything about the game itself.
+#[derive(Debug)]
+pub struct ClientScene {
+    config: GameConfig,
+    peer: Peer<ServerMessage>,
+    id: usize,
+    connected: bool,
+    since_sent: Duration,
+    board: Board,
+    player_text: Text,
+    disconnected_text: Text,
+}
+
+impl ClientScene {
+    /// Connects to the server at `address` and waits for it to let the
+    /// player in.
+    pub fn join(config: GameConfig, address: &str, name: Option<String>) -> Result<Self> {
+        let mut peer = Peer::connect(address)?;
+        peer.send(&ClientMessage::Hello {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            name,
+        })?;
+
+        let (id, level, wrap) = match peer.wait()? {
+            ServerMessage::Welcome {
+                id, level, wrap, ..
+            } => (id, level, wrap),
+            ServerMessage::Rejected { reason } => bail!("The server turned us away: {reason}"),
+            message => bail!("Expected a welcome from the server, got {message:?}"),
+        };
+
+        let board = Board::new(&config, level, wrap);
+        let center = board.world().get_center_position();
+        let (_, bottom) = board.world().min_size();
+
+        Ok(Self {
+            player_text: Text::default()
+                .center((config.columns / 2, bottom.saturating_sub(1)))
+                .with_fg(Color::Yellow),
+            disconnected_text: Text::default()
+                .center(center.saturating_sub(&Point::new(0, 2)))
+                .with_fg(Color::Red),
+            config,
+            peer,
+            id,
+            connected: true,
+            since_sent: Duration::ZERO,
+            board,
+        })
+    }
+
+    fn send(&mut self, message: &ClientMessage) {
+        self.since_sent = Duration::ZERO;
+        if let Err(error) = self.peer.send(message) {
+            self.disconnect(&error.to_string());
+        }
+    }
+
+    fn disconnect(&mut self, reason: &str) {
+        self.connected = false;
+        self.disconnected_text
+            .update_value(format!("{DISCONNECTED_TEXT}\n\n{reason}\n\n{QUIT_TEXT}"));
+        self.disconnected_text.visible = true;
+    }
+
+    fn update_player_text(&mut self) {
+        let player = self
+            .board
+            .snapshot()
+            .players
+            .iter()
+            .find(|player| player.id == self.id);
+
+        self.player_text.visible = player.is_some();
+        if let Some(player) = player {
+            let name = player.name.clone();
+            self.player_text
+                .update_value(format!(" PLAYING AS {name} "));
+        }
+    }
+}
+
+impl GameScene for ClientScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        self.board
+            .draw()
+            .into_iter()
+            .chain(self.player_text.draw())
+            .chain(self.disconnected_text.draw())
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        if !self.connected {
+            return Ok(GameLoopSignal::Run);
+        }
+
+        let messages = match self.peer.receive(elapsed) {
+            Ok(messages) => messages,
+            Err(error) => {
+                self.disconnect(&error.to_string());
+                return Ok(GameLoopSignal::Run);
+            }
+        };
+
+        for message in messages {
+            match message {
+                ServerMessage::Snapshot(snapshot) => self.board.update(snapshot),
+                ServerMessage::Bye { reason } | ServerMessage::Rejected { reason } => {
+                    self.disconnect(&reason);
+                    return Ok(GameLoopSignal::Run);
+                }
+                ServerMessage::Welcome { .. } | ServerMessage::Level { .. } => {}
+            }
+        }
+
+        self.update_player_text();
+
+        self.since_sent += *elapsed;
+        if self.since_sent >= PING_INTERVAL {
+            self.send(&ClientMessage::Ping);
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let Event::Key(e) = event else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        let input = match e.code {
+            KeyCode::Char('q') => {
+                if self.connected {
+                    self.send(&ClientMessage::Bye);
+                }
+
+                return Ok(GameLoopSignal::Stop);
+            }
+            KeyCode::Up | KeyCode::Char('w') => PlayerInput::Up,
+            KeyCode::Down | KeyCode::Char('s') => PlayerInput::Down,
+            KeyCode::Left | KeyCode::Char('a') => PlayerInput::Left,
+            KeyCode::Right | KeyCode::Char('d') => PlayerInput::Right,
+            _ => return Ok(GameLoopSignal::Run),
+        };
+
+        if self.connected {
+            self.send(&ClientMessage::Input { input });
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        self.board.world().min_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{backend::MemoryBackend, game_loop::GameLoop, input::ScriptedInput},
+        scenes::server::ServerScene,
+        test_support::{headless_loop, test_config},
+    };
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+            mpsc,
+        },
+        thread::{self, JoinHandle},
+    };
+
+    fn create_game_loop<T: GameScene>(input: &ScriptedInput, scene: T) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&test_config(), input);
+
+        game_loop.register_scene(scene).load_scene::<T>();
+        game_loop
+    }
+
+    /// Runs a server on its own thread until `running` is cleared, returning
+    /// its address.
+    fn serve(running: Arc<AtomicBool>) -> (String, JoinHandle<()>) {
+        let (sender, address) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let scene = ServerScene::bind(test_config(), 0).unwrap();
+            sender.send(format!("127.0.0.1:{}", scene.port())).unwrap();
+
+            let mut game_loop = create_game_loop(&ScriptedInput::new(), scene);
+            while running.load(Ordering::Relaxed) {
+                game_loop.step(1).unwrap();
+                thread::sleep(Duration::from_millis(2));
+            }
+        });
+
+        (address.recv().unwrap(), server)
+    }
+
+    fn step_until(game_loop: &mut GameLoop<MemoryBackend>, text: &str) {
+        for _ in 0..500 {
+            game_loop.step(1).unwrap();
+            if game_loop.renderer().backend().contains(text) {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        panic!("{text} was never drawn");
+    }
+
+    #[test]
+    fn it_draws_the_game_the_server_is_playing() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (address, server) = serve(running.clone());
+
+        let input = ScriptedInput::new();
+        let client = ClientScene::join(test_config(), &address, Some("kyle".into())).unwrap();
+        let mut game_loop = create_game_loop(&input, client);
+
+        step_until(&mut game_loop, "PLAYING AS KYLE");
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("WAITING FOR PLAYERS")
+        );
+
+        let other = ClientScene::join(test_config(), &address, None).unwrap();
+        step_until(&mut game_loop, "ROUND 1  KYLE 0  P2 0");
+
+        // Leaving ends the round for the player still there
+        drop(other);
+        step_until(&mut game_loop, "KYLE WINS THE ROUND");
+
+        input.push_key(KeyCode::Char('q'));
+        assert!(!game_loop.step(1).unwrap());
+
+        running.store(false, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn it_shows_why_the_server_went_away() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (address, server) = serve(running.clone());
+
+        let input = ScriptedInput::new();
+        let client = ClientScene::join(test_config(), &address, None).unwrap();
+        let mut game_loop = create_game_loop(&input, client);
+
+        step_until(&mut game_loop, "WAITING FOR PLAYERS");
+        running.store(false, Ordering::Relaxed);
+        server.join().unwrap();
+
+        step_until(&mut game_loop, DISCONNECTED_TEXT);
+        assert!(game_loop.renderer().backend().contains(QUIT_TEXT));
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    PlayerInput,
+    config::SharedConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::text::Text,
+    high_scores::{HighScoreTable, HighScores, MAX_ENTRIES, RuleSet},
+};
+
+use super::title::TitleScene;
+
+const TITLE: &str = "HIGH SCORES";
+const HELP_TEXT: &str = "←/→ rule set   esc back";
+const EMPTY_TEXT: &str = "NO SCORES YET";
+
+/// Fits a full table row and every entry between the title and help text.
+const MIN_SIZE: (usize, usize) = (68, MAX_ENTRIES + 8);
+
+/// Lists the saved high scores, one rule set at a time.
+#[derive(Debug)]
+pub struct HighScoresScene {
+    config: SharedConfig,
+    path: PathBuf,
+    tables: Vec<HighScoreTable>,
+    selected_index: usize,
+    center: Point,
+    title_text: Text,
+    rules_text: Text,
+    help_text: Text,
+    rows: Vec<Text>,
+}
+
+impl HighScoresScene {
+    pub fn new(config: SharedConfig, path: PathBuf) -> Self {
+        let (rules, center) = {
+            let config = config.borrow();
+            (
+                RuleSet::from(&*config),
+                Point::new(config.columns / 2, config.rows / 2),
+            )
+        };
+
+        let top = center.saturating_sub(&Point::new(0, (MAX_ENTRIES + 6) / 2));
+        let title_text = Text::default()
+            .with_value(TITLE)
+            .center(top)
+            .with_fg(Color::Yellow)
+            .show();
+
+        let rules_text = Text::default().center(top + Point::new(0usize, 2)).show();
+        let help_text = Text::default()
+            .with_value(HELP_TEXT)
+            .center(top + Point::new(0usize, MAX_ENTRIES + 6))
+            .show();
+
+        let (mut tables, error) = match HighScores::load(&path) {
+            Ok(high_scores) => (high_scores.tables().to_vec(), None),
+            Err(error) => (vec![], Some(error)),
+        };
+
+        // Always start on the rules the next game will be played with
+        let selected_index = match tables.iter().position(|table| table.rules == rules) {
+            Some(index) => index,
+            None => {
+                tables.insert(
+                    0,
+                    HighScoreTable {
+                        rules,
+                        entries: vec![],
+                    },
+                );
+
+                0
+            }
+        };
+
+        let mut scene = Self {
+            config,
+            path,
+            tables,
+            selected_index,
+            center: top + Point::new(0usize, 4),
+            title_text,
+            rules_text,
+            help_text,
+            rows: vec![],
+        };
+
+        scene.update_rows();
+
+        if let Some(error) = error {
+            scene.rows = vec![
+                Text::default()
+                    .with_value(format!("Failed to load high scores: {error}"))
+                    .center(scene.center)
+                    .with_fg(Color::Red)
+                    .show(),
+            ];
+        }
+
+        scene
+    }
+
+    fn update_rows(&mut self) {
+        let table = &self.tables[self.selected_index];
+        self.rules_text.update_value(format!("< {} >", table.rules));
+
+        let header = format!(
+            "{:>2}  {:<10} {:>5} {:>4} {:>6}  {:<10}  {:<20}",
+            "#", "NAME", "SCORE", "LEN", "TIME", "DATE", "SEED"
+        );
+
+        let rows = table.entries.iter().enumerate().map(|(rank, entry)| {
+            format!(
+                "{:>2}  {:<10} {:>5} {:>4} {:>6}  {:<10}  {:<20}",
+                rank + 1,
+                entry.name,
+                entry.score,
+                entry.length,
+                entry.format_duration(),
+                entry.format_date(),
+                entry.seed,
+            )
+        });
+
+        self.rows = if table.entries.is_empty() {
+            vec![
+                Text::default()
+                    .with_value(EMPTY_TEXT)
+                    .center(self.center + Point::new(0usize, 2))
+                    .show(),
+            ]
+        } else {
+            std::iter::once(header)
+                .chain(rows)
+                .enumerate()
+                .map(|(i, row)| {
+                    let text = Text::default()
+                        .with_value(row)
+                        .center(self.center + Point::new(0usize, i))
+                        .show();
+
+                    if i == 0 {
+                        text.with_fg(Color::Yellow)
+                    } else {
+                        text
+                    }
+                })
+                .collect()
+        };
+    }
+
+    fn select_table(&mut self, direction: isize) {
+        let next =
+            (self.selected_index as isize + direction).rem_euclid(self.tables.len() as isize);
+        self.selected_index = next as usize;
+        self.update_rows();
+    }
+}
+
+impl GameScene for HighScoresScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        vec![
+            self.title_text.draw(),
+            self.rules_text.draw(),
+            self.rows.iter().flat_map(|row| row.draw()).collect(),
+            self.help_text.draw(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn update(&mut self, _elapsed: &Duration) -> Result<GameLoopSignal> {
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        MIN_SIZE
+    }
+
+    fn resize(&mut self, _columns: usize, _rows: usize) -> Result<()> {
+        // The shared config already has the new size
+        let selected_index = self.selected_index;
+        *self = Self::new(self.config.clone(), self.path.clone());
+        if selected_index < self.tables.len() {
+            self.selected_index = selected_index;
+            self.update_rows();
+        }
+
+        Ok(())
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let input = match event {
+            Event::Key(e) => match e.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => PlayerInput::Quit,
+                KeyCode::Char('a') | KeyCode::Left => PlayerInput::Left,
+                KeyCode::Char('d') | KeyCode::Right => PlayerInput::Right,
+                _ => PlayerInput::Noop,
+            },
+            _ => PlayerInput::Noop,
+        };
+
+        match input {
+            PlayerInput::Quit => return Ok(GameLoopSignal::load_scene::<TitleScene>()),
+            PlayerInput::Left => self.select_table(-1),
+            PlayerInput::Right => self.select_table(1),
+            _ => (),
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+}
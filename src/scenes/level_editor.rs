@@ -0,0 +1,566 @@
+use anyhow::Result;
+use crossterm::{
+    event::{Event, KeyCode, MouseButton, MouseEventKind},
+    style::Color,
+};
+use std::{
+    any::TypeId,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    GameConfig, GameMode,
+    config::SharedConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::{DrawInstruction, Style},
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{text::Text, world::World},
+    level::Level,
+};
+
+use super::{snake::SnakeScene, title::TitleScene};
+
+const HELP_TEXT: [&str; 2] = [
+    "arrows move  space wall  x erase  p spawn  d direction  +/- target",
+    "mouse: left wall, right erase   c clear  s save  l load  t test  esc back",
+];
+
+/// The size of a new level, which fits in an 80x24 terminal with its border,
+/// the status line and the help text.
+const NEW_LEVEL_SIZE: (usize, usize) = (36, 19);
+const NEW_LEVEL_NAME: &str = "Custom";
+const TARGET_STEP: u32 = 5;
+const UNSAVED_TEXT: &str = "Unsaved changes, press esc again to leave";
+
+/// The world is drawn below the status line and above the help text.
+const WORLD_ORIGIN: Point = Point { x: 0, y: 1 };
+
+const CURSOR_STYLE: Style = Style {
+    fg: Color::Black,
+    bg: Color::Yellow,
+};
+
+#[derive(Debug)]
+enum EditorState {
+    Editing,
+    TestPlaying(Box<SnakeScene>),
+}
+
+/// Edits a level file, placing walls and the spawn point with the keyboard or
+/// mouse. The level can be played without leaving the editor.
+#[derive(Debug)]
+pub struct LevelEditorScene {
+    config: SharedConfig,
+    path: PathBuf,
+    level: Level,
+    world: World,
+    cursor: Point,
+    modified: bool,
+    /// Set after trying to leave with unsaved changes, so leaving again
+    /// discards them.
+    confirming_exit: bool,
+    message: String,
+    state: EditorState,
+    status_text: Text,
+    help_texts: Vec<Text>,
+    spawn_text: String,
+}
+
+impl LevelEditorScene {
+    /// Opens the level at `path`, or starts a new one if there isn't a file
+    /// there yet.
+    pub fn new(config: SharedConfig, path: PathBuf) -> Self {
+        let (level, message) = match Self::load_level(&path) {
+            Ok(level) => (level, format!("Opened {}", path.display())),
+            Err(error) => (Self::new_level(), format!("{error:#}")),
+        };
+
+        let world = Self::create_world(&config.borrow(), &level);
+        let mut scene = Self {
+            config,
+            path,
+            level,
+            world,
+            cursor: Point::default(),
+            modified: false,
+            confirming_exit: false,
+            message,
+            state: EditorState::Editing,
+            status_text: Text::default().at_position((0, 0)).show(),
+            help_texts: vec![],
+            spawn_text: String::new(),
+        };
+
+        scene.update_layout();
+        scene
+    }
+
+    fn new_level() -> Level {
+        let (width, height) = NEW_LEVEL_SIZE;
+        let mut level = Level::empty(width, height);
+        level.name = NEW_LEVEL_NAME.to_string();
+        level
+    }
+
+    /// A missing file starts a new level rather than being an error.
+    fn load_level(path: &Path) -> Result<Level> {
+        if !path.exists() {
+            anyhow::bail!("New level, save to create {}", path.display());
+        }
+
+        Level::load(path)
+    }
+
+    fn create_world(config: &GameConfig, level: &Level) -> World {
+        let config = GameConfig {
+            rows: config.rows.saturating_sub(HELP_TEXT.len()),
+            level: Some(level.clone()),
+            mode: GameMode::Walls,
+            seed: Some(0),
+            ..config.clone()
+        };
+
+        World::new(&config, WORLD_ORIGIN)
+    }
+
+    /// Rebuilds the world and text after the level or screen changes.
+    fn update_layout(&mut self) {
+        let config = self.config.borrow().clone();
+        self.world = Self::create_world(&config, &self.level);
+
+        self.help_texts = HELP_TEXT
+            .iter()
+            .enumerate()
+            .map(|(i, help)| {
+                let row = config.rows.saturating_sub(HELP_TEXT.len()) + i;
+                Text::default()
+                    .with_value(*help)
+                    .center((config.columns / 2, row))
+                    .show()
+            })
+            .collect();
+
+        self.cursor = Point::new(
+            self.cursor.x.min(self.level.width().saturating_sub(1)),
+            self.cursor.y.min(self.level.height().saturating_sub(1)),
+        );
+
+        self.spawn_text = format!("S{}", self.level.direction.arrow());
+        self.update_status();
+    }
+
+    fn update_status(&mut self) {
+        let target = match self.level.target {
+            Some(target) => target.to_string(),
+            None => "none".to_string(),
+        };
+
+        self.status_text.update_value(format!(
+            " {}{}  target {}  {}x{}  {}",
+            self.level.name,
+            if self.modified { "*" } else { "" },
+            target,
+            self.cursor.x + 1,
+            self.cursor.y + 1,
+            self.message,
+        ));
+    }
+
+    fn edit<F: FnOnce(&mut Level)>(&mut self, edit: F) {
+        edit(&mut self.level);
+        self.modified = true;
+        self.confirming_exit = false;
+        self.message.clear();
+        self.update_layout();
+    }
+
+    fn move_cursor(&mut self, x: isize, y: isize) {
+        let x = self.cursor.x.saturating_add_signed(x);
+        let y = self.cursor.y.saturating_add_signed(y);
+        self.cursor = Point::new(
+            x.min(self.level.width().saturating_sub(1)),
+            y.min(self.level.height().saturating_sub(1)),
+        );
+
+        self.update_status();
+    }
+
+    fn change_target(&mut self, increase: bool) {
+        self.edit(|level| {
+            let target = level.target.unwrap_or_default();
+            level.target = match increase {
+                true => Some(target + TARGET_STEP),
+                false => target.checked_sub(TARGET_STEP).filter(|&target| target > 0),
+            };
+        });
+    }
+
+    fn save(&mut self) {
+        self.message = match self.level.save(&self.path) {
+            Ok(()) => {
+                self.modified = false;
+                format!("Saved {}", self.path.display())
+            }
+            Err(error) => format!("{error:#}"),
+        };
+
+        self.update_status();
+    }
+
+    fn reload(&mut self) {
+        match Level::load(&self.path) {
+            Ok(level) => {
+                self.level = level;
+                self.modified = false;
+                self.message = format!("Opened {}", self.path.display());
+            }
+            Err(error) => self.message = format!("{error:#}"),
+        }
+
+        self.update_layout();
+    }
+
+    fn test_play(&mut self) {
+        let config = self.config.borrow().clone();
+        let mut snake = config.snake.clone();
+        snake.speed = self.level.speed.unwrap_or(snake.speed);
+
+        let scene = SnakeScene::new(GameConfig {
+            snake,
+            level: Some(self.level.clone()),
+            ..config
+        });
+
+        self.state = EditorState::TestPlaying(Box::new(scene));
+    }
+
+    /// Leaving the test game any way but playing again comes back to the
+    /// editor.
+    fn forward_signal(&mut self, signal: GameLoopSignal) -> GameLoopSignal {
+        match signal {
+            GameLoopSignal::Run => GameLoopSignal::Run,
+            GameLoopSignal::Load(id) if id == TypeId::of::<SnakeScene>() => {
+                self.test_play();
+                GameLoopSignal::Run
+            }
+            GameLoopSignal::Stop | GameLoopSignal::Load(_) => {
+                self.state = EditorState::Editing;
+                GameLoopSignal::Run
+            }
+        }
+    }
+
+    fn process_editor_input(&mut self, event: &Event) -> GameLoopSignal {
+        if let Event::Mouse(e) = event {
+            let tile = self
+                .world
+                .to_tile(Point::new(e.column as usize, e.row as usize));
+            match (e.kind, tile) {
+                (
+                    MouseEventKind::Down(MouseButton::Left)
+                    | MouseEventKind::Drag(MouseButton::Left),
+                    Some(tile),
+                ) => self.edit(|level| level.set_wall(tile, true)),
+                (
+                    MouseEventKind::Down(MouseButton::Right)
+                    | MouseEventKind::Drag(MouseButton::Right),
+                    Some(tile),
+                ) => self.edit(|level| level.set_wall(tile, false)),
+                (MouseEventKind::Down(MouseButton::Middle), Some(tile)) => {
+                    self.edit(|level| level.set_spawn(tile))
+                }
+                _ => (),
+            }
+
+            return GameLoopSignal::Run;
+        }
+
+        let Event::Key(e) = event else {
+            return GameLoopSignal::Run;
+        };
+
+        let leaving = matches!(e.code, KeyCode::Esc | KeyCode::Char('q'));
+        if leaving && (!self.modified || self.confirming_exit) {
+            return GameLoopSignal::load_scene::<TitleScene>();
+        }
+
+        self.confirming_exit = leaving;
+        let cursor = self.cursor;
+        match e.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.message = UNSAVED_TEXT.to_string();
+                self.update_status();
+            }
+            KeyCode::Up => self.move_cursor(0, -1),
+            KeyCode::Down => self.move_cursor(0, 1),
+            KeyCode::Left => self.move_cursor(-1, 0),
+            KeyCode::Right => self.move_cursor(1, 0),
+            KeyCode::Char(' ') => {
+                let wall = !self.level.is_wall(cursor);
+                self.edit(|level| level.set_wall(cursor, wall));
+            }
+            KeyCode::Char('x') | KeyCode::Delete | KeyCode::Backspace => {
+                self.edit(|level| level.set_wall(cursor, false));
+            }
+            KeyCode::Char('p') => self.edit(|level| level.set_spawn(cursor)),
+            KeyCode::Char('d') => self.edit(|level| level.direction = level.direction.rotate()),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.change_target(true),
+            KeyCode::Char('-') => self.change_target(false),
+            KeyCode::Char('c') => self.edit(Level::clear),
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('l') => self.reload(),
+            KeyCode::Char('t') => self.test_play(),
+            _ => (),
+        }
+
+        GameLoopSignal::Run
+    }
+}
+
+impl GameScene for LevelEditorScene {
+    fn draw(&mut self, timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        if let EditorState::TestPlaying(scene) = &mut self.state {
+            return scene.draw(timestep);
+        }
+
+        let spawn = self.level.spawn().map(|tile| DrawInstruction::Text {
+            position: self.world.to_position(tile),
+            content: &self.spawn_text,
+            style: Style {
+                fg: Color::Green,
+                ..Default::default()
+            },
+        });
+
+        let cursor = DrawInstruction::Text {
+            position: self.world.to_position(self.cursor),
+            content: match self.level.spawn() {
+                Some(spawn) if spawn == self.cursor => &self.spawn_text,
+                _ => "  ",
+            },
+            style: CURSOR_STYLE,
+        };
+
+        self.world
+            .draw()
+            .into_iter()
+            .chain(spawn)
+            .chain([cursor])
+            .chain(self.status_text.draw())
+            .chain(self.help_texts.iter().flat_map(|text| text.draw()))
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        let EditorState::TestPlaying(scene) = &mut self.state else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        let signal = scene.update(elapsed)?;
+        Ok(self.forward_signal(signal))
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        if let EditorState::TestPlaying(scene) = &mut self.state {
+            let signal = scene.process_input(event)?;
+            return Ok(self.forward_signal(signal));
+        }
+
+        Ok(self.process_editor_input(event))
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.borrow().frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        if let EditorState::TestPlaying(scene) = &self.state {
+            return scene.min_size();
+        }
+
+        let help_width = HELP_TEXT.iter().map(|help| help.chars().count()).max();
+        let (columns, rows) = self.world.min_size();
+        (
+            columns.max(help_width.unwrap_or_default()),
+            rows + HELP_TEXT.len(),
+        )
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        if let EditorState::TestPlaying(scene) = &mut self.state {
+            scene.resize(columns, rows)?;
+        }
+
+        self.update_layout();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyEvent, KeyModifiers, MouseEvent};
+    use std::{cell::RefCell, fs, rc::Rc};
+
+    use super::*;
+    use crate::{
+        config::SnakeConfig,
+        engine::{
+            backend::MemoryBackend,
+            game_loop::{GameLoop, TOO_SMALL_TEXT},
+            input::ScriptedInput,
+        },
+        test_support::{headless_loop, test_config},
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: 2,
+                ..test_config().snake
+            },
+            rows: 32,
+            show_border: false,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{name}.txt", std::process::id()))
+    }
+
+    fn create_game_loop(input: &ScriptedInput, path: PathBuf) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        let config = Rc::new(RefCell::new(config()));
+        game_loop
+            .register_scene(LevelEditorScene::new(config, path))
+            .load_scene::<LevelEditorScene>();
+
+        game_loop
+    }
+
+    fn click(input: &ScriptedInput, button: MouseButton, column: u16, row: u16) {
+        input.push(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(button),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }));
+    }
+
+    #[test]
+    fn it_edits_and_saves_a_level() {
+        let path = temp_path("editor-save");
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, path.clone());
+
+        for code in [
+            KeyCode::Char(' '),
+            KeyCode::Right,
+            KeyCode::Char('p'),
+            KeyCode::Char('d'),
+            KeyCode::Char('+'),
+            KeyCode::Char('s'),
+        ] {
+            input.push_key(code);
+            game_loop.step(1).unwrap();
+        }
+
+        let level = Level::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(level.name, NEW_LEVEL_NAME);
+        assert!(level.is_wall(Point::new(0, 0)));
+        assert_eq!(level.spawn(), Some(Point::new(1, 0)));
+        assert_eq!(level.direction, crate::level::Direction::Down);
+        assert_eq!(level.target, Some(TARGET_STEP));
+        assert!(game_loop.renderer().backend().contains("Saved"));
+    }
+
+    #[test]
+    fn it_fits_a_new_level_in_an_80x24_terminal() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&test_config(), &input);
+
+        let shared = Rc::new(RefCell::new(test_config()));
+        game_loop
+            .register_scene(LevelEditorScene::new(shared, temp_path("editor-fits")))
+            .load_scene::<LevelEditorScene>();
+        game_loop.step(1).unwrap();
+
+        let backend = game_loop.renderer().backend();
+        assert!(!backend.contains(TOO_SMALL_TEXT));
+        assert!(backend.contains(NEW_LEVEL_NAME));
+        assert!(backend.contains(HELP_TEXT[1]));
+    }
+
+    #[test]
+    fn it_places_and_erases_walls_with_the_mouse() {
+        let path = temp_path("editor-mouse");
+        fs::write(&path, "name: Mouse\n---\n....\n....").unwrap();
+
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, path.clone());
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains("Mouse "));
+
+        // The 4x2 level is centered in the 80x30 area above the help text
+        let (column, row) = (38, 15);
+        click(&input, MouseButton::Left, column, row);
+        game_loop.step(1).unwrap();
+        click(&input, MouseButton::Left, column + 2, row);
+        game_loop.step(1).unwrap();
+        click(&input, MouseButton::Right, column, row);
+        game_loop.step(1).unwrap();
+        input.push_key(KeyCode::Char('s'));
+        game_loop.step(1).unwrap();
+
+        let level = Level::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            level.walls().copied().collect::<Vec<_>>(),
+            [Point::new(2, 1)]
+        );
+    }
+
+    #[test]
+    fn it_asks_before_leaving_with_unsaved_changes() {
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = LevelEditorScene::new(config, temp_path("editor-unsaved"));
+        let mut press = |code| {
+            scene
+                .process_input(&Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+                .unwrap()
+        };
+
+        press(KeyCode::Char(' '));
+        assert!(matches!(press(KeyCode::Esc), GameLoopSignal::Run));
+
+        // Anything else pressed in between has to be confirmed again
+        press(KeyCode::Right);
+        assert!(matches!(press(KeyCode::Char('q')), GameLoopSignal::Run));
+        assert!(matches!(press(KeyCode::Esc), GameLoopSignal::Load(_)));
+    }
+
+    #[test]
+    fn it_returns_to_the_editor_after_a_test_game() {
+        let path = temp_path("editor-test-play");
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, path);
+
+        input.push_key(KeyCode::Char('t'));
+        game_loop.step(1).unwrap();
+        assert!(!game_loop.renderer().backend().contains(HELP_TEXT[0]));
+
+        input.push_key(KeyCode::Char('q'));
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains(HELP_TEXT[0]));
+    }
+}
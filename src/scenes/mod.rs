@@ -1,2 +1,10 @@
+pub mod campaign;
+pub mod client;
+pub mod high_scores;
+pub mod level_editor;
+pub mod server;
+pub mod settings;
 pub mod snake;
+pub mod spectator;
 pub mod title;
+pub mod versus;
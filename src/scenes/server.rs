@@ -0,0 +1,700 @@
+use anyhow::{Result, anyhow, bail};
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{
+    net::{Shutdown, TcpStream},
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use crate::{
+    GameConfig, GameMode, PlayerInput,
+    config::SnakeConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{arena::Arena, board::Board, text::Text},
+    level::Level,
+    net::{self, ClientMessage, Peer, Phase, PlayerSnapshot, ServerMessage, Snapshot, negotiate},
+    spectators::Spectators,
+};
+
+use super::versus::PLAYER_COLORS;
+
+/// The level played when the server isn't given one, which fits in an 80x24
+/// terminal.
+const DEFAULT_LEVEL_SIZE: (usize, usize) = (38, 22);
+
+/// How long the winner of a round is shown before the next one starts.
+const ROUND_BREAK: Duration = Duration::from_secs(3);
+
+const MAX_NAME_LENGTH: usize = 8;
+
+const SERVER_CLOSED: &str = "Server closed";
+
+/// Someone connected to the server. They have a slot once their hello has
+/// been accepted.
+#[derive(Debug)]
+struct Client {
+    id: usize,
+    peer: Peer<ClientMessage>,
+    slot: Option<usize>,
+    name: String,
+    score: u32,
+    rounds: u32,
+    /// The protocol versions the client asked to watch the game with. It's
+    /// handed over to the spectators at the end of the update.
+    watch: Option<(u32, u32)>,
+    /// Why the client is being disconnected at the end of the update.
+    leaving: Option<String>,
+}
+
+/// Hosts a game that players join over TCP. The server runs the game with
+/// the same rules as a versus match and sends every client a snapshot after
+/// each update. A round starts once two players have joined, up to four can
+/// play, and the last snake left wins the round.
+///
+/// Clients can also ask to watch instead of play, and are handed over to the
+/// server's `Spectators`.
+///
+/// Clients that close their connection, send something the server can't
+/// read or go `net::TIMEOUT` without sending anything are disconnected, and
+/// their snake is taken out of the round. Once `net::MAX_PENDING`
+/// connections are waiting to say hello, any more are closed straight away.
+#[derive(Debug)]
+pub struct ServerScene {
+    config: GameConfig,
+    connections: Receiver<TcpStream>,
+    clients: Vec<Client>,
+    next_id: usize,
+    arena: Option<Arena>,
+    /// The id of the player steering each of the arena's snakes.
+    seats: Vec<usize>,
+    phase: Phase,
+    round: u32,
+    winner: Option<usize>,
+    round_break: Duration,
+    tick: u64,
+    port: u16,
+    board: Board,
+    spectators: Spectators,
+    status_text: Text,
+}
+
+impl ServerScene {
+    /// Starts listening for players on `port`, or on any free port if it's 0.
+    pub fn bind(config: GameConfig, port: u16) -> Result<Self> {
+        let (connections, port) = net::listen(port)?;
+
+        let level = config.level.clone().unwrap_or_else(|| {
+            let (width, height) = DEFAULT_LEVEL_SIZE;
+            Level::empty(width, height)
+        });
+
+        let config = GameConfig {
+            level: Some(level.clone()),
+            ..config
+        };
+
+        let board = Board::new(&config, level, config.mode == GameMode::Wrap);
+        let (_, bottom) = board.world().min_size();
+
+        Ok(Self {
+            spectators: Spectators::new(board.world()),
+            status_text: Text::default()
+                .with_value(format!(" SERVING ON PORT {port} "))
+                .center((config.columns / 2, bottom.saturating_sub(1)))
+                .with_fg(Color::Yellow)
+                .show(),
+            config,
+            connections,
+            clients: vec![],
+            next_id: 1,
+            arena: None,
+            seats: vec![],
+            phase: Phase::Waiting,
+            round: 0,
+            winner: None,
+            round_break: Duration::ZERO,
+            tick: 0,
+            port,
+            board,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn players(&self) -> impl Iterator<Item = &Client> {
+        self.clients.iter().filter(|client| client.slot.is_some())
+    }
+
+    /// Clients that haven't said hello yet, not counting the ones about to
+    /// be handed over to the spectators.
+    fn pending(&self) -> usize {
+        self.clients
+            .iter()
+            .filter(|client| client.slot.is_none() && client.watch.is_none())
+            .count()
+    }
+
+    fn accept_connections(&mut self) {
+        while let Ok(stream) = self.connections.try_recv() {
+            if self.pending() >= net::MAX_PENDING {
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+
+            let Ok(peer) = Peer::new(stream) else {
+                continue;
+            };
+
+            self.clients.push(Client {
+                id: self.next_id,
+                peer,
+                slot: None,
+                name: String::new(),
+                score: 0,
+                rounds: 0,
+                watch: None,
+                leaving: None,
+            });
+
+            self.next_id += 1;
+        }
+    }
+
+    fn read_messages(&mut self, elapsed: &Duration) {
+        for index in 0..self.clients.len() {
+            let messages = match self.clients[index].peer.receive(elapsed) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    self.clients[index].leaving = Some(error.to_string());
+                    continue;
+                }
+            };
+
+            for message in messages {
+                if let Err(error) = self.handle_message(index, message) {
+                    self.clients[index].leaving = Some(error.to_string());
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, index: usize, message: ClientMessage) -> Result<()> {
+        let joined = self.clients[index].slot.is_some();
+
+        match message {
+            // Spectators are greeted once they've been handed over
+            _ if self.clients[index].watch.is_some() => Ok(()),
+            ClientMessage::Hello {
+                min_version,
+                max_version,
+                name,
+            } if !joined => self.welcome(index, min_version, max_version, name),
+            ClientMessage::Watch {
+                min_version,
+                max_version,
+            } if !joined => {
+                self.clients[index].watch = Some((min_version, max_version));
+                Ok(())
+            }
+            ClientMessage::Hello { .. } | ClientMessage::Watch { .. } => {
+                bail!("Said hello twice")
+            }
+            _ if !joined => bail!("Didn't say hello first"),
+            ClientMessage::Input { input } => {
+                self.steer(self.clients[index].id, input);
+                Ok(())
+            }
+            ClientMessage::Ping => Ok(()),
+            ClientMessage::Bye => bail!("Left the game"),
+        }
+    }
+
+    /// Gives the client the first free slot, as long as it speaks a protocol
+    /// version the server does.
+    fn welcome(
+        &mut self,
+        index: usize,
+        min_version: u32,
+        max_version: u32,
+        name: Option<String>,
+    ) -> Result<()> {
+        let slot = (0..PLAYER_COLORS.len())
+            .find(|&slot| self.players().all(|player| player.slot != Some(slot)));
+
+        let accepted = negotiate(min_version, max_version)
+            .and_then(|version| Ok((version, slot.ok_or_else(|| anyhow!("Server is full"))?)));
+
+        let client = &mut self.clients[index];
+        let (version, slot) = match accepted {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                client.peer.send(&ServerMessage::Rejected {
+                    reason: error.to_string(),
+                })?;
+                return Err(error);
+            }
+        };
+
+        client.slot = Some(slot);
+        client.name = player_name(name, slot);
+        client.peer.send(&ServerMessage::Welcome {
+            version,
+            id: client.id,
+            level: self.board.world().level().clone(),
+            wrap: self.config.mode == GameMode::Wrap,
+        })
+    }
+
+    /// Turns the player's snake, if it's still in the round.
+    fn steer(&mut self, id: usize, input: PlayerInput) {
+        let movement = matches!(
+            input,
+            PlayerInput::Up | PlayerInput::Down | PlayerInput::Left | PlayerInput::Right
+        );
+
+        let Some(arena) = &mut self.arena else {
+            return;
+        };
+
+        if let Some(seat) = self.seats.iter().position(|&seat| seat == id)
+            && movement
+            && self.phase == Phase::Playing
+            && arena.is_alive(seat)
+        {
+            arena.process_input(&(seat, input));
+        }
+    }
+
+    /// Hands the clients that asked to watch over to the spectators.
+    fn hand_over_spectators(&mut self) {
+        let (watching, playing) = std::mem::take(&mut self.clients)
+            .into_iter()
+            .partition(|client| client.watch.is_some() && client.leaving.is_none());
+        self.clients = playing;
+
+        for client in watching {
+            let (min_version, max_version) = client.watch.unwrap_or_default();
+            self.spectators.add(client.peer, min_version, max_version);
+        }
+    }
+
+    /// Says goodbye to the clients that are leaving and takes their snakes
+    /// out of the round.
+    fn disconnect_leaving(&mut self) {
+        let (leaving, staying) = std::mem::take(&mut self.clients)
+            .into_iter()
+            .partition(|client| client.leaving.is_some());
+        self.clients = staying;
+
+        for client in leaving {
+            let seat = self.seats.iter().position(|&id| id == client.id);
+            if let (Some(arena), Some(seat)) = (&mut self.arena, seat) {
+                arena.take_out(seat);
+            }
+
+            if client.slot.is_some() {
+                let reason = client.leaving.unwrap_or_default();
+                let _ = client.peer.send(&ServerMessage::Bye { reason });
+            }
+        }
+    }
+
+    fn start_round(&mut self) {
+        let mut players: Vec<_> = self
+            .players()
+            .filter_map(|player| Some((player.slot?, player.id)))
+            .collect();
+        players.sort();
+
+        let snakes = players
+            .iter()
+            .map(|&(slot, _)| SnakeConfig {
+                style: PLAYER_COLORS[slot].0,
+                ..self.config.snake.clone()
+            })
+            .collect();
+
+        self.seats = players.into_iter().map(|(_, id)| id).collect();
+        self.arena = Some(Arena::new(&self.config, snakes));
+        self.round += 1;
+        self.winner = None;
+        self.phase = Phase::Playing;
+    }
+
+    fn update_game(&mut self, elapsed: &Duration) {
+        let enough_players = self.players().count() >= 2;
+
+        match self.phase {
+            Phase::Waiting if enough_players => self.start_round(),
+            Phase::Waiting => {}
+            Phase::Playing => self.update_round(elapsed),
+            Phase::RoundOver => {
+                self.round_break = self.round_break.saturating_sub(*elapsed);
+                if !self.round_break.is_zero() {
+                    return;
+                }
+
+                match enough_players {
+                    true => self.start_round(),
+                    false => {
+                        self.arena = None;
+                        self.seats.clear();
+                        self.phase = Phase::Waiting;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Plays one update of the round by the same rules as a versus match.
+    fn update_round(&mut self, elapsed: &Duration) {
+        let Some(arena) = &mut self.arena else {
+            return;
+        };
+
+        arena.move_snakes(elapsed);
+
+        if arena.living().count() <= 1 {
+            self.winner = arena.living().next().map(|(seat, _)| self.seats[seat]);
+            if let Some(winner) = self.clients.iter_mut().find(|c| Some(c.id) == self.winner) {
+                winner.rounds += 1;
+            }
+
+            self.phase = Phase::RoundOver;
+            self.round_break = ROUND_BREAK;
+            return;
+        }
+
+        let eaten = arena.eat().map(|seat| self.seats[seat]);
+        if let Some(player) = self.clients.iter_mut().find(|c| Some(c.id) == eaten) {
+            player.score += 1;
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut players: Vec<_> = self
+            .players()
+            .map(|player| PlayerSnapshot {
+                id: player.id,
+                name: player.name.clone(),
+                slot: player.slot.unwrap_or_default(),
+                score: player.score,
+                rounds: player.rounds,
+                snake: self.arena.as_ref().and_then(|arena| {
+                    let seat = self.seats.iter().position(|&id| id == player.id)?;
+                    arena.bot_snake(seat)
+                }),
+            })
+            .collect();
+        players.sort_by_key(|player| player.slot);
+
+        let food = self
+            .arena
+            .as_ref()
+            .filter(|_| self.phase == Phase::Playing)
+            .and_then(Arena::food_tile);
+
+        Snapshot {
+            tick: self.tick,
+            round: self.round,
+            phase: self.phase,
+            winner: self.winner,
+            players,
+            food,
+            food_kind: self
+                .arena
+                .as_ref()
+                .map(Arena::food_kind)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Sends every player the latest snapshot. Players too far behind to
+    /// take it are disconnected on the next update.
+    fn broadcast(&mut self, snapshot: &Snapshot) {
+        let message = ServerMessage::Snapshot(snapshot.clone());
+        for client in self.clients.iter_mut().filter(|c| c.slot.is_some()) {
+            if let Err(error) = client.peer.send(&message) {
+                client.leaving = Some(error.to_string());
+            }
+        }
+    }
+}
+
+/// Uppercase letters and digits from the name the player asked for, or
+/// their slot's name if that leaves nothing.
+fn player_name(name: Option<String>, slot: usize) -> String {
+    let name: String = name
+        .unwrap_or_default()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>()
+        .to_ascii_uppercase();
+
+    match name.is_empty() {
+        true => format!("P{}", slot + 1),
+        false => name,
+    }
+}
+
+impl GameScene for ServerScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        self.board
+            .draw()
+            .into_iter()
+            .chain(self.status_text.draw())
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        self.accept_connections();
+        self.read_messages(elapsed);
+        self.hand_over_spectators();
+        self.disconnect_leaving();
+        self.spectators.update(elapsed);
+        self.update_game(elapsed);
+        self.tick += 1;
+
+        let snapshot = self.snapshot();
+        self.broadcast(&snapshot);
+        self.spectators.broadcast(self.board.world(), &snapshot);
+        self.board.update(snapshot);
+
+        let port = self.port;
+        self.status_text
+            .update_value(match self.spectators.count() {
+                0 => format!(" SERVING ON PORT {port} "),
+                watching => format!(" SERVING ON PORT {port}  {watching} WATCHING "),
+            });
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let Event::Key(e) = event else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        if e.code != KeyCode::Char('q') {
+            return Ok(GameLoopSignal::Run);
+        }
+
+        for client in &mut self.clients {
+            client.leaving = Some(SERVER_CLOSED.to_string());
+        }
+
+        self.disconnect_leaving();
+        self.spectators.close(SERVER_CLOSED);
+        Ok(GameLoopSignal::Stop)
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        self.board.world().min_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{backend::MemoryBackend, game_loop::GameLoop, input::ScriptedInput},
+        net::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, TIMEOUT},
+        test_support::{headless_loop, test_config},
+    };
+    use std::thread;
+
+    fn create_game_loop(input: &ScriptedInput) -> (GameLoop<MemoryBackend>, String) {
+        let mut game_loop = headless_loop(&test_config(), input);
+
+        let scene = ServerScene::bind(test_config(), 0).unwrap();
+        let address = format!("127.0.0.1:{}", scene.port());
+        game_loop.register_scene(scene).load_scene::<ServerScene>();
+
+        (game_loop, address)
+    }
+
+    fn join(address: &str, min_version: u32, max_version: u32) -> Peer<ServerMessage> {
+        let peer = Peer::connect(address).unwrap();
+        peer.send(&ClientMessage::Hello {
+            min_version,
+            max_version,
+            name: None,
+        })
+        .unwrap();
+
+        peer
+    }
+
+    /// Steps the server until `peer` is sent a message matching `matches`,
+    /// giving the network a moment between steps.
+    fn step_until(
+        game_loop: &mut GameLoop<MemoryBackend>,
+        peer: &mut Peer<ServerMessage>,
+        matches: impl Fn(&ServerMessage) -> bool,
+    ) -> ServerMessage {
+        for _ in 0..500 {
+            game_loop.step(1).unwrap();
+            thread::sleep(Duration::from_millis(2));
+
+            let messages = peer.receive(&Duration::ZERO).unwrap_or_default();
+            if let Some(message) = messages.into_iter().find(&matches) {
+                return message;
+            }
+        }
+
+        panic!("The server never sent the expected message");
+    }
+
+    #[test]
+    fn it_starts_a_round_once_two_players_join() {
+        let input = ScriptedInput::new();
+        let (mut game_loop, address) = create_game_loop(&input);
+
+        let mut first = join(&address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let welcome = step_until(&mut game_loop, &mut first, |message| {
+            matches!(message, ServerMessage::Welcome { .. })
+        });
+        assert!(matches!(welcome, ServerMessage::Welcome { version: 1, .. }));
+
+        game_loop.step(1).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("WAITING FOR PLAYERS")
+        );
+
+        let mut second = join(&address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let ServerMessage::Snapshot(snapshot) = step_until(
+            &mut game_loop,
+            &mut second,
+            |m| matches!(m, ServerMessage::Snapshot(s) if s.phase == Phase::Playing),
+        ) else {
+            unreachable!()
+        };
+
+        assert_eq!(snapshot.round, 1);
+        assert_eq!(snapshot.players.len(), 2);
+        assert!(snapshot.players.iter().all(|player| player.snake.is_some()));
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("ROUND 1  P1 0  P2 0")
+        );
+    }
+
+    #[test]
+    fn it_rejects_clients_that_speak_another_version() {
+        let input = ScriptedInput::new();
+        let (mut game_loop, address) = create_game_loop(&input);
+
+        let mut peer = join(&address, PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 1);
+        let rejected = step_until(&mut game_loop, &mut peer, |message| {
+            matches!(message, ServerMessage::Rejected { .. })
+        });
+
+        let ServerMessage::Rejected { reason } = rejected else {
+            unreachable!()
+        };
+        assert!(reason.contains("protocol versions"), "{reason}");
+    }
+
+    #[test]
+    fn it_closes_connections_past_the_pending_limit() {
+        let input = ScriptedInput::new();
+        let (mut game_loop, address) = create_game_loop(&input);
+
+        let mut pending: Vec<_> = (0..net::MAX_PENDING)
+            .map(|_| Peer::<ServerMessage>::connect(&address).unwrap())
+            .collect();
+        let mut extra = Peer::<ServerMessage>::connect(&address).unwrap();
+
+        let closed = (0..500).any(|_| {
+            game_loop.step(1).unwrap();
+            thread::sleep(Duration::from_millis(2));
+            extra.receive(&Duration::ZERO).is_err()
+        });
+
+        assert!(closed);
+        assert!(pending[0].receive(&Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn it_disconnects_players_that_go_quiet() {
+        let input = ScriptedInput::new();
+        let (mut game_loop, address) = create_game_loop(&input);
+
+        // This client never pings, so the server gives up on it
+        let mut peer = join(&address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let bye = step_until(&mut game_loop, &mut peer, |message| {
+            matches!(message, ServerMessage::Bye { .. })
+        });
+
+        assert_eq!(
+            bye,
+            ServerMessage::Bye {
+                reason: format!("Timed out after {}s without a message", TIMEOUT.as_secs())
+            }
+        );
+    }
+
+    #[test]
+    fn it_sends_spectators_the_game_without_giving_them_a_seat() {
+        let input = ScriptedInput::new();
+        let (mut game_loop, address) = create_game_loop(&input);
+
+        let mut spectator = Peer::connect(&address).unwrap();
+        spectator
+            .send(&ClientMessage::Watch {
+                min_version: MIN_PROTOCOL_VERSION,
+                max_version: PROTOCOL_VERSION,
+            })
+            .unwrap();
+
+        step_until(&mut game_loop, &mut spectator, |message| {
+            matches!(message, ServerMessage::Welcome { .. })
+        });
+        assert!(game_loop.renderer().backend().contains("1 WATCHING"));
+
+        let _first = join(&address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let _second = join(&address, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION);
+        let ServerMessage::Snapshot(snapshot) = step_until(
+            &mut game_loop,
+            &mut spectator,
+            |m| matches!(m, ServerMessage::Snapshot(s) if s.phase == Phase::Playing),
+        ) else {
+            unreachable!()
+        };
+
+        assert_eq!(snapshot.players.len(), 2);
+    }
+
+    #[test]
+    fn it_cleans_up_player_names() {
+        assert_eq!(player_name(Some("kyle s!".to_string()), 0), "KYLES");
+        assert_eq!(
+            player_name(Some("a very long name".to_string()), 0),
+            "AVERYLON"
+        );
+        assert_eq!(player_name(Some("???".to_string()), 2), "P3");
+        assert_eq!(player_name(None, 1), "P2");
+    }
+}
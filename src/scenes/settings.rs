@@ -0,0 +1,570 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{fmt::Display, path::PathBuf, time::Duration};
+
+use crate::{
+    GameMode, PlayerInput, SnakeStyle,
+    config::{ConfigFile, GameConfig, SharedConfig},
+    curve::{STEADY, SpeedCurve},
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{menu::Menu, text::Text},
+};
+
+use super::title::TitleScene;
+
+const TITLE: &str = "SETTINGS";
+const HELP_TEXT: &str = "↑/↓ select   ←/→ change   esc back";
+
+/// Room for the help text and the title and help rows 6 above and below the
+/// center, with the menu in between.
+const MIN_SIZE: (usize, usize) = (36, 14);
+
+const SPEED_RANGE: (f32, f32) = (1.0, 60.0);
+const GROW_RATE_RANGE: (usize, usize) = (0, 10);
+const FRAME_RATE_RANGE: (u8, u8) = (5, 60);
+const FRAME_RATE_STEP: u8 = 5;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SettingsOption {
+    Speed,
+    GrowRate,
+    SnakeStyle,
+    FrameRate,
+    ShowBorder,
+    ShowFrameRate,
+    Mode,
+    PowerUps,
+    Curve,
+    Back,
+}
+
+impl SettingsOption {
+    const ALL: [Self; 10] = [
+        Self::Speed,
+        Self::GrowRate,
+        Self::SnakeStyle,
+        Self::FrameRate,
+        Self::ShowBorder,
+        Self::ShowFrameRate,
+        Self::Mode,
+        Self::PowerUps,
+        Self::Curve,
+        Self::Back,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Speed => "SPEED",
+            Self::GrowRate => "GROW RATE",
+            Self::SnakeStyle => "SNAKE STYLE",
+            Self::FrameRate => "FRAME RATE",
+            Self::ShowBorder => "BORDER",
+            Self::ShowFrameRate => "SHOW FPS",
+            Self::Mode => "MODE",
+            Self::PowerUps => "POWER-UPS",
+            Self::Curve => "SPEED CURVE",
+            Self::Back => "BACK",
+        }
+    }
+
+    fn value(&self, config: &GameConfig) -> String {
+        match self {
+            Self::Speed => format!("{}", config.snake.speed),
+            Self::GrowRate => format!("{}", config.snake.grow_rate),
+            Self::SnakeStyle => format!("{:?}", config.snake.style).to_uppercase(),
+            Self::FrameRate => format!("{}", config.frame_rate),
+            Self::ShowBorder => on_off(config.show_border),
+            Self::ShowFrameRate => on_off(config.show_frame_rate),
+            Self::Mode => format!("{:?}", config.mode).to_uppercase(),
+            Self::PowerUps => on_off(config.food.is_some()),
+            Self::Curve => config
+                .curve
+                .as_ref()
+                .map_or(STEADY, |curve| curve.name.as_str())
+                .to_uppercase(),
+            Self::Back => String::new(),
+        }
+    }
+
+    /// Moves the setting one step in `direction`, which is either -1 or 1.
+    /// Power-ups are placed with the config file's weights, and its curves
+    /// are picked from along with the built-in ones.
+    fn change(&self, config: &mut GameConfig, config_file: &ConfigFile, direction: i8) {
+        match self {
+            Self::Speed => {
+                config.snake.speed =
+                    (config.snake.speed + direction as f32).clamp(SPEED_RANGE.0, SPEED_RANGE.1);
+            }
+            Self::GrowRate => {
+                config.snake.grow_rate = config
+                    .snake
+                    .grow_rate
+                    .saturating_add_signed(direction as isize)
+                    .clamp(GROW_RATE_RANGE.0, GROW_RATE_RANGE.1);
+            }
+            Self::SnakeStyle => {
+                let styles = SnakeStyle::value_variants();
+                let index = styles
+                    .iter()
+                    .position(|style| *style == config.snake.style)
+                    .unwrap_or_default();
+
+                let next = (index as isize + direction as isize).rem_euclid(styles.len() as isize);
+                config.snake.style = styles[next as usize];
+            }
+            Self::FrameRate => {
+                config.frame_rate = config
+                    .frame_rate
+                    .saturating_add_signed(direction * FRAME_RATE_STEP as i8)
+                    .clamp(FRAME_RATE_RANGE.0, FRAME_RATE_RANGE.1);
+            }
+            Self::ShowBorder => config.show_border = !config.show_border,
+            Self::ShowFrameRate => config.show_frame_rate = !config.show_frame_rate,
+            Self::Mode => {
+                config.mode = match config.mode {
+                    GameMode::Walls => GameMode::Wrap,
+                    GameMode::Wrap => GameMode::Walls,
+                };
+            }
+            Self::PowerUps => {
+                config.food = match config.food {
+                    Some(_) => None,
+                    None => Some(config_file.food.clone().unwrap_or_default()),
+                };
+            }
+            Self::Curve => {
+                let curves = config_file.curves.as_ref();
+                let names = SpeedCurve::names(curves);
+                let index = config
+                    .curve
+                    .as_ref()
+                    .and_then(|curve| names.iter().position(|name| *name == curve.name))
+                    .unwrap_or_default();
+
+                let next = (index as isize + direction as isize).rem_euclid(names.len() as isize);
+                config.curve = SpeedCurve::find(names[next as usize], curves)
+                    .ok()
+                    .flatten();
+            }
+            Self::Back => (),
+        }
+    }
+
+    /// Copies the setting from `config` to the config file.
+    fn save(&self, config: &GameConfig, config_file: &mut ConfigFile) {
+        match self {
+            Self::Speed => config_file.speed = Some(config.snake.speed),
+            Self::GrowRate => config_file.grow_rate = Some(config.snake.grow_rate),
+            Self::SnakeStyle => config_file.snake_style = Some(config.snake.style),
+            Self::FrameRate => config_file.frame_rate = Some(config.frame_rate),
+            Self::ShowBorder => config_file.show_border = Some(config.show_border),
+            Self::ShowFrameRate => config_file.show_frame_rate = Some(config.show_frame_rate),
+            Self::Mode => config_file.mode = Some(config.mode),
+            Self::PowerUps => config_file.power_ups = Some(config.food.is_some()),
+            Self::Curve => {
+                let name = config
+                    .curve
+                    .as_ref()
+                    .map_or(STEADY, |curve| curve.name.as_str());
+                config_file.curve = Some(name.to_string());
+            }
+            Self::Back => (),
+        }
+    }
+
+    fn to_row(self, config: &GameConfig) -> SettingsRow {
+        SettingsRow {
+            label: self.label(),
+            value: self.value(config),
+            adjustable: self != Self::Back,
+        }
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "ON" } else { "OFF" }.into()
+}
+
+struct SettingsRow {
+    label: &'static str,
+    value: String,
+    adjustable: bool,
+}
+
+impl Display for SettingsRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.adjustable {
+            return write!(f, "{:^30}", self.label);
+        }
+
+        write!(f, "  {:<14}< {:>8} >  ", self.label, self.value)
+    }
+}
+
+/// Edits the shared `GameConfig`. Changes are picked up by the next game
+/// that's started, and the settings that were changed are saved to the config
+/// file when leaving the scene. Settings only given on the command line or by
+/// a level stay out of the file.
+#[derive(Debug)]
+pub struct SettingsScene {
+    config: SharedConfig,
+    config_path: PathBuf,
+    /// The config file as it was when the scene was opened, for the food
+    /// weights and speed curves the settings can be changed to.
+    config_file: ConfigFile,
+    changed: Vec<SettingsOption>,
+    title_text: Text,
+    error_text: Text,
+    help_text: Text,
+    menu: Menu,
+}
+
+impl SettingsScene {
+    pub fn new(config: SharedConfig, config_path: PathBuf) -> Self {
+        let (center, menu) = {
+            let config = config.borrow();
+            let center = Self::center(&config);
+            let rows = SettingsOption::ALL.map(|option| option.to_row(&config));
+            let menu = Menu::new(rows, center.saturating_sub(&Point::new(0, 4)));
+            (center, menu)
+        };
+
+        let title_text = Text::default()
+            .with_value(TITLE)
+            .center(center.saturating_sub(&Point::new(0, 6)))
+            .with_fg(Color::Yellow)
+            .show();
+
+        let mut error_text = Text::default()
+            .center(Self::error_position(center))
+            .with_fg(Color::Red)
+            .hide();
+
+        // The settings still work without the file, and leaving shows the
+        // error again rather than saving over it
+        let config_file = ConfigFile::load(&config_path).unwrap_or_else(|error| {
+            error_text.update_value(format!("{error:#}"));
+            error_text.visible = true;
+            ConfigFile::default()
+        });
+
+        let help_text = Text::default()
+            .with_value(HELP_TEXT)
+            .center(center + Point::new(0usize, 6))
+            .show();
+
+        Self {
+            config,
+            config_path,
+            config_file,
+            changed: vec![],
+            title_text,
+            error_text,
+            help_text,
+            menu,
+        }
+    }
+
+    fn center(config: &GameConfig) -> Point {
+        Point::new(config.columns / 2, config.rows / 2)
+    }
+
+    /// The error is shown between the title and the menu.
+    fn error_position(center: Point) -> Point {
+        center.saturating_sub(&Point::new(0, 5))
+    }
+
+    fn selected_option(&self) -> SettingsOption {
+        SettingsOption::ALL[self.menu.selected_index()]
+    }
+
+    /// Saves the changed settings over the ones in the config file. If they
+    /// can't be saved the error is shown instead, and leaving again goes
+    /// without saving.
+    fn exit(&mut self) -> GameLoopSignal {
+        if self.changed.is_empty() {
+            return GameLoopSignal::load_scene::<TitleScene>();
+        }
+
+        let saved = ConfigFile::load(&self.config_path).and_then(|mut config_file| {
+            let config = self.config.borrow();
+            for option in &self.changed {
+                option.save(&config, &mut config_file);
+            }
+
+            config_file.save(&self.config_path)
+        });
+
+        self.changed.clear();
+        match saved {
+            Ok(()) => GameLoopSignal::load_scene::<TitleScene>(),
+            Err(error) => {
+                self.error_text.update_value(format!("{error:#}"));
+                self.error_text.visible = true;
+                GameLoopSignal::Run
+            }
+        }
+    }
+
+    fn change_selected(&mut self, direction: i8) {
+        let option = self.selected_option();
+        if !self.changed.contains(&option) {
+            self.changed.push(option);
+        }
+
+        let mut config = self.config.borrow_mut();
+        option.change(&mut config, &self.config_file, direction);
+        self.menu
+            .update_option(self.menu.selected_index(), option.to_row(&config));
+    }
+}
+
+impl GameScene for SettingsScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        vec![
+            self.title_text.draw(),
+            self.error_text.draw(),
+            self.menu.draw(),
+            self.help_text.draw(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn update(&mut self, _elapsed: &Duration) -> Result<GameLoopSignal> {
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        MIN_SIZE
+    }
+
+    fn resize(&mut self, _columns: usize, _rows: usize) -> Result<()> {
+        // The shared config already has the new size
+        let selected_index = self.menu.selected_index();
+        let changed = std::mem::take(&mut self.changed);
+        let error_text = std::mem::take(&mut self.error_text);
+
+        *self = Self::new(self.config.clone(), self.config_path.clone());
+        self.menu.select(selected_index);
+        self.changed = changed;
+        self.error_text =
+            error_text.center(Self::error_position(Self::center(&self.config.borrow())));
+        Ok(())
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let input = match event {
+            Event::Key(e) => match e.code {
+                KeyCode::Enter => PlayerInput::Select,
+                KeyCode::Esc | KeyCode::Char('q') => PlayerInput::Quit,
+                KeyCode::Char('a') | KeyCode::Left => PlayerInput::Left,
+                KeyCode::Char('s') | KeyCode::Down => PlayerInput::Down,
+                KeyCode::Char('d') | KeyCode::Right => PlayerInput::Right,
+                KeyCode::Char('w') | KeyCode::Up => PlayerInput::Up,
+                _ => PlayerInput::Noop,
+            },
+            _ => PlayerInput::Noop,
+        };
+
+        match input {
+            PlayerInput::Quit => return Ok(self.exit()),
+            PlayerInput::Up => self.menu.select_previous(),
+            PlayerInput::Down => self.menu.select_next(),
+            PlayerInput::Left => self.change_selected(-1),
+            PlayerInput::Right => self.change_selected(1),
+            PlayerInput::Select if self.selected_option() == SettingsOption::Back => {
+                return Ok(self.exit());
+            }
+            PlayerInput::Select => self.change_selected(1),
+            _ => (),
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyEvent, KeyModifiers};
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{config::SnakeConfig, test_support::test_config};
+
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: 2,
+                size: 6,
+                ..test_config().snake
+            },
+            rows: 32,
+            show_border: false,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    fn press(scene: &mut SettingsScene, code: KeyCode) -> GameLoopSignal {
+        scene
+            .process_input(&Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+            .unwrap()
+    }
+
+    fn config_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snake-{}-{name}.toml", std::process::id()))
+    }
+
+    #[test]
+    fn it_changes_the_shared_config() {
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), config_path("change"));
+
+        press(&mut scene, KeyCode::Right);
+        press(&mut scene, KeyCode::Down);
+        press(&mut scene, KeyCode::Left);
+        press(&mut scene, KeyCode::Down);
+        press(&mut scene, KeyCode::Right);
+
+        let config = config.borrow();
+        assert_eq!(config.snake.speed, 16.0);
+        assert_eq!(config.snake.grow_rate, 1);
+        assert_eq!(config.snake.style, SnakeStyle::Yellow);
+    }
+
+    #[test]
+    fn it_saves_the_config_when_leaving() {
+        let path = config_path("save");
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), path.clone());
+
+        press(&mut scene, KeyCode::Right);
+        press(&mut scene, KeyCode::Esc);
+
+        let saved = ConfigFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved.speed, Some(16.0));
+    }
+
+    #[test]
+    fn it_only_saves_the_settings_that_changed() {
+        let path = config_path("merge");
+        std::fs::write(&path, "grow-rate = 4\nmode = \"walls\"\n").unwrap();
+
+        // Wrap mode is as if it were given with --mode
+        let config = Rc::new(RefCell::new(GameConfig {
+            mode: GameMode::Wrap,
+            ..config()
+        }));
+        let mut scene = SettingsScene::new(config.clone(), path.clone());
+
+        press(&mut scene, KeyCode::Right);
+        press(&mut scene, KeyCode::Esc);
+
+        let saved = ConfigFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            saved,
+            ConfigFile {
+                speed: Some(16.0),
+                grow_rate: Some(4),
+                mode: Some(GameMode::Walls),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn it_shows_an_error_when_the_config_cant_be_saved() {
+        // The config file's directory can't be created inside of a file
+        let file = config_path("read-only");
+        std::fs::write(&file, "").unwrap();
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), file.join("config.toml"));
+
+        press(&mut scene, KeyCode::Right);
+        assert!(matches!(
+            press(&mut scene, KeyCode::Esc),
+            GameLoopSignal::Run
+        ));
+        assert!(scene.error_text.visible);
+
+        // Leaving again goes without saving, keeping the change for this run
+        assert!(matches!(
+            press(&mut scene, KeyCode::Esc),
+            GameLoopSignal::Load(_)
+        ));
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(config.borrow().snake.speed, 16.0);
+    }
+
+    #[test]
+    fn it_keeps_the_configured_food_weights_when_turning_power_ups_back_on() {
+        let path = config_path("food");
+        std::fs::write(&path, "[food]\nghost = 1\n").unwrap();
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), path.clone());
+        std::fs::remove_file(&path).unwrap();
+
+        for _ in 0..7 {
+            press(&mut scene, KeyCode::Down);
+        }
+        press(&mut scene, KeyCode::Right);
+        press(&mut scene, KeyCode::Right);
+        press(&mut scene, KeyCode::Right);
+
+        let food = config.borrow().food.clone().unwrap();
+        assert_eq!(food, toml::from_str("ghost = 1").unwrap());
+    }
+
+    #[test]
+    fn it_cycles_through_the_configured_curves() {
+        let path = config_path("curves");
+        std::fs::write(
+            &path,
+            "[curves.sprint]\nby = \"time\"\nevery = 10\nstep = 2.0\nmax-level = 3\n",
+        )
+        .unwrap();
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), path.clone());
+        std::fs::remove_file(&path).unwrap();
+
+        for _ in 0..8 {
+            press(&mut scene, KeyCode::Down);
+        }
+        press(&mut scene, KeyCode::Left);
+        assert_eq!(config.borrow().curve.as_ref().unwrap().name, "sprint");
+
+        press(&mut scene, KeyCode::Right);
+        assert!(config.borrow().curve.is_none());
+    }
+
+    #[test]
+    fn it_clamps_values_to_their_range() {
+        let config = Rc::new(RefCell::new(config()));
+        let mut scene = SettingsScene::new(config.clone(), config_path("clamp"));
+
+        press(&mut scene, KeyCode::Down);
+        press(&mut scene, KeyCode::Down);
+        press(&mut scene, KeyCode::Down);
+        for _ in 0..20 {
+            press(&mut scene, KeyCode::Left);
+        }
+
+        assert_eq!(config.borrow().frame_rate, FRAME_RATE_RANGE.0);
+    }
+}
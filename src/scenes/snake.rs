@@ -1,10 +1,16 @@
-use anyhow::Result;
-use crossterm::event::{self, KeyCode};
+use anyhow::{Error, Result};
+use crossterm::{
+    event::{self, KeyCode},
+    style::Color,
+};
 use event::Event;
-use std::time::Duration;
+use snake::game::{Game, Outcome};
+use std::{cell::RefCell, fmt::Display, path::PathBuf, rc::Rc, time::Duration};
 
 use crate::{
     GameConfig, PlayerInput,
+    ai::{Autopilot, Difficulty},
+    bot::{Bot, BotSnake},
     engine::{
         game_loop::GameLoopSignal,
         point::Point,
@@ -12,101 +18,527 @@ use crate::{
         timestep::Timestep,
         traits::{Entity, GameScene},
     },
-    entities::{food::Food, score::Score, snake::Snake, text::Text, world::World},
+    entities::{menu::Menu, score::Score, text::Text},
+    high_scores::{HighScore, HighScores, RuleSet},
+    net::{Phase, PlayerSnapshot, Snapshot},
+    replay::{Replay, ReplayPlayer, ReplayRecorder},
+    spectators::Spectators,
 };
 
+use super::title::TitleScene;
+
 const GAME_OVER: &str = "GAME OVER";
+const LEVEL_CLEAR: &str = "LEVEL CLEAR";
+const BOT_ERROR: &str = "BOT ERROR";
 const FPS_LABEL: &str = "FPS: ";
 const PAUSE_TEXT: &str = "PAUSED";
+const SEED_LABEL: &str = "SEED: ";
+const NAME_PROMPT: &str = "NEW HIGH SCORE! NAME: ";
+const MAX_NAME_LENGTH: usize = 10;
+const DEFAULT_NAME: &str = "PLAYER";
+/// What spectators see the player called.
+const SPECTATOR_NAME: &str = "P1";
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum SnakeSceneState {
     Playing,
     Paused,
+    EnteringName,
     GameOver,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum GameOverOption {
+    PlayAgain,
+    MainMenu,
+}
+
+impl GameOverOption {
+    const ALL: [Self; 2] = [Self::PlayAgain, Self::MainMenu];
+
+    pub fn perform_action(&self) -> GameLoopSignal {
+        match self {
+            Self::PlayAgain => GameLoopSignal::load_scene::<SnakeScene>(),
+            Self::MainMenu => GameLoopSignal::load_scene::<TitleScene>(),
+        }
+    }
+}
+
+impl Display for GameOverOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PlayAgain => write!(f, "   PLAY AGAIN   "),
+            Self::MainMenu => write!(f, "   MAIN MENU    "),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SnakeScene {
-    config: GameConfig,
-    world: World,
-    snake: Snake,
-    food: Food,
+    game: Game,
     score: Score,
+    /// How far up the speed curve the snake is, counting from 1.
+    speed_level: Score,
     state_text: Text,
+    seed_text: Text,
     fps_text: Text,
+    level_text: Text,
+    replay_text: Text,
+    game_over_menu: Menu,
+    name_text: Text,
+    name: String,
     state: SnakeSceneState,
+    tick: u64,
+    recorder: Option<ReplayRecorder>,
+    player: Option<ReplayPlayer>,
+    high_scores_path: Option<PathBuf>,
+    autopilot: Option<Autopilot>,
+    bot: Option<Bot>,
+    spectators: Option<Rc<RefCell<Spectators>>>,
+    spectators_text: Text,
+    error_text: Text,
 }
 
 impl SnakeScene {
     pub fn new(config: GameConfig) -> Self {
-        let world = Self::create_world(&config);
-        let food = Food::new(world.get_random_position());
+        let game = Game::new(&config);
+        let world = game.world();
         let state_text = Text::default()
             .with_value(GAME_OVER)
             .center(world.get_center_position())
             .hide();
 
+        let seed_text = Text::default()
+            .with_value(format!("{SEED_LABEL}{}", world.seed()))
+            .center(world.get_center_position() + Point::new(0usize, 1))
+            .hide();
+
         let fps_text = Text::default()
             .with_value(FPS_LABEL)
-            .at_position((config.columns - (FPS_LABEL.len() + 6), 0))
+            .at_position((config.columns.saturating_sub(FPS_LABEL.len() + 6), 0))
             .set_visibility(config.show_frame_rate);
 
-        let snake = world.create_snake();
+        let level_text = Text::default()
+            .center((config.columns / 2, 0))
+            .with_fg(Color::Yellow)
+            .show();
+
+        let replay_text = Text::default()
+            .at_position((2, config.rows.saturating_sub(1)))
+            .hide();
+
+        let game_over_menu = Menu::new(
+            GameOverOption::ALL,
+            world.get_center_position() + Point::new(0usize, 3),
+        )
+        .hide();
+
+        let name_text = Text::default()
+            .center(world.get_center_position() + Point::new(0usize, 3))
+            .with_fg(Color::Yellow)
+            .hide();
+
+        let error_text = Text::default()
+            .center(world.get_center_position() + Point::new(0usize, 2))
+            .with_fg(Color::Red)
+            .hide();
+
+        let (_, bottom) = world.min_size();
+        let spectators_text = Text::default()
+            .center((config.columns / 2, bottom.saturating_sub(1)))
+            .with_fg(Color::Yellow)
+            .hide();
 
-        Self {
-            config,
-            world,
-            food,
+        let mut speed_level = Score::new(Point::new(0, 0)).with_label("Speed");
+        speed_level.set(1);
+
+        let mut scene = Self {
+            game,
             state_text,
+            seed_text,
             fps_text,
-            snake,
+            level_text,
+            replay_text,
+            game_over_menu,
+            name_text,
+            name: String::new(),
             state: SnakeSceneState::Playing,
             score: Score::new(Point::new(2, 0)),
+            speed_level,
+            tick: 0,
+            recorder: None,
+            player: None,
+            high_scores_path: None,
+            autopilot: None,
+            bot: None,
+            spectators: None,
+            spectators_text,
+            error_text,
+        }
+        .with_level_text();
+
+        scene.layout_hud();
+        scene
+    }
+
+    /// Plays back a recorded game, ignoring movement keys in favor of the
+    /// inputs stored in the replay.
+    pub fn from_replay(replay: Replay) -> Self {
+        let player = ReplayPlayer::new(replay);
+        let mut scene = Self::new(player.config().clone());
+        scene.replay_text.update_value(player.status());
+        scene.replay_text.visible = true;
+        scene.player = Some(player);
+        scene
+    }
+
+    /// Records every input applied to the snake, saving it to `path` when the
+    /// game ends or the player quits.
+    pub fn with_recorder(mut self, path: PathBuf) -> Self {
+        let replay = Replay::new(self.game.config(), self.game.world().seed());
+        self.recorder = Some(ReplayRecorder::new(path, replay));
+        self
+    }
+
+    /// Prompts for a name and saves the score to `path` when the game ends
+    /// with a score that makes the high score table.
+    pub fn with_high_scores(mut self, path: PathBuf) -> Self {
+        self.high_scores_path = Some(path);
+        self
+    }
+
+    /// Grows the snake to `length` over its first few moves, such as to keep
+    /// the length it had at the end of the previous level.
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.game.grow_to(length);
+        self
+    }
+
+    /// Lets the computer steer the snake, such as for a demo game.
+    pub fn with_autopilot(mut self, difficulty: Difficulty) -> Self {
+        self.autopilot = Some(Autopilot::new(difficulty));
+        self
+    }
+
+    /// Lets an external program steer the snake in place of the keyboard.
+    pub fn with_bot(mut self, bot: Bot) -> Self {
+        self.bot = Some(bot);
+        self
+    }
+
+    /// Sends every update of the game to the spectators.
+    pub fn with_spectators(mut self, spectators: Rc<RefCell<Spectators>>) -> Self {
+        self.spectators = Some(spectators);
+        self.update_spectators_text();
+        self
+    }
+
+    fn update_spectators_text(&mut self) {
+        let Some(status) = self.spectators.as_ref().and_then(|s| s.borrow().status()) else {
+            return;
+        };
+
+        self.spectators_text.update_value(status);
+        self.spectators_text.visible = true;
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        self.game.config()
+    }
+
+    /// The length and speed the snake has reached.
+    pub fn snake_stats(&self) -> (usize, f32) {
+        let snake = self.game.snake();
+        (snake.size(), snake.speed())
+    }
+
+    /// True once the snake has crashed or cleared the level.
+    pub fn is_over(&self) -> bool {
+        matches!(
+            self.state,
+            SnakeSceneState::GameOver | SnakeSceneState::EnteringName
+        )
+    }
+
+    /// True once the score has reached the level's target.
+    pub fn is_level_clear(&self) -> bool {
+        self.game.is_level_clear()
+    }
+
+    fn with_level_text(mut self) -> Self {
+        self.update_level_text();
+        self
+    }
+
+    /// Shows the level's name and how close the score is to its target.
+    fn update_level_text(&mut self) {
+        let level = self.game.world().level();
+        let value = match level.target {
+            Some(target) => format!(" {} {}/{target} ", level.name, self.score.value()),
+            None if !level.name.is_empty() => format!(" {} ", level.name),
+            None => String::new(),
+        };
+
+        self.level_text.update_value(value.to_uppercase());
+    }
+
+    /// Lines the speed level and effects up after the score, which can have
+    /// just grown a digit.
+    fn layout_hud(&mut self) {
+        let mut x = 2 + self.score.width();
+        if self.game.config().curve.is_some() {
+            self.speed_level.move_to(Point::new(x, 0));
+            x += self.speed_level.width();
         }
+
+        self.game.effects_mut().move_to(Point::new(x, 0));
     }
 
-    fn create_world(config: &GameConfig) -> World {
-        World::new(config, Point::new(0, 0))
+    /// Brings the score, speed level and level text up to date with the game.
+    fn update_hud(&mut self) {
+        let score = self.game.score();
+        let speed_level = self.game.speed_level() + 1;
+        if score == self.score.value() && speed_level == self.speed_level.value() {
+            return;
+        }
+
+        self.score.set(score);
+        self.speed_level.set(speed_level);
+        self.layout_hud();
+        self.update_level_text();
     }
 
-    fn update_scene(&mut self, elapsed: &Duration) -> GameLoopSignal {
+    fn update_scene(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
         self.state_text.visible = false;
 
         if self.state == SnakeSceneState::Paused {
             self.state_text.update_value(PAUSE_TEXT);
             self.state_text.visible = true;
-            return GameLoopSignal::Run;
+            return Ok(GameLoopSignal::Run);
         }
 
-        self.snake.update(elapsed);
+        if let Some(player) = &mut self.player {
+            for input in player.inputs_for(self.tick) {
+                self.game.steer(&input);
+            }
+        }
 
-        if self.world.detect_collision(self.snake.head()) || self.snake.detect_self_collision() {
-            self.state = SnakeSceneState::GameOver;
-            self.state_text.update_value(GAME_OVER);
-            self.state_text.visible = true;
+        self.tick += 1;
+        let outcome = self.game.update(elapsed);
+        self.update_hud();
 
-            return GameLoopSignal::Run;
+        match outcome {
+            Some(Outcome::Crashed) => {
+                self.end_game(GAME_OVER)?;
+                return Ok(GameLoopSignal::Run);
+            }
+            Some(Outcome::Ate { .. }) if self.is_level_clear() => {
+                self.end_game(LEVEL_CLEAR)?;
+                return Ok(GameLoopSignal::Run);
+            }
+            _ => (),
+        }
+
+        if let Some(autopilot) = &mut self.autopilot
+            && let Some(input) = autopilot.update(
+                elapsed,
+                self.game.world(),
+                0,
+                &[self.game.snake()],
+                self.game.food().get_position(),
+            )
+        {
+            self.game.steer(&input);
+        }
+
+        self.update_bot()
+    }
+
+    /// Applies the bot's move once the snake reaches a new tile. Anything
+    /// going wrong with the bot ends the game with the reason shown.
+    fn update_bot(&mut self) -> Result<GameLoopSignal> {
+        let Some(bot) = &mut self.bot else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        let state = bot.update(
+            self.game.world(),
+            &[self.game.snake()],
+            0,
+            self.game.food().get_position(),
+            self.game.score(),
+            self.tick,
+        );
+
+        match state {
+            Ok(Some(input)) => {
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(self.tick, input);
+                }
+
+                self.game.steer(&input);
+            }
+            Ok(None) => (),
+            Err(error) => {
+                self.show_error(&error);
+                self.end_game(BOT_ERROR)?;
+            }
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    /// The game as spectators see it, a single player game with no rounds.
+    pub fn snapshot(&self) -> Snapshot {
+        let (world, food) = (self.game.world(), self.game.food());
+
+        Snapshot {
+            tick: self.tick,
+            round: 0,
+            phase: match self.is_over() {
+                true => Phase::RoundOver,
+                false => Phase::Playing,
+            },
+            winner: None,
+            players: vec![PlayerSnapshot {
+                id: 0,
+                name: SPECTATOR_NAME.to_string(),
+                slot: 0,
+                score: self.game.score(),
+                rounds: 0,
+                snake: Some(BotSnake::new(world, self.game.snake())),
+            }],
+            food: world
+                .to_tile(food.get_position())
+                .map(|tile| (tile.x, tile.y)),
+            food_kind: food.kind(),
         }
+    }
 
-        if self.snake.detect_head_collision(self.food.get_position()) {
-            self.snake.grow(self.config.snake.grow_rate);
-            self.food = self.spawn_food();
-            self.score.increment();
+    fn end_game(&mut self, message: &str) -> Result<()> {
+        self.state = SnakeSceneState::GameOver;
+        self.state_text.update_value(message);
+        self.state_text.visible = true;
+        self.seed_text.visible = true;
+        self.save_recording()?;
+
+        if self.player.is_none()
+            && let Some(high_scores) = self.load_high_scores()
+            && high_scores.qualifies(&RuleSet::from(self.game.config()), self.game.score())
+        {
+            self.state = SnakeSceneState::EnteringName;
+            self.update_name_text();
+            self.name_text.visible = true;
+            return Ok(());
         }
 
+        self.game_over_menu.visible = self.player.is_none();
+        Ok(())
+    }
+
+    fn show_error(&mut self, error: &Error) {
+        self.error_text.update_value(format!("{error:#}"));
+        self.error_text.visible = true;
+    }
+
+    /// Reads the high scores, if they're being kept. A file that can't be read
+    /// is shown as an error and treated as empty, so the score isn't lost.
+    fn load_high_scores(&mut self) -> Option<HighScores> {
+        let path = self.high_scores_path.as_ref()?;
+        let high_scores = HighScores::load(path);
+
+        Some(high_scores.unwrap_or_else(|error| {
+            self.show_error(&error);
+            HighScores::default()
+        }))
+    }
+
+    fn update_name_text(&mut self) {
+        let cursor = if self.name.len() < MAX_NAME_LENGTH {
+            "_"
+        } else {
+            ""
+        };
+        self.name_text
+            .update_value(format!("{NAME_PROMPT}{}{cursor}", self.name));
+    }
+
+    fn save_high_score(&mut self) {
+        let Some(mut high_scores) = self.load_high_scores() else {
+            return;
+        };
+
+        let name = match self.name.trim() {
+            "" => DEFAULT_NAME.to_string(),
+            name => name.to_string(),
+        };
+
+        high_scores.insert(
+            &RuleSet::from(self.game.config()),
+            HighScore::new(
+                name,
+                self.game.score(),
+                self.game.snake().length(),
+                self.game.play_time(),
+                self.game.world().seed(),
+            ),
+        );
+
+        if let Some(path) = &self.high_scores_path
+            && let Err(error) = high_scores.save(path)
+        {
+            self.show_error(&error);
+        }
+
+        self.state = SnakeSceneState::GameOver;
+        self.name_text.visible = false;
+        self.game_over_menu.visible = true;
+    }
+
+    fn process_name_input(&mut self, event: &Event) -> GameLoopSignal {
+        let Event::Key(e) = event else {
+            return GameLoopSignal::Run;
+        };
+
+        match e.code {
+            KeyCode::Enter => self.save_high_score(),
+            KeyCode::Backspace => {
+                self.name.pop();
+            }
+            KeyCode::Char(c)
+                if (c.is_ascii_alphanumeric() || c == ' ') && self.name.len() < MAX_NAME_LENGTH =>
+            {
+                self.name.push(c.to_ascii_uppercase());
+            }
+            _ => (),
+        }
+
+        self.update_name_text();
         GameLoopSignal::Run
     }
 
-    fn spawn_food(&self) -> Food {
-        let mut tries = 0;
-        let mut position = self.world.get_random_position();
-        while tries < 4 && self.snake.detect_collision(position) {
-            tries += 1;
-            position = self.world.get_random_position();
+    fn save_recording(&self) -> Result<()> {
+        match &self.recorder {
+            Some(recorder) => recorder.save(),
+            None => Ok(()),
         }
+    }
 
-        Food::new(position)
+    fn process_replay_input(&mut self, input: PlayerInput) -> GameLoopSignal {
+        let Some(player) = &mut self.player else {
+            return GameLoopSignal::Run;
+        };
+
+        match input {
+            PlayerInput::Quit => return GameLoopSignal::Stop,
+            PlayerInput::Pause => player.toggle_pause(),
+            PlayerInput::FastForward => player.toggle_fast_forward(),
+            PlayerInput::Step => player.step(),
+            _ => (),
+        }
+
+        self.replay_text.update_value(player.status());
+        GameLoopSignal::Run
     }
 }
 
@@ -116,12 +548,24 @@ impl GameScene for SnakeScene {
             .update_value(format!(" {}{} ", FPS_LABEL, timestep.frame_rate));
 
         vec![
-            self.food.draw(),
-            self.world.draw(),
-            self.snake.draw(),
+            self.game.food().draw(),
+            self.game.world().draw(),
+            self.game.snake().draw(),
             self.score.draw(),
+            match self.game.config().curve {
+                Some(_) => self.speed_level.draw(),
+                None => vec![],
+            },
+            self.game.effects().draw(),
             self.fps_text.draw(),
+            self.level_text.draw(),
+            self.replay_text.draw(),
+            self.spectators_text.draw(),
             self.state_text.draw(),
+            self.seed_text.draw(),
+            self.error_text.draw(),
+            self.game_over_menu.draw(),
+            self.name_text.draw(),
         ]
         .into_iter()
         .flatten()
@@ -129,28 +573,99 @@ impl GameScene for SnakeScene {
     }
 
     fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
-        Ok(match self.state {
-            SnakeSceneState::GameOver => GameLoopSignal::Run,
-            SnakeSceneState::Paused | SnakeSceneState::Playing => self.update_scene(elapsed),
-        })
+        let ticks = match &mut self.player {
+            Some(player) => player.ticks_to_run(),
+            None => 1,
+        };
+
+        for _ in 0..ticks {
+            if self.is_over() {
+                break;
+            }
+
+            self.update_scene(elapsed)?;
+        }
+
+        if let Some(spectators) = &self.spectators {
+            spectators
+                .borrow_mut()
+                .broadcast(self.game.world(), &self.snapshot());
+        }
+
+        self.update_spectators_text();
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.game.config().frame_rate)
+    }
+
+    /// The world keeps the size it was created with, so it needs a terminal at
+    /// least that big.
+    fn min_size(&self) -> (usize, usize) {
+        self.game.world().min_size()
+    }
+
+    /// Pauses the game so the player has a chance to get ready once the
+    /// terminal is large enough to show all of the world again.
+    fn resize(&mut self, _columns: usize, _rows: usize) -> Result<()> {
+        if self.state == SnakeSceneState::Playing {
+            self.state = SnakeSceneState::Paused;
+            self.state_text.update_value(PAUSE_TEXT);
+            self.state_text.visible = true;
+        }
+
+        if let Some(player) = &mut self.player
+            && !player.is_paused()
+        {
+            player.toggle_pause();
+            self.replay_text.update_value(player.status());
+        }
+
+        Ok(())
     }
 
     fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        if self.state == SnakeSceneState::EnteringName {
+            return Ok(self.process_name_input(event));
+        }
+
         let input = match event {
             Event::Key(e) => match e.code {
                 KeyCode::Char('a') | KeyCode::Left => PlayerInput::Left,
                 KeyCode::Char('s') | KeyCode::Down => PlayerInput::Down,
                 KeyCode::Char('d') | KeyCode::Right => PlayerInput::Right,
                 KeyCode::Char('w') | KeyCode::Up => PlayerInput::Up,
-                KeyCode::Char('p') => PlayerInput::Pause,
+                KeyCode::Char('p') | KeyCode::Char(' ') => PlayerInput::Pause,
+                KeyCode::Char('f') => PlayerInput::FastForward,
+                KeyCode::Char('n') => PlayerInput::Step,
                 KeyCode::Char('q') => PlayerInput::Quit,
+                KeyCode::Enter => PlayerInput::Select,
                 _ => PlayerInput::Noop,
             },
             _ => PlayerInput::Noop,
         };
 
+        if self.player.is_some() {
+            return Ok(self.process_replay_input(input));
+        }
+
         Ok(match (input, &self.state) {
-            (PlayerInput::Quit, _) => GameLoopSignal::Stop,
+            (PlayerInput::Quit, _) => {
+                self.save_recording()?;
+                GameLoopSignal::Stop
+            }
+            (PlayerInput::Up, SnakeSceneState::GameOver) => {
+                self.game_over_menu.select_previous();
+                GameLoopSignal::Run
+            }
+            (PlayerInput::Down, SnakeSceneState::GameOver) => {
+                self.game_over_menu.select_next();
+                GameLoopSignal::Run
+            }
+            (PlayerInput::Select, SnakeSceneState::GameOver) => {
+                GameOverOption::ALL[self.game_over_menu.selected_index()].perform_action()
+            }
             (PlayerInput::Pause, SnakeSceneState::Paused) => {
                 self.state = SnakeSceneState::Playing;
                 GameLoopSignal::Run
@@ -159,11 +674,405 @@ impl GameScene for SnakeScene {
                 self.state = SnakeSceneState::Paused;
                 GameLoopSignal::Run
             }
-            (input, SnakeSceneState::Playing) => {
-                self.snake.process_input(&input);
+            (
+                input @ (PlayerInput::Up
+                | PlayerInput::Down
+                | PlayerInput::Left
+                | PlayerInput::Right),
+                SnakeSceneState::Playing,
+            ) if self.bot.is_none() => {
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(self.tick, input);
+                }
+
+                self.game.steer(&input);
                 GameLoopSignal::Run
             }
             _ => GameLoopSignal::Run,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use snake::entities::{
+        effects::Effect,
+        food::{Food, FoodKind},
+    };
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        GameMode,
+        curve::SpeedCurve,
+        engine::{
+            backend::MemoryBackend,
+            game_loop::{GameLoop, TOO_SMALL_TEXT},
+            input::ScriptedInput,
+        },
+        test_support::{headless_loop, test_config},
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            rows: 32,
+            columns: 50,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    fn create_game_loop(input: &ScriptedInput) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        game_loop
+            .register_scene(TitleScene::new(config()))
+            .register_scene_factory(|| SnakeScene::new(config()))
+            .load_scene::<SnakeScene>();
+
+        game_loop
+    }
+
+    #[test]
+    fn it_ends_the_game_when_hitting_a_wall() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        game_loop.step(1).unwrap();
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[test]
+    fn it_shows_the_seed_when_the_game_is_over() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        game_loop
+            .register_scene(SnakeScene::new(GameConfig {
+                seed: Some(1234),
+                ..config()
+            }))
+            .load_scene::<SnakeScene>();
+
+        game_loop.step(1).unwrap();
+        assert!(!game_loop.renderer().backend().contains("SEED: 1234"));
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains("SEED: 1234"));
+    }
+
+    #[test]
+    fn it_shows_the_port_spectators_can_watch_on() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        let scene = SnakeScene::new(config());
+        let spectators = Spectators::listen(0, scene.game.world()).unwrap();
+        let port = spectators.port().unwrap();
+
+        game_loop
+            .register_scene(scene.with_spectators(Rc::new(RefCell::new(spectators))))
+            .load_scene::<SnakeScene>();
+
+        game_loop.step(1).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains(&format!("SPECTATE ON PORT {port}"))
+        );
+    }
+
+    #[test]
+    fn it_clears_the_level_when_reaching_its_target() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        // The seed puts the first food on the tile in front of the snake
+        game_loop
+            .register_scene(SnakeScene::new(GameConfig {
+                seed: Some(1),
+                level: Some("name: Short\ntarget: 1\n---\nS.".parse().unwrap()),
+                ..config()
+            }))
+            .load_scene::<SnakeScene>();
+
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains("SHORT 0/1"));
+
+        game_loop.step(5).unwrap();
+        assert!(game_loop.renderer().backend().contains("SHORT 1/1"));
+        assert!(game_loop.renderer().backend().contains(LEVEL_CLEAR));
+    }
+
+    #[test]
+    fn it_keeps_the_score_when_the_high_scores_cant_be_read() {
+        let path = std::env::temp_dir().join(format!(
+            "snake-{}-corrupt-high-scores.json",
+            std::process::id()
+        ));
+        fs::write(&path, "not json").unwrap();
+
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        // The seed puts the first food on the tile in front of the snake
+        game_loop
+            .register_scene(
+                SnakeScene::new(GameConfig {
+                    seed: Some(1),
+                    level: Some("name: Short\n---\nS.".parse().unwrap()),
+                    ..config()
+                })
+                .with_high_scores(path.clone()),
+            )
+            .load_scene::<SnakeScene>();
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains(NAME_PROMPT));
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("Failed to parse high scores")
+        );
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains(GAME_OVER));
+
+        let high_scores = HighScores::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(high_scores.tables()[0].entries[0].score, 1);
+    }
+
+    #[test]
+    fn it_survives_when_turning_away_from_a_wall() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        input.push_key(KeyCode::Down);
+        game_loop.step(5).unwrap();
+
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[test]
+    fn it_pauses_the_game() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        input.push_key(KeyCode::Char('p'));
+        game_loop.step(60).unwrap();
+
+        assert!(game_loop.renderer().backend().contains(PAUSE_TEXT));
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[test]
+    fn it_starts_a_fresh_game_when_playing_again() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains("PLAY AGAIN"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(2).unwrap();
+
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+        assert!(game_loop.renderer().backend().contains("Score: 0"));
+    }
+
+    #[test]
+    fn it_returns_to_the_title_from_the_game_over_menu() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        game_loop.step(30).unwrap();
+        input.push_key(KeyCode::Down);
+        game_loop.step(1).unwrap();
+        input.push_key(KeyCode::Enter);
+        game_loop.step(2).unwrap();
+
+        assert!(game_loop.renderer().backend().contains("NEW GAME"));
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[test]
+    fn it_pauses_while_the_terminal_is_too_small() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        input.push(Event::Resize(20, 10));
+        game_loop.step(5).unwrap();
+        assert!(game_loop.renderer().backend().contains(TOO_SMALL_TEXT));
+
+        input.push(Event::Resize(config().columns as u16, config().rows as u16));
+        game_loop.step(5).unwrap();
+        assert!(!game_loop.renderer().backend().contains(TOO_SMALL_TEXT));
+        assert!(game_loop.renderer().backend().contains(PAUSE_TEXT));
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[test]
+    fn it_stops_the_loop_when_quitting() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input);
+
+        input.push_key(KeyCode::Char('q'));
+        assert!(!game_loop.step(1).unwrap());
+    }
+
+    /// A scene on a wrapping world, so the snake can keep going straight,
+    /// with `kind` of food on the tile `offset` tiles in front of the snake.
+    /// The seed keeps the food placed after eating away from the snake's
+    /// path, so tests only ever score what they put in front of it.
+    fn create_scene_with_food(kind: FoodKind, offset: usize) -> SnakeScene {
+        let mut scene = SnakeScene::new(GameConfig {
+            mode: GameMode::Wrap,
+            seed: Some(1),
+            ..config()
+        });
+
+        let head = scene.game.snake().head();
+        scene
+            .game
+            .set_food(Food::of_kind(Point::new(head.x / 2 + offset, head.y), kind));
+        scene
+    }
+
+    #[test]
+    fn it_shows_the_effects_of_the_food_eaten() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        game_loop
+            .register_scene(create_scene_with_food(FoodKind::Ghost, 1))
+            .load_scene::<SnakeScene>();
+
+        game_loop.step(3).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("Score: 1  GHOST 5s")
+        );
+    }
+
+    #[test]
+    fn it_replaces_food_that_expires() {
+        let mut scene = create_scene_with_food(FoodKind::Bonus, 0);
+        scene
+            .game
+            .set_food(Food::of_kind(Point::new(1, 1), FoodKind::Bonus));
+
+        let frame = Duration::from_secs(1) / config().frame_rate as u32;
+        for _ in 0..config().frame_rate as usize * 6 {
+            scene.update(&frame).unwrap();
+        }
+        assert_eq!(scene.game.food().kind(), FoodKind::Bonus);
+
+        scene.update(&frame).unwrap();
+        assert_eq!(scene.game.food().kind(), FoodKind::Apple);
+        assert_eq!(scene.score.value(), 0);
+    }
+
+    #[test]
+    fn it_doubles_points_while_the_multiplier_is_active() {
+        let mut scene = create_scene_with_food(FoodKind::Multiplier, 1);
+        scene.game.effects_mut().start(Effect::Multiplier);
+
+        let frame = Duration::from_secs(1) / config().frame_rate as u32;
+        for _ in 0..3 {
+            scene.update(&frame).unwrap();
+        }
+
+        assert_eq!(scene.score.value(), 2);
+        assert!(!scene.is_over());
+    }
+
+    #[test]
+    fn it_speeds_up_along_the_curve() {
+        let mut scene = SnakeScene::new(GameConfig {
+            mode: GameMode::Wrap,
+            curve: SpeedCurve::builtin("steep"),
+            seed: Some(1),
+            ..config()
+        });
+
+        let head = scene.game.snake().head();
+        scene.game.set_food(Food::of_kind(
+            Point::new(head.x / 2 + 1, head.y),
+            FoodKind::Bonus,
+        ));
+
+        let frame = Duration::from_secs(1) / config().frame_rate as u32;
+        scene.update(&frame).unwrap();
+        assert_eq!(scene.game.snake().speed(), config().snake.speed);
+
+        // The bonus fruit in front scores 5, which is two speed levels up
+        for _ in 0..3 {
+            scene.update(&frame).unwrap();
+        }
+
+        assert_eq!(scene.game.snake().speed(), config().snake.speed + 3.0);
+        assert!(matches!(
+            scene.speed_level.draw()[..],
+            [DrawInstruction::Text { content: " Speed: 3 ", position, .. }] if position.x == 2 + scene.score.width()
+        ));
+    }
+
+    #[cfg(unix)]
+    fn create_bot_game_loop(input: &ScriptedInput, reply: &str) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        let bot = Bot::new(
+            format!("while read line; do echo '{reply}'; done"),
+            Duration::from_millis(500),
+        );
+
+        game_loop
+            .register_scene(SnakeScene::new(config()).with_bot(bot))
+            .load_scene::<SnakeScene>();
+
+        game_loop
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_lets_a_bot_steer_the_snake() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_bot_game_loop(&input, r#"{"move": "down"}"#);
+
+        // The keyboard is ignored, so turning back up doesn't undo the move
+        input.push_key(KeyCode::Up);
+        game_loop.step(5).unwrap();
+
+        assert!(!game_loop.renderer().backend().contains(GAME_OVER));
+        assert!(!game_loop.renderer().backend().contains(BOT_ERROR));
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains(GAME_OVER));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_ends_the_game_when_the_bot_fails() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_bot_game_loop(&input, "up please");
+
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains(BOT_ERROR));
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("isn't a move: up please")
+        );
+    }
+}
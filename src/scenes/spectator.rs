@@ -0,0 +1,411 @@
+use anyhow::{Result, bail};
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::time::Duration;
+
+use crate::{
+    GameConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{board::Board, text::Text},
+    level::Level,
+    net::{
+        ClientMessage, MIN_PROTOCOL_VERSION, PING_INTERVAL, PROTOCOL_VERSION, Peer, ServerMessage,
+    },
+};
+
+/// The camera follows the highlighted snake around levels bigger than the
+/// terminal, so spectators only need enough room for the scoreboard.
+const MIN_SIZE: (usize, usize) = (40, 12);
+
+const SCOREBOARD_TITLE: &str = "SCOREBOARD";
+const SCOREBOARD_KEYS: &str = "←/→ SWITCH  TAB HIDE  Q QUIT";
+const DISCONNECTED_TEXT: &str = "DISCONNECTED";
+const QUIT_TEXT: &str = "PRESS Q TO QUIT";
+
+/// Watches a game hosted with `snake serve` or played with `--spectate`
+/// without taking part in it. Spectators have their own camera, which keeps
+/// the highlighted snake in view, and a scoreboard overlay. Nothing they press
+/// is sent to the game.
+#[derive(Debug)]
+pub struct SpectatorScene {
+    config: GameConfig,
+    peer: Peer<ServerMessage>,
+    /// Why the game went away, once it has.
+    disconnected: Option<String>,
+    since_sent: Duration,
+    board: Board,
+    /// The id of the player whose snake is highlighted.
+    following: Option<usize>,
+    show_scoreboard: bool,
+    scoreboard_text: Text,
+    status_text: Text,
+    disconnected_text: Text,
+}
+
+impl SpectatorScene {
+    /// Connects to the game at `address` and waits for it to let the
+    /// spectator in.
+    pub fn watch(config: GameConfig, address: &str) -> Result<Self> {
+        let mut peer = Peer::connect(address)?;
+        peer.send(&ClientMessage::Watch {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+        })?;
+
+        let (level, wrap) = match peer.wait()? {
+            ServerMessage::Welcome { level, wrap, .. } => (level, wrap),
+            ServerMessage::Rejected { reason } => bail!("The game turned us away: {reason}"),
+            message => bail!("Expected a welcome from the game, got {message:?}"),
+        };
+
+        let mut scene = Self {
+            board: Board::with_camera(&config, level, wrap),
+            config,
+            peer,
+            disconnected: None,
+            since_sent: Duration::ZERO,
+            following: None,
+            show_scoreboard: true,
+            scoreboard_text: Text::default(),
+            status_text: Text::default(),
+            disconnected_text: Text::default(),
+        };
+
+        scene.create_texts();
+        Ok(scene)
+    }
+
+    /// Lays the texts out for the config's columns and rows.
+    fn create_texts(&mut self) {
+        let (columns, rows) = (self.config.columns, self.config.rows);
+
+        self.scoreboard_text = Text::default().center((columns / 2, 2));
+        self.status_text = Text::default()
+            .center((columns / 2, rows.saturating_sub(1)))
+            .with_fg(Color::Yellow);
+        self.disconnected_text = Text::default()
+            .center(Point::new(columns / 2, rows.saturating_sub(4) / 2))
+            .with_fg(Color::Red);
+
+        self.update_texts();
+    }
+
+    /// Recreates the board for a new level or terminal size, keeping the
+    /// latest snapshot.
+    fn create_board(&mut self, level: Level, wrap: bool) {
+        let snapshot = self.board.snapshot().clone();
+        self.board = Board::with_camera(&self.config, level, wrap);
+        self.board.update(snapshot);
+        self.follow();
+    }
+
+    /// Keeps the camera on the highlighted snake, switching to the first
+    /// player when the one being followed leaves.
+    fn follow(&mut self) {
+        let players = &self.board.snapshot().players;
+        if !players
+            .iter()
+            .any(|player| Some(player.id) == self.following)
+        {
+            self.following = players.first().map(|player| player.id);
+        }
+
+        let head = players
+            .iter()
+            .find(|player| Some(player.id) == self.following)
+            .and_then(|player| player.snake.as_ref())
+            .and_then(|snake| snake.body.first().copied());
+
+        self.board.highlight(self.following);
+        if let Some(head) = head {
+            self.board.follow(head);
+        }
+    }
+
+    /// Highlights the player `step` places after the one being followed.
+    fn switch(&mut self, step: isize) {
+        let players = &self.board.snapshot().players;
+        let Some(index) = players
+            .iter()
+            .position(|player| Some(player.id) == self.following)
+        else {
+            return;
+        };
+
+        let index = (index as isize + step).rem_euclid(players.len() as isize) as usize;
+        self.following = Some(players[index].id);
+        self.follow();
+        self.update_texts();
+    }
+
+    fn update_texts(&mut self) {
+        let players = &self.board.snapshot().players;
+        let following = players
+            .iter()
+            .find(|player| Some(player.id) == self.following);
+
+        self.status_text.visible = following.is_some() && self.disconnected.is_none();
+        if let Some(player) = following {
+            self.status_text
+                .update_value(format!(" WATCHING {} ", player.name));
+        }
+
+        let rows: Vec<_> = players
+            .iter()
+            .map(|player| {
+                let length = player
+                    .snake
+                    .as_ref()
+                    .map_or("-".to_string(), |snake| snake.body.len().to_string());
+
+                format!(
+                    "{} {:<8}  SCORE {:>3}  ROUNDS {:>2}  LENGTH {:>3}",
+                    match Some(player.id) == self.following {
+                        true => '>',
+                        false => ' ',
+                    },
+                    player.name,
+                    player.score,
+                    player.rounds,
+                    length
+                )
+            })
+            .collect();
+
+        self.scoreboard_text.visible = self.show_scoreboard && self.disconnected.is_none();
+        self.scoreboard_text.update_value(format!(
+            "{SCOREBOARD_TITLE}\n\n{}\n\n{SCOREBOARD_KEYS}",
+            rows.join("\n")
+        ));
+
+        self.disconnected_text.visible = self.disconnected.is_some();
+        if let Some(reason) = &self.disconnected {
+            self.disconnected_text
+                .update_value(format!("{DISCONNECTED_TEXT}\n\n{reason}\n\n{QUIT_TEXT}"));
+        }
+    }
+
+    fn disconnect(&mut self, reason: String) {
+        self.disconnected = Some(reason);
+        self.update_texts();
+    }
+}
+
+impl GameScene for SpectatorScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        self.board
+            .draw()
+            .into_iter()
+            .chain(self.scoreboard_text.draw())
+            .chain(self.status_text.draw())
+            .chain(self.disconnected_text.draw())
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        if self.disconnected.is_some() {
+            return Ok(GameLoopSignal::Run);
+        }
+
+        let messages = match self.peer.receive(elapsed) {
+            Ok(messages) => messages,
+            Err(error) => {
+                self.disconnect(error.to_string());
+                return Ok(GameLoopSignal::Run);
+            }
+        };
+
+        for message in messages {
+            match message {
+                ServerMessage::Snapshot(snapshot) => self.board.update(snapshot),
+                ServerMessage::Level { level, wrap } => self.create_board(level, wrap),
+                ServerMessage::Bye { reason } | ServerMessage::Rejected { reason } => {
+                    self.disconnect(reason);
+                    return Ok(GameLoopSignal::Run);
+                }
+                ServerMessage::Welcome { .. } => {}
+            }
+        }
+
+        self.follow();
+        self.update_texts();
+
+        self.since_sent += *elapsed;
+        if self.since_sent >= PING_INTERVAL {
+            self.since_sent = Duration::ZERO;
+            if let Err(error) = self.peer.send(&ClientMessage::Ping) {
+                self.disconnect(error.to_string());
+            }
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let Event::Key(e) = event else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        match e.code {
+            KeyCode::Char('q') => {
+                if self.disconnected.is_none() {
+                    let _ = self.peer.send(&ClientMessage::Bye);
+                }
+
+                return Ok(GameLoopSignal::Stop);
+            }
+            KeyCode::Left | KeyCode::Char('a') => self.switch(-1),
+            KeyCode::Right | KeyCode::Char('d') => self.switch(1),
+            KeyCode::Tab => {
+                self.show_scoreboard = !self.show_scoreboard;
+                self.update_texts();
+            }
+            _ => {}
+        }
+
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        MIN_SIZE
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        self.config.resize(columns, rows);
+        self.create_board(self.board.level().clone(), self.board.wraps());
+        self.create_texts();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{backend::MemoryBackend, game_loop::GameLoop, input::ScriptedInput},
+        scenes::{client::ClientScene, server::ServerScene},
+        test_support::{headless_loop, test_config},
+    };
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+            mpsc,
+        },
+        thread::{self, JoinHandle},
+    };
+
+    fn create_game_loop<T: GameScene>(input: &ScriptedInput, scene: T) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&test_config(), input);
+
+        game_loop.register_scene(scene).load_scene::<T>();
+        game_loop
+    }
+
+    /// Runs a server on its own thread until `running` is cleared, returning
+    /// its address.
+    fn serve(running: Arc<AtomicBool>) -> (String, JoinHandle<()>) {
+        let (sender, address) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let scene = ServerScene::bind(test_config(), 0).unwrap();
+            sender.send(format!("127.0.0.1:{}", scene.port())).unwrap();
+
+            let mut game_loop = create_game_loop(&ScriptedInput::new(), scene);
+            while running.load(Ordering::Relaxed) {
+                game_loop.step(1).unwrap();
+                thread::sleep(Duration::from_millis(2));
+            }
+        });
+
+        (address.recv().unwrap(), server)
+    }
+
+    fn step_until(game_loop: &mut GameLoop<MemoryBackend>, text: &str) {
+        for _ in 0..500 {
+            game_loop.step(1).unwrap();
+            if game_loop.renderer().backend().contains(text) {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        panic!("{text} was never drawn");
+    }
+
+    #[test]
+    fn it_watches_a_game_without_joining_it() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (address, server) = serve(running.clone());
+
+        let input = ScriptedInput::new();
+        let spectator = SpectatorScene::watch(test_config(), &address).unwrap();
+        let mut game_loop = create_game_loop(&input, spectator);
+        step_until(&mut game_loop, "WAITING FOR PLAYERS");
+
+        let _first = ClientScene::join(test_config(), &address, Some("kyle".into())).unwrap();
+        let _second = ClientScene::join(test_config(), &address, None).unwrap();
+
+        // Spectators don't take a player's seat
+        step_until(&mut game_loop, "ROUND 1  KYLE 0  P2 0");
+        step_until(&mut game_loop, "WATCHING KYLE");
+        assert!(game_loop.renderer().backend().contains("> KYLE"));
+
+        input.push_key(KeyCode::Right);
+        step_until(&mut game_loop, "WATCHING P2");
+        assert!(game_loop.renderer().backend().contains("> P2"));
+
+        input.push_key(KeyCode::Tab);
+        game_loop.step(1).unwrap();
+        assert!(!game_loop.renderer().backend().contains(SCOREBOARD_TITLE));
+
+        input.push_key(KeyCode::Char('q'));
+        assert!(!game_loop.step(1).unwrap());
+
+        running.store(false, Ordering::Relaxed);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn it_ignores_keys_that_would_steer() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (address, server) = serve(running.clone());
+
+        let input = ScriptedInput::new();
+        let spectator = SpectatorScene::watch(test_config(), &address).unwrap();
+        let mut game_loop = create_game_loop(&input, spectator);
+
+        let _first = ClientScene::join(test_config(), &address, None).unwrap();
+        let _second = ClientScene::join(test_config(), &address, None).unwrap();
+        step_until(&mut game_loop, "ROUND 1");
+
+        // Steering up would take the first snake into the wall, but spectators
+        // are never sent to the server
+        for _ in 0..3 {
+            input.push_key(KeyCode::Up);
+            game_loop.step(1).unwrap();
+        }
+
+        for _ in 0..10 {
+            game_loop.step(1).unwrap();
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert!(!game_loop.renderer().backend().contains("WINS THE ROUND"));
+
+        running.store(false, Ordering::Relaxed);
+        server.join().unwrap();
+        step_until(&mut game_loop, DISCONNECTED_TEXT);
+    }
+}
@@ -7,17 +7,25 @@ use std::{fmt::Display, time::Duration};
 
 use crate::{
     GameConfig, PlayerInput,
+    ai::Difficulty,
     engine::{
         game_loop::GameLoopSignal,
         point::Point,
-        renderer::{DrawInstruction, Style},
+        renderer::DrawInstruction,
         timestep::Timestep,
         traits::{Entity, GameScene},
     },
-    entities::text::Text,
+    entities::{menu::Menu, text::Text},
 };
 
-use super::snake::SnakeScene;
+use super::{
+    campaign::CampaignScene,
+    high_scores::HighScoresScene,
+    level_editor::LevelEditorScene,
+    settings::SettingsScene,
+    snake::SnakeScene,
+    versus::{CpuVersusScene, VersusScene},
+};
 
 const TITLE: &str = "
 ███████╗███╗   ██╗ █████╗ ██╗  ██╗███████╗
@@ -43,9 +51,22 @@ const STATIC_SNAKE: &str = "
 
 const STATIC_FOOD: &str = "⬤";
 
+const DEMO_TEXT: &str = " PRESS ANY KEY ";
+
+/// How long the title screen waits for a key before playing a demo game.
+const ATTRACT_DELAY: Duration = Duration::from_secs(10);
+
+/// The title art is drawn 15 rows above the center and is 42 columns wide.
+const MIN_SIZE: (usize, usize) = (44, 30);
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord)]
 enum MenuOption {
     NewGame = 0,
+    Versus,
+    VersusCpu,
+    Campaign,
+    LevelEditor,
+    HighScores,
     Settings,
     Exit,
 }
@@ -54,13 +75,29 @@ impl MenuOption {
     pub fn perform_action(&self) -> GameLoopSignal {
         match self {
             Self::NewGame => GameLoopSignal::load_scene::<SnakeScene>(),
+            Self::Versus => GameLoopSignal::load_scene::<VersusScene>(),
+            Self::VersusCpu => GameLoopSignal::load_scene::<CpuVersusScene>(),
+            Self::Campaign => GameLoopSignal::load_scene::<CampaignScene>(),
+            Self::LevelEditor => GameLoopSignal::load_scene::<LevelEditorScene>(),
+            Self::HighScores => GameLoopSignal::load_scene::<HighScoresScene>(),
+            Self::Settings => GameLoopSignal::load_scene::<SettingsScene>(),
             Self::Exit => GameLoopSignal::Stop,
-            _ => GameLoopSignal::Run,
         }
     }
 
     pub fn iter() -> impl Iterator<Item = Self> {
-        [Self::NewGame, Self::Settings, Self::Exit].iter().copied()
+        [
+            Self::NewGame,
+            Self::Versus,
+            Self::VersusCpu,
+            Self::Campaign,
+            Self::LevelEditor,
+            Self::HighScores,
+            Self::Settings,
+            Self::Exit,
+        ]
+        .iter()
+        .copied()
     }
 }
 
@@ -68,6 +105,11 @@ impl Display for MenuOption {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::NewGame => write!(f, "    NEW GAME    "),
+            Self::Versus => write!(f, "     VERSUS     "),
+            Self::VersusCpu => write!(f, "     VS CPU     "),
+            Self::Campaign => write!(f, "    CAMPAIGN    "),
+            Self::LevelEditor => write!(f, "  LEVEL EDITOR  "),
+            Self::HighScores => write!(f, "  HIGH SCORES   "),
             Self::Settings => write!(f, "    SETTINGS    "),
             Self::Exit => write!(f, "      EXIT      "),
         }
@@ -80,8 +122,13 @@ impl TryFrom<usize> for MenuOption {
     fn try_from(value: usize) -> std::prelude::v1::Result<Self, Self::Error> {
         Ok(match value {
             0 => Self::NewGame,
-            1 => Self::Settings,
-            2 => Self::Exit,
+            1 => Self::Versus,
+            2 => Self::VersusCpu,
+            3 => Self::Campaign,
+            4 => Self::LevelEditor,
+            5 => Self::HighScores,
+            6 => Self::Settings,
+            7 => Self::Exit,
             _ => return Err(anyhow!("Failed to convert {value} to MenuOption")),
         })
     }
@@ -89,86 +136,92 @@ impl TryFrom<usize> for MenuOption {
 
 #[derive(Debug)]
 pub struct TitleScene {
+    config: GameConfig,
     title_text: Text,
     static_snake: Text,
     static_food: Text,
-    options: Vec<Text>,
-    selected_index: usize,
+    menu: Menu,
+    demo_text: Text,
+    demo: Option<Box<SnakeScene>>,
+    idle: Duration,
 }
 
 impl TitleScene {
     pub fn new(config: GameConfig) -> Self {
         let origin = Point::new(0, 0);
-        let diagonal = Point::new(config.columns - origin.x, config.rows - origin.y);
+        let diagonal = Point::new(config.columns, config.rows).saturating_sub(&origin);
         let center = Self::get_center_position(origin, diagonal);
 
         let title_text = Text::default()
             .with_value(TITLE)
-            .center(center - Point::new(0usize, 15))
+            .center(center.saturating_sub(&Point::new(0, 15)))
             .with_fg(Color::Yellow)
             .show();
 
         let static_snake = Text::default()
             .with_value(STATIC_SNAKE)
-            .center(center - Point::new(0usize, 8))
+            .center(center.saturating_sub(&Point::new(0, 8)))
             .with_fg(Color::Green)
             .show();
 
         let static_food = Text::default()
             .with_value(STATIC_FOOD)
-            .center(Point::new(center.x + 18, center.y - 4))
+            .center(Point::new(center.x + 18, center.y.saturating_sub(4)))
             .with_fg(Color::Red)
             .show();
 
-        let options = MenuOption::iter()
-            .enumerate()
-            .map(|(i, option)| {
-                let text = Text::default()
-                    .with_value(option.to_string())
-                    .center(center - Point::new(0usize, 5 - i))
-                    .with_fg(Color::Yellow)
-                    .show();
-
-                if i == 0 {
-                    return text.with_fg(Color::Black).with_bg(Color::Yellow);
-                }
+        let menu = Menu::new(MenuOption::iter(), center.saturating_sub(&Point::new(0, 5)));
 
-                text
-            })
-            .collect();
+        let demo_text = Text::default()
+            .with_value(DEMO_TEXT)
+            .center(center.saturating_sub(&Point::new(0, 5)))
+            .with_fg(Color::Yellow)
+            .show();
 
         Self {
+            config,
             title_text,
             static_snake,
             static_food,
-            options,
-            selected_index: 0,
+            menu,
+            demo_text,
+            demo: None,
+            idle: Duration::ZERO,
         }
     }
 
-    pub fn get_center_position(origin: Point, diagonal: Point) -> Point {
-        Point::new((origin.x + diagonal.x) / 2, (origin.y + diagonal.y) / 2)
-    }
+    /// A game steered by the computer that plays behind the title, like an
+    /// arcade machine's attract mode.
+    fn create_demo(&self) -> Box<SnakeScene> {
+        let scene = SnakeScene::new(GameConfig {
+            show_border: true,
+            show_frame_rate: false,
+            seed: None,
+            level: None,
+            ..self.config.clone()
+        });
 
-    pub fn select_previous_option(&mut self) {
-        self.selected_index = self
-            .selected_index
-            .wrapping_sub(1)
-            .clamp(0, self.options.len() - 1);
+        Box::new(scene.with_autopilot(Difficulty::Hard))
     }
 
-    pub fn select_next_option(&mut self) {
-        self.selected_index = (self.selected_index + 1) % self.options.len();
+    pub fn get_center_position(origin: Point, diagonal: Point) -> Point {
+        Point::new((origin.x + diagonal.x) / 2, (origin.y + diagonal.y) / 2)
     }
 }
 
 impl GameScene for TitleScene {
-    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+    fn draw(&mut self, timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        if let Some(demo) = &mut self.demo {
+            return demo
+                .draw(timestep)
+                .into_iter()
+                .chain(self.title_text.draw())
+                .chain(self.demo_text.draw())
+                .collect();
+        }
+
         vec![
-            self.options
-                .iter()
-                .flat_map(|option| option.draw())
-                .collect::<Vec<_>>(),
+            self.menu.draw(),
             self.title_text.draw(),
             self.static_snake.draw(),
             self.static_food.draw(),
@@ -178,25 +231,51 @@ impl GameScene for TitleScene {
         .collect()
     }
 
-    fn update(&mut self, _elapsed: &Duration) -> Result<GameLoopSignal> {
-        for (i, option) in self.options.iter_mut().enumerate() {
-            option.style = if self.selected_index == i {
-                Style {
-                    fg: Color::Black,
-                    bg: Color::Yellow,
-                }
-            } else {
-                Style {
-                    fg: Color::Yellow,
-                    bg: Color::Reset,
-                }
-            };
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        if let Some(demo) = &mut self.demo {
+            demo.update(elapsed)?;
+            if demo.is_over() {
+                self.demo = Some(self.create_demo());
+            }
+
+            return Ok(GameLoopSignal::Run);
+        }
+
+        self.idle += *elapsed;
+        if self.idle >= ATTRACT_DELAY {
+            self.demo = Some(self.create_demo());
         }
 
         Ok(GameLoopSignal::Run)
     }
 
+    fn min_size(&self) -> (usize, usize) {
+        MIN_SIZE
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        let selected_index = self.menu.selected_index();
+        *self = Self::new(GameConfig {
+            columns,
+            rows,
+            ..self.config.clone()
+        });
+
+        self.menu.select(selected_index);
+        Ok(())
+    }
+
     fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        if !matches!(event, Event::Key(_)) {
+            return Ok(GameLoopSignal::Run);
+        }
+
+        // Any key stops the demo without also acting on the menu
+        self.idle = Duration::ZERO;
+        if self.demo.take().is_some() {
+            return Ok(GameLoopSignal::Run);
+        }
+
         let input = match event {
             Event::Key(e) => match e.code {
                 KeyCode::Enter => PlayerInput::Select,
@@ -212,10 +291,10 @@ impl GameScene for TitleScene {
 
         match input {
             PlayerInput::Quit => return Ok(GameLoopSignal::Stop),
-            PlayerInput::Up => self.select_previous_option(),
-            PlayerInput::Down => self.select_next_option(),
+            PlayerInput::Up => self.menu.select_previous(),
+            PlayerInput::Down => self.menu.select_next(),
             PlayerInput::Select => {
-                return Ok(MenuOption::try_from(self.selected_index)?.perform_action());
+                return Ok(MenuOption::try_from(self.menu.selected_index())?.perform_action());
             }
             _ => (),
         }
@@ -223,3 +302,111 @@ impl GameScene for TitleScene {
         Ok(GameLoopSignal::Run)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    use super::*;
+    use crate::{
+        config::SnakeConfig,
+        engine::{game_loop::TOO_SMALL_TEXT, input::ScriptedInput},
+        test_support::{headless_loop, test_config},
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: 2,
+                size: 6,
+                ..test_config().snake
+            },
+            rows: 32,
+            show_border: false,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    fn menu_position(scene: &mut TitleScene) -> Point {
+        scene
+            .menu
+            .draw()
+            .into_iter()
+            .find_map(|instruction| match instruction {
+                DrawInstruction::Text { position, .. } => Some(position),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn it_re_centers_when_resized() {
+        let mut scene = TitleScene::new(config());
+        let before = menu_position(&mut scene);
+
+        scene
+            .process_input(&Event::Key(KeyEvent::new(
+                KeyCode::Down,
+                KeyModifiers::NONE,
+            )))
+            .unwrap();
+        scene.resize(120, 40).unwrap();
+
+        assert_eq!(menu_position(&mut scene), before + Point::new(20usize, 4));
+        assert_eq!(scene.menu.selected_index(), 1);
+    }
+
+    #[test]
+    fn it_asks_for_a_bigger_terminal_instead_of_panicking() {
+        let small = GameConfig {
+            rows: 10,
+            columns: 20,
+            ..config()
+        };
+
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&small, &input);
+        game_loop
+            .register_scene(TitleScene::new(small))
+            .load_scene::<TitleScene>();
+
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains(TOO_SMALL_TEXT));
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("20x10, NEEDS 44x30")
+        );
+
+        input.push(Event::Resize(config().columns as u16, config().rows as u16));
+        game_loop.step(1).unwrap();
+        assert!(!game_loop.renderer().backend().contains(TOO_SMALL_TEXT));
+        assert!(game_loop.renderer().backend().contains("NEW GAME"));
+    }
+
+    #[test]
+    fn it_plays_a_demo_when_left_idle() {
+        let input = ScriptedInput::new();
+        let mut game_loop = headless_loop(&config(), &input);
+
+        game_loop
+            .register_scene(TitleScene::new(config()))
+            .load_scene::<TitleScene>();
+
+        let frames = ATTRACT_DELAY.as_secs() as usize * config().frame_rate as usize;
+        game_loop.step(frames - 1).unwrap();
+        assert!(!game_loop.renderer().backend().contains(DEMO_TEXT));
+
+        game_loop.step(30).unwrap();
+        assert!(game_loop.renderer().backend().contains(DEMO_TEXT));
+        assert!(game_loop.renderer().backend().contains("Score: "));
+        assert!(!game_loop.renderer().backend().contains("NEW GAME"));
+
+        input.push_key(KeyCode::Enter);
+        game_loop.step(1).unwrap();
+        assert!(game_loop.renderer().backend().contains("NEW GAME"));
+        assert!(!game_loop.renderer().backend().contains(DEMO_TEXT));
+    }
+}
@@ -0,0 +1,779 @@
+use anyhow::Result;
+use crossterm::{
+    event::{Event, KeyCode},
+    style::Color,
+};
+use std::{any::TypeId, cell::RefCell, fmt::Display, iter, rc::Rc, time::Duration};
+
+use crate::{
+    GameConfig, PlayerInput, SnakeStyle,
+    ai::{Autopilot, Difficulty},
+    config::SnakeConfig,
+    engine::{
+        game_loop::GameLoopSignal,
+        point::Point,
+        renderer::DrawInstruction,
+        timestep::Timestep,
+        traits::{Entity, GameScene},
+    },
+    entities::{arena::Arena, menu::Menu, score::Score, text::Text},
+    net::{Phase, PlayerSnapshot, Snapshot},
+    spectators::Spectators,
+};
+
+use super::title::TitleScene;
+
+/// The style and color of each player, in the order they're spawned.
+pub const PLAYER_COLORS: [(SnakeStyle, Color); 4] = [
+    (SnakeStyle::Green, Color::Green),
+    (SnakeStyle::Blue, Color::Blue),
+    (SnakeStyle::Yellow, Color::Yellow),
+    (SnakeStyle::Magenta, Color::Magenta),
+];
+
+const CPU_NAMES: [&str; MAX_OPPONENTS] = ["CPU1", "CPU2", "CPU3"];
+
+/// The most computer controlled snakes a match can have, one for each spawn
+/// point left after the player's.
+pub const MAX_OPPONENTS: usize = 3;
+
+/// Rounds a player needs to win to take the match.
+const ROUNDS_TO_WIN: u32 = 3;
+
+const DRAW_TEXT: &str = "DRAW";
+const PAUSE_TEXT: &str = "PAUSED";
+const NEXT_ROUND_TEXT: &str = "PRESS ENTER FOR THE NEXT ROUND";
+const RESULTS_TITLE: &str = "RESULTS";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum VersusState {
+    Playing,
+    Paused,
+    RoundOver,
+    MatchOver,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum MatchOverOption {
+    Rematch,
+    MainMenu,
+}
+
+impl MatchOverOption {
+    const ALL: [Self; 2] = [Self::Rematch, Self::MainMenu];
+
+    pub fn perform_action(&self) -> GameLoopSignal {
+        match self {
+            Self::Rematch => GameLoopSignal::load_scene::<VersusScene>(),
+            Self::MainMenu => GameLoopSignal::load_scene::<TitleScene>(),
+        }
+    }
+}
+
+impl Display for MatchOverOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rematch => write!(f, "    REMATCH     "),
+            Self::MainMenu => write!(f, "   MAIN MENU    "),
+        }
+    }
+}
+
+/// One side of the match. The score counts food eaten over every round.
+#[derive(Debug)]
+struct Player {
+    name: &'static str,
+    autopilot: Option<Autopilot>,
+    score: Score,
+    rounds: u32,
+    longest: usize,
+    crashes: u32,
+}
+
+/// Two players share one keyboard and one world, WASD steering the first
+/// snake and the arrow keys the second. A snake that crashes into a wall,
+/// itself or another snake is out until the next round, the last one left
+/// wins the round, and the first player to win
+/// `ROUNDS_TO_WIN` rounds takes the match. Computer controlled snakes can take
+/// the place of the second player.
+#[derive(Debug)]
+pub struct VersusScene {
+    config: GameConfig,
+    arena: Arena,
+    players: Vec<Player>,
+    round: u32,
+    state: VersusState,
+    /// The player that won the last round, if it wasn't a draw.
+    winner: Option<usize>,
+    tick: u64,
+    spectators: Option<Rc<RefCell<Spectators>>>,
+    spectators_text: Text,
+    round_text: Text,
+    state_text: Text,
+    prompt_text: Text,
+    results_text: Text,
+    match_over_menu: Menu,
+}
+
+impl VersusScene {
+    pub fn new(config: GameConfig) -> Self {
+        Self::with_players(config, [("P1", None), ("P2", None)])
+    }
+
+    /// A match between the player, who can steer with either WASD or the
+    /// arrow keys, and up to `MAX_OPPONENTS` computer controlled snakes.
+    pub fn against_ai(config: GameConfig, opponents: usize, difficulty: Difficulty) -> Self {
+        let opponents = CPU_NAMES
+            .iter()
+            .take(opponents.clamp(1, MAX_OPPONENTS))
+            .map(|&name| (name, Some(Autopilot::new(difficulty))));
+
+        Self::with_players(config, iter::once(("P1", None)).chain(opponents))
+    }
+
+    fn with_players(
+        config: GameConfig,
+        players: impl IntoIterator<Item = (&'static str, Option<Autopilot>)>,
+    ) -> Self {
+        let (players, snake_configs): (Vec<_>, Vec<_>) = players
+            .into_iter()
+            .zip(PLAYER_COLORS)
+            .enumerate()
+            .map(|(index, ((name, autopilot), (style, color)))| {
+                let snake_config = SnakeConfig {
+                    style,
+                    ..config.snake.clone()
+                };
+
+                // Scores go in the corners, leaving room on the right for
+                // them to reach three digits
+                let position = Point::new(
+                    match index % 2 {
+                        0 => 2,
+                        _ => config.columns.saturating_sub(14),
+                    },
+                    match index / 2 {
+                        0 => 0,
+                        _ => config.rows.saturating_sub(1),
+                    },
+                );
+
+                let player = Player {
+                    name,
+                    autopilot,
+                    score: Score::new(position).with_label(name).with_fg(color),
+                    rounds: 0,
+                    longest: 0,
+                    crashes: 0,
+                };
+
+                (player, snake_config)
+            })
+            .unzip();
+
+        let arena = Arena::new(&config, snake_configs);
+        let center = arena.world().get_center_position();
+        let (_, bottom) = arena.world().min_size();
+
+        let mut scene = Self {
+            round: 1,
+            state: VersusState::Playing,
+            winner: None,
+            tick: 0,
+            spectators: None,
+            spectators_text: Text::default()
+                .center((config.columns / 2, bottom.saturating_sub(1)))
+                .with_fg(Color::Yellow)
+                .hide(),
+            round_text: Text::default()
+                .center((config.columns / 2, 0))
+                .with_fg(Color::Yellow)
+                .show(),
+            state_text: Text::default().center(center).hide(),
+            prompt_text: Text::default()
+                .with_value(NEXT_ROUND_TEXT)
+                .center(center + Point::new(0usize, 2))
+                .hide(),
+            results_text: Text::default()
+                .center(center.saturating_sub(&Point::new(0, 6)))
+                .hide(),
+            match_over_menu: Menu::new(MatchOverOption::ALL, center + Point::new(0usize, 3)).hide(),
+            config,
+            arena,
+            players,
+        };
+
+        scene.update_round_text();
+        scene
+    }
+
+    /// Sends every update of the match to the spectators.
+    pub fn with_spectators(mut self, spectators: Rc<RefCell<Spectators>>) -> Self {
+        self.spectators = Some(spectators);
+        self.update_spectators_text();
+        self
+    }
+
+    fn update_spectators_text(&mut self) {
+        let Some(status) = self.spectators.as_ref().and_then(|s| s.borrow().status()) else {
+            return;
+        };
+
+        self.spectators_text.update_value(status);
+        self.spectators_text.visible = true;
+    }
+
+    fn update_round_text(&mut self) {
+        let rounds = match &self.players[..] {
+            [first, second] => format!(
+                "{} {} - {} {}",
+                first.name, first.rounds, second.rounds, second.name
+            ),
+            players => players
+                .iter()
+                .map(|player| format!("{} {}", player.name, player.rounds))
+                .collect::<Vec<_>>()
+                .join("  "),
+        };
+
+        self.round_text
+            .update_value(format!(" ROUND {}  {rounds} ", self.round));
+    }
+
+    fn start_round(&mut self) {
+        self.arena.start_round();
+        self.round += 1;
+        self.state = VersusState::Playing;
+        self.winner = None;
+        self.state_text.visible = false;
+        self.prompt_text.visible = false;
+        self.update_round_text();
+    }
+
+    fn update_round(&mut self, elapsed: &Duration) {
+        let crashed = self.arena.move_snakes(elapsed);
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.longest = player.longest.max(self.arena.snake(index).length());
+        }
+
+        for (index, _) in crashed {
+            self.players[index].crashes += 1;
+        }
+
+        if self.arena.living().count() <= 1 {
+            self.end_round();
+            return;
+        }
+
+        if let Some(index) = self.arena.eat() {
+            self.players[index].score.increment();
+        }
+    }
+
+    /// Lets each computer controlled snake steer for the next tile, after
+    /// every snake has moved.
+    fn update_autopilots(&mut self, elapsed: &Duration) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            let Some(autopilot) = &mut player.autopilot else {
+                continue;
+            };
+
+            let (players, snakes): (Vec<_>, Vec<_>) = self.arena.living().unzip();
+            let input = players
+                .iter()
+                .position(|&other| other == index)
+                .and_then(|you| {
+                    autopilot.update(elapsed, self.arena.world(), you, &snakes, self.arena.food())
+                });
+
+            if let Some(input) = input {
+                self.arena.process_input(&(index, input));
+            }
+        }
+    }
+
+    /// The last snake standing wins the round. If the last snakes crashed on
+    /// the same tick the round is a draw and nobody scores it.
+    fn end_round(&mut self) {
+        self.state = VersusState::RoundOver;
+        self.state_text.visible = true;
+        self.prompt_text.visible = true;
+
+        self.winner = self.arena.living().map(|(index, _)| index).next();
+        let Some(winner) = self.winner else {
+            self.state_text.update_value(DRAW_TEXT);
+            return;
+        };
+
+        let player = &mut self.players[winner];
+        player.rounds += 1;
+        let name = player.name;
+
+        if player.rounds >= ROUNDS_TO_WIN {
+            self.end_match(name);
+        } else {
+            self.state_text
+                .update_value(format!("{name} WINS THE ROUND"));
+        }
+
+        self.update_round_text();
+    }
+
+    fn end_match(&mut self, winner: &str) {
+        self.state = VersusState::MatchOver;
+        self.state_text
+            .update_value(format!("{winner} WINS THE MATCH"));
+        self.prompt_text.visible = false;
+
+        let rows: Vec<_> = self
+            .players
+            .iter()
+            .map(|player| {
+                format!(
+                    "{}  ROUNDS {}  SCORE {:>3}  LONGEST {:>3}  CRASHES {}",
+                    player.name,
+                    player.rounds,
+                    player.score.value(),
+                    player.longest,
+                    player.crashes
+                )
+            })
+            .collect();
+
+        self.results_text
+            .update_value(format!("{RESULTS_TITLE}\n\n{}", rows.join("\n")));
+        self.results_text.visible = true;
+        self.match_over_menu.visible = true;
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let playing = matches!(self.state, VersusState::Playing | VersusState::Paused);
+
+        Snapshot {
+            tick: self.tick,
+            round: self.round,
+            phase: match playing {
+                true => Phase::Playing,
+                false => Phase::RoundOver,
+            },
+            winner: self.winner,
+            players: self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(index, player)| PlayerSnapshot {
+                    id: index,
+                    name: player.name.to_string(),
+                    slot: index,
+                    score: player.score.value(),
+                    rounds: player.rounds,
+                    snake: self.arena.bot_snake(index),
+                })
+                .collect(),
+            food: self.arena.food_tile().filter(|_| playing),
+            food_kind: self.arena.food_kind(),
+        }
+    }
+
+    /// WASD steers the first player and the arrow keys the second. When the
+    /// second player is computer controlled, both steer the first.
+    fn map_key(&self, code: KeyCode) -> Option<(usize, PlayerInput)> {
+        let second = match self.players[1].autopilot {
+            Some(_) => 0,
+            None => 1,
+        };
+
+        Some(match code {
+            KeyCode::Char('w') => (0, PlayerInput::Up),
+            KeyCode::Char('a') => (0, PlayerInput::Left),
+            KeyCode::Char('s') => (0, PlayerInput::Down),
+            KeyCode::Char('d') => (0, PlayerInput::Right),
+            KeyCode::Up => (second, PlayerInput::Up),
+            KeyCode::Left => (second, PlayerInput::Left),
+            KeyCode::Down => (second, PlayerInput::Down),
+            KeyCode::Right => (second, PlayerInput::Right),
+            _ => return None,
+        })
+    }
+}
+
+impl GameScene for VersusScene {
+    fn draw(&mut self, _timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        let scores = self.players.iter().flat_map(|player| player.score.draw());
+
+        self.arena
+            .draw()
+            .into_iter()
+            .chain(scores)
+            .chain(self.round_text.draw())
+            .chain(self.spectators_text.draw())
+            .chain(self.results_text.draw())
+            .chain(self.state_text.draw())
+            .chain(self.prompt_text.draw())
+            .chain(self.match_over_menu.draw())
+            .collect()
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        if self.state == VersusState::Playing {
+            self.update_round(elapsed);
+        }
+
+        if self.state == VersusState::Playing {
+            self.update_autopilots(elapsed);
+        }
+
+        self.tick += 1;
+        if let Some(spectators) = &self.spectators {
+            let snapshot = self.snapshot();
+            spectators
+                .borrow_mut()
+                .broadcast(self.arena.world(), &snapshot);
+        }
+
+        self.update_spectators_text();
+        Ok(GameLoopSignal::Run)
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let Event::Key(e) = event else {
+            return Ok(GameLoopSignal::Run);
+        };
+
+        Ok(match (e.code, self.state) {
+            (KeyCode::Char('q'), _) => GameLoopSignal::Stop,
+            (KeyCode::Char('p') | KeyCode::Char(' '), VersusState::Playing) => {
+                self.state = VersusState::Paused;
+                self.state_text.update_value(PAUSE_TEXT);
+                self.state_text.visible = true;
+                GameLoopSignal::Run
+            }
+            (KeyCode::Char('p') | KeyCode::Char(' '), VersusState::Paused) => {
+                self.state = VersusState::Playing;
+                self.state_text.visible = false;
+                GameLoopSignal::Run
+            }
+            (KeyCode::Enter, VersusState::RoundOver) => {
+                self.start_round();
+                GameLoopSignal::Run
+            }
+            (KeyCode::Up | KeyCode::Char('w'), VersusState::MatchOver) => {
+                self.match_over_menu.select_previous();
+                GameLoopSignal::Run
+            }
+            (KeyCode::Down | KeyCode::Char('s'), VersusState::MatchOver) => {
+                self.match_over_menu.select_next();
+                GameLoopSignal::Run
+            }
+            (KeyCode::Enter, VersusState::MatchOver) => {
+                MatchOverOption::ALL[self.match_over_menu.selected_index()].perform_action()
+            }
+            (code, VersusState::Playing) => {
+                if let Some(input) = self.map_key(code) {
+                    self.arena.process_input(&input);
+                }
+
+                GameLoopSignal::Run
+            }
+            _ => GameLoopSignal::Run,
+        })
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        Some(self.config.frame_rate)
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        self.arena.world().min_size()
+    }
+
+    /// Pauses the round so neither player is caught out by the resize.
+    fn resize(&mut self, _columns: usize, _rows: usize) -> Result<()> {
+        if self.state == VersusState::Playing {
+            self.state = VersusState::Paused;
+            self.state_text.update_value(PAUSE_TEXT);
+            self.state_text.visible = true;
+        }
+
+        Ok(())
+    }
+}
+
+/// A versus match against computer controlled snakes. It's registered apart
+/// from `VersusScene` so the title menu can start either kind of match.
+#[derive(Debug)]
+pub struct CpuVersusScene {
+    config: GameConfig,
+    opponents: usize,
+    difficulty: Difficulty,
+    spectators: Option<Rc<RefCell<Spectators>>>,
+    scene: VersusScene,
+}
+
+impl CpuVersusScene {
+    pub fn new(config: GameConfig, opponents: usize, difficulty: Difficulty) -> Self {
+        Self {
+            scene: VersusScene::against_ai(config.clone(), opponents, difficulty),
+            config,
+            opponents,
+            difficulty,
+            spectators: None,
+        }
+    }
+
+    /// Sends every update of the match, and of any rematch, to the
+    /// spectators.
+    pub fn with_spectators(mut self, spectators: Rc<RefCell<Spectators>>) -> Self {
+        self.scene = self.scene.with_spectators(spectators.clone());
+        self.spectators = Some(spectators);
+        self
+    }
+
+    /// A rematch loads `VersusScene`, which would be against another player,
+    /// so the match is restarted here instead.
+    fn forward_signal(&mut self, signal: GameLoopSignal) -> GameLoopSignal {
+        match signal {
+            GameLoopSignal::Load(id) if id == TypeId::of::<VersusScene>() => {
+                let scene =
+                    VersusScene::against_ai(self.config.clone(), self.opponents, self.difficulty);
+                self.scene = match &self.spectators {
+                    Some(spectators) => scene.with_spectators(spectators.clone()),
+                    None => scene,
+                };
+                GameLoopSignal::Run
+            }
+            signal => signal,
+        }
+    }
+}
+
+impl GameScene for CpuVersusScene {
+    fn draw(&mut self, timestep: &Timestep) -> Vec<DrawInstruction<'_>> {
+        self.scene.draw(timestep)
+    }
+
+    fn update(&mut self, elapsed: &Duration) -> Result<GameLoopSignal> {
+        let signal = self.scene.update(elapsed)?;
+        Ok(self.forward_signal(signal))
+    }
+
+    fn process_input(&mut self, event: &Event) -> Result<GameLoopSignal> {
+        let signal = self.scene.process_input(event)?;
+        Ok(self.forward_signal(signal))
+    }
+
+    fn frame_rate(&self) -> Option<u8> {
+        self.scene.frame_rate()
+    }
+
+    fn min_size(&self) -> (usize, usize) {
+        self.scene.min_size()
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> Result<()> {
+        self.scene.resize(columns, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{backend::MemoryBackend, game_loop::GameLoop, input::ScriptedInput},
+        net::{ClientMessage, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, Peer, ServerMessage},
+        test_support::{headless_loop, test_config},
+    };
+    use std::thread;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            rows: 32,
+            columns: 50,
+            ..test_config()
+        }
+    }
+
+    fn create_game_loop(input: &ScriptedInput, level: Option<&str>) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        let versus = GameConfig {
+            level: level.map(|level| level.parse().unwrap()),
+            ..config()
+        };
+
+        game_loop
+            .register_scene(TitleScene::new(config()))
+            .register_scene_factory(move || VersusScene::new(versus.clone()))
+            .load_scene::<VersusScene>();
+
+        game_loop
+    }
+
+    #[test]
+    fn it_draws_the_round_when_heads_collide() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, Some("---\n..S......"));
+
+        game_loop.step(1).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("ROUND 1  P1 0 - 0 P2")
+        );
+
+        game_loop.step(3).unwrap();
+        assert!(game_loop.renderer().backend().contains(DRAW_TEXT));
+        assert!(game_loop.renderer().backend().contains(NEXT_ROUND_TEXT));
+    }
+
+    #[test]
+    fn it_gives_the_round_to_the_snake_still_standing() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, None);
+
+        // Both snakes head for a wall and only the second player turns away
+        input.push_key(KeyCode::Up);
+        game_loop.step(30).unwrap();
+
+        assert!(game_loop.renderer().backend().contains("P2 WINS THE ROUND"));
+        assert!(game_loop.renderer().backend().contains("P1 0 - 1 P2"));
+    }
+
+    #[test]
+    fn it_shows_the_results_once_a_player_wins_the_match() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_game_loop(&input, Some("---\n.........\n..S......\n........."));
+
+        // The second player steers off the top of the level every round
+        for round in 1..=ROUNDS_TO_WIN {
+            if round > 1 {
+                input.push_key(KeyCode::Enter);
+                game_loop.step(1).unwrap();
+            }
+
+            input.push_key(KeyCode::Up);
+            game_loop.step(3).unwrap();
+            assert!(
+                game_loop
+                    .renderer()
+                    .backend()
+                    .contains(&format!("P1 {round} - 0 P2"))
+            );
+        }
+
+        assert!(game_loop.renderer().backend().contains("P1 WINS THE MATCH"));
+        assert!(game_loop.renderer().backend().contains(RESULTS_TITLE));
+        assert!(game_loop.renderer().backend().contains("CRASHES 3"));
+
+        input.push_key(KeyCode::Down);
+        game_loop.step(1).unwrap();
+        input.push_key(KeyCode::Enter);
+        game_loop.step(2).unwrap();
+
+        assert!(game_loop.renderer().backend().contains("NEW GAME"));
+    }
+
+    fn create_cpu_game_loop(input: &ScriptedInput, opponents: usize) -> GameLoop<MemoryBackend> {
+        let mut game_loop = headless_loop(&config(), input);
+
+        game_loop
+            .register_scene(CpuVersusScene::new(config(), opponents, Difficulty::Hard))
+            .load_scene::<CpuVersusScene>();
+
+        game_loop
+    }
+
+    #[test]
+    fn it_plays_against_computer_controlled_snakes() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_cpu_game_loop(&input, 1);
+
+        // The player drives into the wall while the computer steers clear
+        game_loop.step(30).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("CPU1 WINS THE ROUND")
+        );
+        assert!(game_loop.renderer().backend().contains("P1 0 - 1 CPU1"));
+    }
+
+    #[test]
+    fn it_keeps_the_round_going_until_one_snake_is_left() {
+        let input = ScriptedInput::new();
+        let mut game_loop = create_cpu_game_loop(&input, 2);
+
+        game_loop.step(1).unwrap();
+        assert!(
+            game_loop
+                .renderer()
+                .backend()
+                .contains("P1 0  CPU1 0  CPU2 0")
+        );
+
+        game_loop.step(30).unwrap();
+        assert!(!game_loop.renderer().backend().contains(DRAW_TEXT));
+        assert!(!game_loop.renderer().backend().contains("WINS THE ROUND"));
+    }
+
+    #[test]
+    fn it_steers_the_player_with_the_arrows_against_the_computer() {
+        let mut scene = VersusScene::against_ai(config(), 1, Difficulty::Easy);
+        assert_eq!(scene.map_key(KeyCode::Up), Some((0, PlayerInput::Up)));
+        assert_eq!(
+            scene.map_key(KeyCode::Char('a')),
+            Some((0, PlayerInput::Left))
+        );
+
+        scene = VersusScene::new(config());
+        assert_eq!(scene.map_key(KeyCode::Up), Some((1, PlayerInput::Up)));
+    }
+
+    #[test]
+    fn it_streams_the_match_to_spectators() {
+        let scene = VersusScene::new(config());
+        let spectators = Spectators::listen(0, scene.arena.world()).unwrap();
+        let address = format!("127.0.0.1:{}", spectators.port().unwrap());
+        let spectators = Rc::new(RefCell::new(spectators));
+
+        let mut game_loop = headless_loop(&config(), &ScriptedInput::new());
+
+        game_loop
+            .on_update({
+                let spectators = spectators.clone();
+                move |elapsed| spectators.borrow_mut().update(elapsed)
+            })
+            .register_scene(scene.with_spectators(spectators.clone()))
+            .load_scene::<VersusScene>();
+
+        let mut peer = Peer::<ServerMessage>::connect(&address).unwrap();
+        peer.send(&ClientMessage::Watch {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+        })
+        .unwrap();
+
+        while spectators.borrow().count() == 0 {
+            game_loop.step(1).unwrap();
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        // Spectators are greeted after the match updates, so they're sent
+        // their first snapshot on the next one
+        game_loop.step(1).unwrap();
+        assert!(matches!(
+            peer.wait().unwrap(),
+            ServerMessage::Welcome { .. }
+        ));
+        let ServerMessage::Snapshot(snapshot) = peer.wait().unwrap() else {
+            panic!("Expected a snapshot");
+        };
+
+        assert_eq!(snapshot.round, 1);
+        assert_eq!(snapshot.phase, Phase::Playing);
+        assert_eq!(snapshot.players[1].name, "P2");
+        assert!(snapshot.players.iter().all(|player| player.snake.is_some()));
+    }
+}
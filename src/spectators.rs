@@ -0,0 +1,390 @@
+use anyhow::{Result, anyhow, bail};
+use std::{
+    net::{Shutdown, TcpStream},
+    sync::mpsc::Receiver,
+    time::Duration,
+};
+
+use crate::{
+    entities::world::World,
+    level::Level,
+    net::{self, ClientMessage, Peer, ServerMessage, Snapshot, negotiate},
+};
+
+/// The most spectators that can watch one game at a time.
+pub const MAX_SPECTATORS: usize = 16;
+
+const WATCH_ONLY: &str = "This game can only be watched";
+
+#[derive(Debug)]
+struct Spectator {
+    id: usize,
+    peer: Peer<ClientMessage>,
+    /// False until the spectator's request to watch has been accepted.
+    watching: bool,
+    /// Why the spectator is being disconnected at the end of the update.
+    leaving: Option<String>,
+}
+
+/// People watching a game without playing in it. They're sent the same
+/// snapshots the game is drawn from, and nothing they send can change it.
+#[derive(Debug)]
+pub struct Spectators {
+    connections: Option<Receiver<TcpStream>>,
+    port: Option<u16>,
+    level: Level,
+    wrap: bool,
+    spectators: Vec<Spectator>,
+    next_id: usize,
+}
+
+impl Spectators {
+    /// Spectators handed over by a server that accepts its own connections,
+    /// watching the world's level.
+    pub fn new(world: &World) -> Self {
+        Self {
+            connections: None,
+            port: None,
+            level: world.level().clone(),
+            wrap: world.wraps(),
+            spectators: vec![],
+            next_id: 1,
+        }
+    }
+
+    /// Listens for spectators on `port`, or on any free port if it's 0, and
+    /// turns away anyone asking to play.
+    pub fn listen(port: u16, world: &World) -> Result<Self> {
+        let (connections, port) = net::listen(port)?;
+
+        Ok(Self {
+            connections: Some(connections),
+            port: Some(port),
+            ..Self::new(world)
+        })
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// The port to watch on and how many are watching, for the game's HUD,
+    /// or `None` if the spectators were handed over by a server.
+    pub fn status(&self) -> Option<String> {
+        let port = self.port()?;
+        Some(match self.count() {
+            0 => format!(" SPECTATE ON PORT {port} "),
+            watching => format!(" SPECTATE ON PORT {port}  {watching} WATCHING "),
+        })
+    }
+
+    /// How many spectators are watching.
+    pub fn count(&self) -> usize {
+        self.spectators.iter().filter(|s| s.watching).count()
+    }
+
+    /// Takes over a connection whose first message asked to watch.
+    pub fn add(&mut self, peer: Peer<ClientMessage>, min_version: u32, max_version: u32) {
+        let index = self.push(peer);
+        if let Err(error) = self.welcome(index, min_version, max_version) {
+            self.spectators[index].leaving = Some(error.to_string());
+        }
+    }
+
+    /// Greets new spectators and lets go of the ones that have left, gone
+    /// quiet or fallen behind.
+    pub fn update(&mut self, elapsed: &Duration) {
+        while let Some(stream) = self
+            .connections
+            .as_ref()
+            .and_then(|connections| connections.try_recv().ok())
+        {
+            let pending = self.spectators.iter().filter(|s| !s.watching).count();
+            if pending >= net::MAX_PENDING {
+                let _ = stream.shutdown(Shutdown::Both);
+            } else if let Ok(peer) = Peer::new(stream) {
+                self.push(peer);
+            }
+        }
+
+        for index in 0..self.spectators.len() {
+            let messages = match self.spectators[index].peer.receive(elapsed) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    self.spectators[index].leaving = Some(error.to_string());
+                    continue;
+                }
+            };
+
+            for message in messages {
+                if let Err(error) = self.handle_message(index, message) {
+                    self.spectators[index].leaving = Some(error.to_string());
+                    break;
+                }
+            }
+        }
+
+        self.disconnect_leaving();
+    }
+
+    /// Sends every spectator the snapshot, first telling them about the
+    /// world's level if it's changed since the last one.
+    pub fn broadcast(&mut self, world: &World, snapshot: &Snapshot) {
+        if world.level() != &self.level || world.wraps() != self.wrap {
+            self.level = world.level().clone();
+            self.wrap = world.wraps();
+            self.send(&ServerMessage::Level {
+                level: self.level.clone(),
+                wrap: self.wrap,
+            });
+        }
+
+        self.send(&ServerMessage::Snapshot(snapshot.clone()));
+    }
+
+    /// Says goodbye to every spectator.
+    pub fn close(&mut self, reason: &str) {
+        for spectator in &mut self.spectators {
+            spectator.leaving = Some(reason.to_string());
+        }
+
+        self.disconnect_leaving();
+    }
+
+    fn push(&mut self, peer: Peer<ClientMessage>) -> usize {
+        self.spectators.push(Spectator {
+            id: self.next_id,
+            peer,
+            watching: false,
+            leaving: None,
+        });
+
+        self.next_id += 1;
+        self.spectators.len() - 1
+    }
+
+    fn handle_message(&mut self, index: usize, message: ClientMessage) -> Result<()> {
+        let watching = self.spectators[index].watching;
+
+        match message {
+            ClientMessage::Watch {
+                min_version,
+                max_version,
+            } if !watching => self.welcome(index, min_version, max_version),
+            ClientMessage::Hello { .. } if !watching => {
+                self.spectators[index].peer.send(&ServerMessage::Rejected {
+                    reason: WATCH_ONLY.to_string(),
+                })?;
+                bail!(WATCH_ONLY)
+            }
+            _ if !watching => bail!("Didn't say hello first"),
+            ClientMessage::Bye => bail!("Left the game"),
+            // Spectators can't steer, so anything else they send is ignored
+            _ => Ok(()),
+        }
+    }
+
+    /// Lets the spectator watch, as long as there's room and it speaks a
+    /// protocol version the game does.
+    fn welcome(&mut self, index: usize, min_version: u32, max_version: u32) -> Result<()> {
+        let room = self.count() < MAX_SPECTATORS;
+        let accepted = negotiate(min_version, max_version).and_then(|version| match room {
+            true => Ok(version),
+            false => Err(anyhow!("Too many spectators")),
+        });
+
+        let spectator = &mut self.spectators[index];
+        let version = match accepted {
+            Ok(version) => version,
+            Err(error) => {
+                spectator.peer.send(&ServerMessage::Rejected {
+                    reason: error.to_string(),
+                })?;
+                return Err(error);
+            }
+        };
+
+        spectator.watching = true;
+        spectator.peer.send(&ServerMessage::Welcome {
+            version,
+            id: spectator.id,
+            level: self.level.clone(),
+            wrap: self.wrap,
+        })
+    }
+
+    fn send(&mut self, message: &ServerMessage) {
+        for spectator in self.spectators.iter_mut().filter(|s| s.watching) {
+            if let Err(error) = spectator.peer.send(message) {
+                spectator.leaving = Some(error.to_string());
+            }
+        }
+    }
+
+    fn disconnect_leaving(&mut self) {
+        let (leaving, staying) = std::mem::take(&mut self.spectators)
+            .into_iter()
+            .partition(|spectator| spectator.leaving.is_some());
+        self.spectators = staying;
+
+        for spectator in leaving.into_iter().filter(|s| s.watching) {
+            let reason = spectator.leaving.unwrap_or_default();
+            let _ = spectator.peer.send(&ServerMessage::Bye { reason });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        GameConfig, PlayerInput,
+        engine::point::Point,
+        net::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION},
+        test_support::test_config,
+    };
+    use std::thread;
+
+    fn watch(spectators: &Spectators, message: &ClientMessage) -> Peer<ServerMessage> {
+        let address = format!("127.0.0.1:{}", spectators.port().unwrap());
+        let peer = Peer::connect(&address).unwrap();
+        peer.send(message).unwrap();
+        peer
+    }
+
+    /// Updates the spectators until `done` is true, giving the peers' threads
+    /// time to catch up.
+    fn update_until(spectators: &mut Spectators, done: impl Fn(&Spectators) -> bool) {
+        for _ in 0..500 {
+            spectators.update(&Duration::from_millis(1));
+            if done(spectators) {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(2));
+        }
+
+        panic!("The spectators never got there");
+    }
+
+    const WATCH: ClientMessage = ClientMessage::Watch {
+        min_version: MIN_PROTOCOL_VERSION,
+        max_version: PROTOCOL_VERSION,
+    };
+
+    #[test]
+    fn it_sends_spectators_every_snapshot() {
+        let world = World::new(&test_config(), Point::new(0, 0));
+        let mut spectators = Spectators::listen(0, &world).unwrap();
+        let mut peer = watch(&spectators, &WATCH);
+
+        update_until(&mut spectators, |spectators| spectators.count() == 1);
+        assert!(matches!(
+            peer.wait().unwrap(),
+            ServerMessage::Welcome { version: 1, .. }
+        ));
+
+        let snapshot = Snapshot {
+            tick: 7,
+            ..Snapshot::default()
+        };
+        spectators.broadcast(&world, &snapshot);
+        assert_eq!(peer.wait().unwrap(), ServerMessage::Snapshot(snapshot));
+    }
+
+    #[test]
+    fn it_shows_its_port_and_how_many_are_watching() {
+        let world = World::new(&test_config(), Point::new(0, 0));
+        let mut spectators = Spectators::listen(0, &world).unwrap();
+        let port = spectators.port().unwrap();
+        assert_eq!(
+            spectators.status(),
+            Some(format!(" SPECTATE ON PORT {port} "))
+        );
+
+        let _peer = watch(&spectators, &WATCH);
+        update_until(&mut spectators, |spectators| spectators.count() == 1);
+        assert_eq!(
+            spectators.status(),
+            Some(format!(" SPECTATE ON PORT {port}  1 WATCHING "))
+        );
+
+        assert_eq!(Spectators::new(&world).status(), None);
+    }
+
+    #[test]
+    fn it_ignores_inputs_from_spectators() {
+        let world = World::new(&test_config(), Point::new(0, 0));
+        let mut spectators = Spectators::listen(0, &world).unwrap();
+        let peer = watch(&spectators, &WATCH);
+
+        update_until(&mut spectators, |spectators| spectators.count() == 1);
+        peer.send(&ClientMessage::Input {
+            input: PlayerInput::Up,
+        })
+        .unwrap();
+
+        for _ in 0..10 {
+            spectators.update(&Duration::from_millis(1));
+            thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(spectators.count(), 1);
+
+        peer.send(&ClientMessage::Bye).unwrap();
+        update_until(&mut spectators, |spectators| spectators.count() == 0);
+    }
+
+    #[test]
+    fn it_turns_away_players() {
+        let world = World::new(&test_config(), Point::new(0, 0));
+        let mut spectators = Spectators::listen(0, &world).unwrap();
+        let mut peer = watch(
+            &spectators,
+            &ClientMessage::Hello {
+                min_version: MIN_PROTOCOL_VERSION,
+                max_version: PROTOCOL_VERSION,
+                name: None,
+            },
+        );
+
+        // The reply only arrives once an update has read the hello
+        let reply = (0..500).find_map(|_| {
+            spectators.update(&Duration::from_millis(1));
+            thread::sleep(Duration::from_millis(2));
+            peer.receive(&Duration::ZERO).ok()?.into_iter().next()
+        });
+
+        assert_eq!(
+            reply,
+            Some(ServerMessage::Rejected {
+                reason: WATCH_ONLY.to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn it_tells_spectators_when_the_level_changes() {
+        let world = World::new(&test_config(), Point::new(0, 0));
+        let mut spectators = Spectators::listen(0, &world).unwrap();
+        let mut peer = watch(&spectators, &WATCH);
+
+        update_until(&mut spectators, |spectators| spectators.count() == 1);
+        peer.wait().unwrap();
+
+        let level = Level::builtin("pillars").unwrap();
+        let pillars = World::new(
+            &GameConfig {
+                level: Some(level.clone()),
+                ..test_config()
+            },
+            Point::new(0, 0),
+        );
+
+        spectators.broadcast(&pillars, &Snapshot::default());
+        assert_eq!(
+            peer.wait().unwrap(),
+            ServerMessage::Level { level, wrap: false }
+        );
+        assert!(matches!(peer.wait().unwrap(), ServerMessage::Snapshot(_)));
+    }
+}
@@ -0,0 +1,51 @@
+//! Setup shared by the binary's tests.
+
+use std::time::Duration;
+
+use crate::{
+    GameConfig, GameMode, SnakeStyle,
+    config::SnakeConfig,
+    engine::{
+        backend::MemoryBackend,
+        game_loop::{GameLoop, GameLoopConfig},
+        input::ScriptedInput,
+        renderer::Renderer,
+    },
+};
+
+/// A walled 80x24 game with a short snake and a fixed seed. Tests change
+/// what they need with struct update syntax.
+pub fn test_config() -> GameConfig {
+    GameConfig {
+        snake: SnakeConfig {
+            speed: 15.0,
+            grow_rate: 1,
+            size: 3,
+            turn_queue_depth: 3,
+            style: SnakeStyle::Green,
+        },
+        rows: 24,
+        columns: 80,
+        frame_rate: 15,
+        mode: GameMode::Walls,
+        show_frame_rate: false,
+        show_border: true,
+        seed: Some(1),
+        level: None,
+        food: None,
+        curve: None,
+    }
+}
+
+/// A game loop that draws into memory at `config`'s size and frame rate and
+/// reads its events from `input`. Scenes still need registering and loading.
+pub fn headless_loop(config: &GameConfig, input: &ScriptedInput) -> GameLoop<MemoryBackend> {
+    GameLoop::headless(
+        Renderer::new(MemoryBackend::new(), config.rows, config.columns),
+        GameLoopConfig {
+            frame_rate: config.frame_rate,
+            input_poll_rate: Duration::from_millis(0),
+        },
+        input.clone(),
+    )
+}
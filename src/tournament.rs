@@ -0,0 +1,572 @@
+use anyhow::{Result, anyhow, bail};
+use clap::ValueEnum;
+use std::{
+    fmt::{Display, Write},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    GameConfig,
+    ai::{Autopilot, Difficulty},
+    bot::{Bot, DEFAULT_BOT_TIMEOUT},
+    engine::traits::Entity,
+    entities::arena::{Arena, Crash},
+    scenes::versus::MAX_OPPONENTS,
+};
+
+/// The most players a game can have, one for each spawn point.
+pub const MAX_PLAYERS: usize = MAX_OPPONENTS + 1;
+
+/// The terminal size games are played as if they had, in columns and rows,
+/// so results don't depend on the terminal the tournament is run in.
+pub const TOURNAMENT_SIZE: (u16, u16) = (80, 24);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entrant {
+    /// Heads for the food by the shortest distance, ignoring what's in the
+    /// way until it's about to hit it.
+    Greedy,
+    /// Finds a path to the food and follows its tail when there isn't a safe
+    /// one, like the hard computer opponents.
+    Pathfinding,
+    /// A command started with the system shell, like `--bot`.
+    Bot(String),
+}
+
+impl FromStr for Entrant {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value.trim() {
+            "" => bail!("player must be greedy, pathfinding or a bot command"),
+            "greedy" => Self::Greedy,
+            "pathfinding" => Self::Pathfinding,
+            command => Self::Bot(command.to_string()),
+        })
+    }
+}
+
+impl Display for Entrant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greedy => write!(f, "greedy"),
+            Self::Pathfinding => write!(f, "pathfinding"),
+            Self::Bot(command) => write!(f, "{command}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum, Eq, PartialEq)]
+pub enum ResultsFormat {
+    #[default]
+    Text,
+    Csv,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct TournamentOptions {
+    #[arg(
+        long = "player",
+        value_name = "PLAYER",
+        required = true,
+        help = "Add a player: greedy, pathfinding or a bot command. Give 2 to 4 players"
+    )]
+    pub players: Vec<Entrant>,
+
+    #[arg(long, default_value_t = 100, help = "Set how many games to play")]
+    pub games: usize,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Seed the first game, each game after it using the next seed"
+    )]
+    pub seed: u64,
+
+    #[arg(
+        long,
+        help = "Set how many games are played at once [default: one per CPU]"
+    )]
+    pub threads: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = 10_000,
+        help = "End games that are still going after this many updates"
+    )]
+    pub max_ticks: u64,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Set how long bots have to reply to each move [default: 100]"
+    )]
+    pub bot_timeout: Option<u64>,
+
+    #[arg(
+        value_enum,
+        long,
+        default_value_t,
+        help = "Set how results are printed"
+    )]
+    pub format: ResultsFormat,
+}
+
+/// Why a player's snake was taken out of a game.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Out {
+    Crash(Crash),
+    BotError,
+}
+
+/// How one player did in one game.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct PlayerResult {
+    score: u32,
+    length: usize,
+    out: Option<Out>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GameResult {
+    /// The index of the winning entrant, or nothing for a draw.
+    winner: Option<usize>,
+    /// Results in the order entrants were given.
+    players: Vec<PlayerResult>,
+}
+
+/// One player's totals over every game of the tournament.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Standing {
+    pub player: String,
+    pub games: u32,
+    pub wins: u32,
+    pub total_score: u64,
+    pub total_length: u64,
+    pub wall: u32,
+    pub itself: u32,
+    pub snake: u32,
+    pub head_on: u32,
+    pub bot_error: u32,
+}
+
+impl Standing {
+    pub fn average_score(&self) -> f64 {
+        self.total_score as f64 / f64::from(self.games.max(1))
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.total_length as f64 / f64::from(self.games.max(1))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Standings {
+    pub players: Vec<Standing>,
+    pub games: u32,
+    pub draws: u32,
+}
+
+impl Standings {
+    pub fn to_text(&self) -> String {
+        let width = self
+            .players
+            .iter()
+            .map(|standing| standing.player.chars().count())
+            .chain([6])
+            .max()
+            .unwrap_or_default();
+
+        let mut text = format!(
+            "{:<width$}  GAMES   WINS  AVG SCORE  AVG LENGTH   WALL   SELF  SNAKE  HEAD-ON  BOT ERROR\n",
+            "PLAYER"
+        );
+
+        for standing in &self.players {
+            let _ = writeln!(
+                text,
+                "{:<width$}  {:>5}  {:>5}  {:>9.2}  {:>10.2}  {:>5}  {:>5}  {:>5}  {:>7}  {:>9}",
+                standing.player,
+                standing.games,
+                standing.wins,
+                standing.average_score(),
+                standing.average_length(),
+                standing.wall,
+                standing.itself,
+                standing.snake,
+                standing.head_on,
+                standing.bot_error,
+            );
+        }
+
+        let _ = writeln!(text, "\n{} games played, {} drawn", self.games, self.draws);
+        text
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "player,games,wins,average_score,average_length,wall,self,snake,head_on,bot_error\n",
+        );
+
+        for standing in &self.players {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{:.2},{:.2},{},{},{},{},{}",
+                csv_field(&standing.player),
+                standing.games,
+                standing.wins,
+                standing.average_score(),
+                standing.average_length(),
+                standing.wall,
+                standing.itself,
+                standing.snake,
+                standing.head_on,
+                standing.bot_error,
+            );
+        }
+
+        csv
+    }
+
+    fn add(&mut self, game: &GameResult) {
+        self.games += 1;
+        if game.winner.is_none() {
+            self.draws += 1;
+        }
+
+        for (index, (standing, result)) in self.players.iter_mut().zip(&game.players).enumerate() {
+            standing.games += 1;
+            standing.wins += u32::from(game.winner == Some(index));
+            standing.total_score += u64::from(result.score);
+            standing.total_length += result.length as u64;
+
+            let count = match result.out {
+                Some(Out::Crash(Crash::Wall)) => &mut standing.wall,
+                Some(Out::Crash(Crash::Itself)) => &mut standing.itself,
+                Some(Out::Crash(Crash::Snake)) => &mut standing.snake,
+                Some(Out::Crash(Crash::HeadOn)) => &mut standing.head_on,
+                Some(Out::BotError) => &mut standing.bot_error,
+                None => continue,
+            };
+
+            *count += 1;
+        }
+    }
+}
+
+/// Quotes a CSV field if it has anything in it that needs quoting.
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+/// Plays every game of the tournament and prints the results.
+pub fn run(config: &GameConfig, options: &TournamentOptions) -> Result<()> {
+    let standings = play(config, options)?;
+
+    match options.format {
+        ResultsFormat::Text => print!("{}", standings.to_text()),
+        ResultsFormat::Csv => print!("{}", standings.to_csv()),
+    }
+
+    Ok(())
+}
+
+/// Plays every game of the tournament, spread across threads. Games are
+/// seeded from the options' seed in order, so the results are the same
+/// however many threads there are. Games follow versus rules, so power-ups
+/// and speed curves are errors rather than being left out quietly.
+pub fn play(config: &GameConfig, options: &TournamentOptions) -> Result<Standings> {
+    let players = options.players.len();
+    if !(2..=MAX_PLAYERS).contains(&players) {
+        bail!("A tournament needs 2 to {MAX_PLAYERS} players, got {players}");
+    }
+
+    if config.food.is_some() {
+        bail!("Tournaments are played by versus rules, which don't have power-ups");
+    }
+
+    if config.curve.is_some() {
+        bail!("Tournaments are played by versus rules, which don't have speed curves");
+    }
+
+    let threads = options
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1)
+        .clamp(1, options.games.max(1));
+
+    let next_game = AtomicUsize::new(0);
+    let mut results = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let game = next_game.fetch_add(1, Ordering::Relaxed);
+                        if game >= options.games {
+                            break results;
+                        }
+
+                        results.push((game, play_game(config, options, game)));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .map_err(|_| anyhow!("A tournament thread panicked"))
+            })
+            .collect::<Result<Vec<_>>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    results.sort_by_key(|(game, _)| *game);
+
+    let mut standings = Standings {
+        players: options
+            .players
+            .iter()
+            .map(|player| Standing {
+                player: player.to_string(),
+                ..Standing::default()
+            })
+            .collect(),
+        ..Standings::default()
+    };
+
+    for (_, result) in &results {
+        standings.add(result);
+    }
+
+    Ok(standings)
+}
+
+/// Something steering a snake in a tournament game.
+enum Pilot {
+    Autopilot(Autopilot),
+    Bot(Bot),
+}
+
+impl Pilot {
+    fn new(entrant: &Entrant, bot_timeout: Duration) -> Self {
+        match entrant {
+            Entrant::Greedy => Self::Autopilot(Autopilot::greedy()),
+            Entrant::Pathfinding => Self::Autopilot(Autopilot::new(Difficulty::Hard)),
+            Entrant::Bot(command) => Self::Bot(Bot::new(command.clone(), bot_timeout)),
+        }
+    }
+}
+
+/// Plays one game with the same rules as a versus round: snakes move, the
+/// crashed ones are taken out, the food is eaten and then each snake steers
+/// for its next tile. Players take turns at each spawn point from one game
+/// to the next.
+fn play_game(config: &GameConfig, options: &TournamentOptions, game: usize) -> GameResult {
+    let players = options.players.len();
+    let seats: Vec<_> = (0..players).map(|seat| (seat + game) % players).collect();
+    let bot_timeout = options
+        .bot_timeout
+        .map_or(DEFAULT_BOT_TIMEOUT, Duration::from_millis);
+
+    let config = GameConfig {
+        seed: Some(options.seed.wrapping_add(game as u64)),
+        ..config.clone()
+    };
+
+    let mut arena = Arena::new(&config, vec![config.snake.clone(); players]);
+    let mut pilots: Vec<_> = seats
+        .iter()
+        .map(|&entrant| Pilot::new(&options.players[entrant], bot_timeout))
+        .collect();
+
+    let mut results = vec![PlayerResult::default(); players];
+    let elapsed = Duration::from_secs_f32(1.0 / f32::from(config.frame_rate.max(1)));
+
+    for tick in 0..options.max_ticks {
+        for (seat, crash) in arena.move_snakes(&elapsed) {
+            results[seat].out = Some(Out::Crash(crash));
+        }
+
+        if arena.living().count() <= 1 {
+            break;
+        }
+
+        if let Some(seat) = arena.eat() {
+            results[seat].score += 1;
+        }
+
+        for (seat, pilot) in pilots.iter_mut().enumerate() {
+            let (living, snakes): (Vec<_>, Vec<_>) = arena.living().unzip();
+            let Some(you) = living.iter().position(|&other| other == seat) else {
+                continue;
+            };
+
+            let (world, food) = (arena.world(), arena.food());
+            let input = match pilot {
+                Pilot::Autopilot(autopilot) => {
+                    autopilot.update(&elapsed, world, you, &snakes, food)
+                }
+                Pilot::Bot(bot) => {
+                    match bot.update(world, &snakes, you, food, results[seat].score, tick) {
+                        Ok(input) => input,
+                        Err(_) => {
+                            arena.take_out(seat);
+                            results[seat].out = Some(Out::BotError);
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            if let Some(input) = input {
+                arena.process_input(&(seat, input));
+            }
+        }
+
+        if arena.living().count() <= 1 {
+            break;
+        }
+    }
+
+    for (seat, result) in results.iter_mut().enumerate() {
+        result.length = arena.snake(seat).length();
+    }
+
+    // The last snake left wins. Games that run out of time go to the highest
+    // score still in the game, and a tie is a draw.
+    let living: Vec<_> = (0..players).filter(|&seat| arena.is_alive(seat)).collect();
+    let best = living.iter().map(|&seat| results[seat].score).max();
+    let leaders: Vec<_> = living
+        .into_iter()
+        .filter(|&seat| Some(results[seat].score) == best)
+        .collect();
+
+    let mut ordered = vec![PlayerResult::default(); players];
+    for (seat, result) in results.into_iter().enumerate() {
+        ordered[seats[seat]] = result;
+    }
+
+    GameResult {
+        winner: match leaders[..] {
+            [seat] => Some(seats[seat]),
+            _ => None,
+        },
+        players: ordered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::SnakeConfig, curve::SpeedCurve, entities::food::SpawnTable,
+        test_support::test_config,
+    };
+
+    fn config() -> GameConfig {
+        GameConfig {
+            snake: SnakeConfig {
+                grow_rate: 2,
+                size: 6,
+                ..test_config().snake
+            },
+            show_border: false,
+            seed: None,
+            ..test_config()
+        }
+    }
+
+    fn options(players: &[&str], games: usize, threads: usize) -> TournamentOptions {
+        TournamentOptions {
+            players: players
+                .iter()
+                .map(|player| player.parse().unwrap())
+                .collect(),
+            games,
+            seed: 3,
+            threads: Some(threads),
+            max_ticks: 2_000,
+            bot_timeout: Some(500),
+            format: ResultsFormat::Text,
+        }
+    }
+
+    #[test]
+    fn it_plays_the_same_games_however_many_threads_there_are() {
+        let standings = play(&config(), &options(&["greedy", "pathfinding"], 6, 3)).unwrap();
+        let again = play(&config(), &options(&["greedy", "pathfinding"], 6, 1)).unwrap();
+        assert_eq!(standings, again);
+
+        assert_eq!(standings.games, 6);
+        let wins: u32 = standings.players.iter().map(|standing| standing.wins).sum();
+        assert_eq!(wins + standings.draws, 6);
+        assert!(standings.players.iter().all(|standing| standing.games == 6));
+    }
+
+    #[test]
+    fn it_needs_two_to_four_players() {
+        assert!(play(&config(), &options(&["greedy"], 1, 1)).is_err());
+        assert!(play(&config(), &options(&["greedy"; 5], 1, 1)).is_err());
+    }
+
+    #[test]
+    fn it_rejects_rules_versus_games_dont_have() {
+        let players = options(&["greedy", "greedy"], 1, 1);
+        let power_ups = GameConfig {
+            food: Some(SpawnTable::default()),
+            ..config()
+        };
+        let curve = GameConfig {
+            curve: SpeedCurve::builtin("steep"),
+            ..config()
+        };
+
+        assert!(play(&power_ups, &players).is_err());
+        assert!(play(&curve, &players).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_takes_out_bots_that_fail() {
+        let standings = play(&config(), &options(&["exit 1", "greedy"], 2, 2)).unwrap();
+
+        assert_eq!(standings.players[0].bot_error, 2);
+        assert_eq!(standings.players[1].wins, 2);
+    }
+
+    #[test]
+    fn it_prints_the_results_as_text_and_csv() {
+        let standings = Standings {
+            players: vec![Standing {
+                player: "python3 bot.py, fast".to_string(),
+                games: 4,
+                wins: 3,
+                total_score: 10,
+                total_length: 30,
+                wall: 1,
+                ..Standing::default()
+            }],
+            games: 4,
+            draws: 1,
+        };
+
+        assert_eq!(
+            standings.to_csv().lines().nth(1),
+            Some("\"python3 bot.py, fast\",4,3,2.50,7.50,1,0,0,0,0")
+        );
+        assert!(standings.to_text().contains("4 games played, 1 drawn"));
+        assert!(standings.to_text().contains("      2.50        7.50"));
+    }
+}